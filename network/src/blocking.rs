@@ -0,0 +1,283 @@
+//! Blocking interface to the network handler, for simple tools which don't run an event loop.
+use std::{fmt, thread};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::Async;
+use futures::executor;
+use futures::sync::oneshot;
+
+use screeps_api::{self, RoomName};
+use screeps_api::websocket::RoomMapViewUpdate;
+use websocket;
+
+use code::{ActiveBranch, Branch, CodeModules};
+use event::NetworkEvent;
+use request::{Request, RoomRect};
+use tokio::Handler;
+use {ConnectionSettings, Disconnected, Notify, ScreepsConnection};
+
+/// Default time to wait for any single result.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Error resulting from a blocking call.
+#[derive(Debug)]
+pub enum Error {
+    /// No result was received before the timeout.
+    TimedOut,
+    /// The network handler stopped before a result was received.
+    Disconnected,
+    /// The request was made, but resulted in an error.
+    Api(screeps_api::Error),
+    /// The websocket connection errored.
+    Websocket(websocket::WebSocketError),
+    /// A websocket message could not be parsed.
+    WebsocketParse(screeps_api::websocket::parsing::ParseError),
+    /// The network handler returned an event which did not match the request made.
+    UnexpectedEvent(NetworkEvent),
+}
+
+impl From<screeps_api::Error> for Error {
+    fn from(e: screeps_api::Error) -> Self {
+        Error::Api(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::TimedOut => write!(f, "timed out waiting for a response"),
+            Error::Disconnected => write!(f, "network handler stopped before a response was received"),
+            Error::Api(ref e) => e.fmt(f),
+            Error::Websocket(ref e) => e.fmt(f),
+            Error::WebsocketParse(ref e) => e.fmt(f),
+            Error::UnexpectedEvent(ref e) => write!(f, "unexpected network event received: {:?}", e),
+        }
+    }
+}
+
+/// Notify implementation which unparks the thread currently waiting on a `BlockingClient`.
+#[derive(Clone, Default)]
+pub struct ThreadNotify(Arc<Mutex<Option<thread::Thread>>>);
+
+impl ThreadNotify {
+    fn wait_on_current(&self) {
+        *self.0.lock().expect("expected ThreadNotify lock to not be poisoned") = Some(thread::current());
+    }
+}
+
+impl Notify for ThreadNotify {
+    fn wakeup(&self) -> Result<(), Disconnected> {
+        if let Some(ref thread) = *self.0.lock().expect("expected ThreadNotify lock to not be poisoned") {
+            thread.unpark();
+        }
+        Ok(())
+    }
+}
+
+struct ThreadUnpark(thread::Thread);
+
+impl executor::Notify for ThreadUnpark {
+    fn notify(&self, _: usize) {
+        self.0.unpark();
+    }
+}
+
+/// Waits on a result receiver until the deadline.
+fn wait_for<T>(receiver: oneshot::Receiver<T>, deadline: Instant) -> Result<T, Error> {
+    let unpark = Arc::new(ThreadUnpark(thread::current()));
+    let mut spawned = executor::spawn(receiver);
+
+    loop {
+        match spawned.poll_future_notify(&unpark, 0) {
+            Ok(Async::Ready(value)) => return Ok(value),
+            Ok(Async::NotReady) => (),
+            Err(oneshot::Canceled) => return Err(Error::Disconnected),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::TimedOut);
+        }
+        thread::park_timeout(deadline - now);
+    }
+}
+
+/// Client which makes requests and blocks until their results are available.
+///
+/// This wraps the tokio `Handler`, so it shares the same executors, login token handling and disk cache.
+pub struct BlockingClient {
+    handler: Handler<ThreadNotify>,
+    notify: ThreadNotify,
+    timeout: Duration,
+}
+
+impl BlockingClient {
+    /// Creates a new client with the given settings, and the default timeout.
+    pub fn new(settings: ConnectionSettings) -> Self {
+        let notify = ThreadNotify::default();
+
+        BlockingClient {
+            handler: Handler::new(settings, notify.clone()),
+            notify: notify,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        }
+    }
+
+    /// Gets the time any one call will wait for a result.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets the time any one call will wait for a result.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Makes a request, and waits for the event resulting from it.
+    pub fn request(&mut self, request: Request) -> Result<NetworkEvent, Error> {
+        let deadline = Instant::now() + self.timeout;
+        let (_, receiver) = self.handler.send_with_response(request);
+
+        wait_for(receiver, deadline)
+    }
+
+    /// Logs in with the username and password in this client's settings.
+    pub fn login(&mut self) -> Result<(), Error> {
+        match self.request(Request::login())? {
+            NetworkEvent::Login { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Gets information on the logged in user.
+    pub fn my_info(&mut self) -> Result<screeps_api::MyInfo, Error> {
+        match self.request(Request::my_info())? {
+            NetworkEvent::MyInfo { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Gets the list of shards, or `None` if the server does not have shards.
+    pub fn shard_list(&mut self) -> Result<Option<Vec<screeps_api::ShardInfo>>, Error> {
+        match self.request(Request::shard_list())? {
+            NetworkEvent::ShardList { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Gets the terrain of a room, from the disk cache if available.
+    pub fn room_terrain(&mut self, room_name: RoomName) -> Result<screeps_api::TerrainGrid, Error> {
+        match self.request(Request::room_terrain(room_name))? {
+            NetworkEvent::RoomTerrain { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Lists the user's code branches.
+    pub fn branches(&mut self) -> Result<Vec<Branch>, Error> {
+        match self.request(Request::branches())? {
+            NetworkEvent::Branches { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Gets the modules in a code branch.
+    pub fn code(&mut self, branch: &str) -> Result<CodeModules, Error> {
+        match self.request(Request::get_code(branch.to_owned()))? {
+            NetworkEvent::Code { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Replaces the modules in a code branch.
+    pub fn upload_code(&mut self, branch: &str, modules: CodeModules) -> Result<(), Error> {
+        match self.request(Request::upload_code(branch.to_owned(), modules))? {
+            NetworkEvent::CodeUploaded { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Makes a branch the one running in the world or the simulation room.
+    pub fn set_active_branch(&mut self, branch: &str, target: ActiveBranch) -> Result<(), Error> {
+        match self.request(Request::set_active_branch(branch.to_owned(), target))? {
+            NetworkEvent::ActiveBranchSet { result, .. } => Ok(result?),
+            other => Err(Error::UnexpectedEvent(other)),
+        }
+    }
+
+    /// Subscribes to map view updates for the given rooms.
+    ///
+    /// The returned iterator never ends by itself: it returns `Error::TimedOut` whenever no update arrives within
+    /// the timeout, and can be continued afterwards. Dropping it unsubscribes from all rooms.
+    pub fn subscribe_map(&mut self, rooms: RoomRect) -> MapUpdates {
+        self.handler.send(Request::subscribe_map_view(rooms));
+
+        MapUpdates {
+            client: self,
+            rooms: rooms,
+        }
+    }
+
+    /// Waits for the next map view update.
+    fn next_map_update(&mut self) -> Result<(RoomName, RoomMapViewUpdate), Error> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            self.notify.wait_on_current();
+
+            match self.handler.poll() {
+                Some(NetworkEvent::MapView { room_name, result }) => return Ok((room_name, result)),
+                Some(NetworkEvent::WebsocketError { error }) => return Err(Error::Websocket(error)),
+                Some(NetworkEvent::WebsocketParseError { error }) => return Err(Error::WebsocketParse(error)),
+                Some(NetworkEvent::WebsocketHttpError { error }) => return Err(Error::Api(error)),
+                Some(other) => {
+                    debug!("blocking client ignoring event while waiting for map view: {:?}", other);
+                }
+                None => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(Error::TimedOut);
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BlockingClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BlockingClient")
+            .field("handler", &self.handler)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Iterator over map view updates for a subscribed set of rooms.
+pub struct MapUpdates<'a> {
+    client: &'a mut BlockingClient,
+    rooms: RoomRect,
+}
+
+impl<'a> MapUpdates<'a> {
+    /// Gets the rooms subscribed to.
+    pub fn rooms(&self) -> RoomRect {
+        self.rooms
+    }
+}
+
+impl<'a> Iterator for MapUpdates<'a> {
+    type Item = Result<(RoomName, RoomMapViewUpdate), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.client.next_map_update())
+    }
+}
+
+impl<'a> Drop for MapUpdates<'a> {
+    fn drop(&mut self) {
+        self.client.handler.send(Request::subscribe_map_view(Vec::new()));
+    }
+}
@@ -0,0 +1,125 @@
+//! Code branches and modules, and reading them from and writing them to local directories.
+//!
+//! JavaScript modules are stored as `<name>.js` files, and binary modules such as WebAssembly as `<name>.wasm`.
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path};
+
+/// Modules in a single branch, by module name.
+pub type CodeModules = BTreeMap<String, CodeModule>;
+
+/// A single module of code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CodeModule {
+    /// JavaScript source.
+    Text(String),
+    /// Binary data, such as a compiled WebAssembly module.
+    Binary(Vec<u8>),
+}
+
+/// A branch of code stored on the server.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Branch {
+    pub name: String,
+    /// Whether this branch runs in the world.
+    pub active_world: bool,
+    /// Whether this branch runs in the simulation room.
+    pub active_sim: bool,
+}
+
+/// Where a branch can be made active.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ActiveBranch {
+    World,
+    Sim,
+}
+
+impl ActiveBranch {
+    /// Gets the name the API uses for this.
+    pub fn api_name(&self) -> &'static str {
+        match *self {
+            ActiveBranch::World => "activeWorld",
+            ActiveBranch::Sim => "activeSim",
+        }
+    }
+}
+
+const TEXT_EXTENSION: &str = "js";
+const BINARY_EXTENSION: &str = "wasm";
+
+/// Reads all `.js` and `.wasm` files directly inside a directory as modules.
+///
+/// Other files and subdirectories are ignored.
+pub fn load_directory<P: AsRef<Path>>(dir: P) -> io::Result<CodeModules> {
+    let mut modules = CodeModules::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+
+        let module = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(TEXT_EXTENSION) => {
+                let mut text = String::new();
+                File::open(&path)?.read_to_string(&mut text)?;
+                CodeModule::Text(text)
+            }
+            Some(BINARY_EXTENSION) => {
+                let mut data = Vec::new();
+                File::open(&path)?.read_to_end(&mut data)?;
+                CodeModule::Binary(data)
+            }
+            _ => continue,
+        };
+
+        modules.insert(name, module);
+    }
+
+    Ok(modules)
+}
+
+/// Whether a module name can be used as a file name, without reaching outside of the directory.
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        // trailing separators are dropped by `components`, so compare against the whole name.
+        (Some(Component::Normal(component)), None) => component == OsStr::new(name),
+        _ => false,
+    }
+}
+
+/// Writes each module to a file in a directory, creating the directory if it doesn't exist.
+///
+/// Fails without writing anything if any module name isn't a plain file name, such as one containing `/` or `..`.
+pub fn write_directory<P: AsRef<Path>>(dir: P, modules: &CodeModules) -> io::Result<()> {
+    if let Some(name) = modules.keys().find(|name| !is_file_name(name)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("module name {:?} can't be used as a file name", name),
+        ));
+    }
+
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    for (name, module) in modules {
+        match *module {
+            CodeModule::Text(ref text) => {
+                File::create(dir.join(format!("{}.{}", name, TEXT_EXTENSION)))?.write_all(text.as_bytes())?
+            }
+            CodeModule::Binary(ref data) => {
+                File::create(dir.join(format!("{}.{}", name, BINARY_EXTENSION)))?.write_all(data)?
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Decompression of data which the server sends compressed, such as `Memory` contents and large websocket messages.
+use std::borrow::Cow;
+use std::{fmt, io};
+use std::io::Read;
+use std::string::FromUtf8Error;
+
+use base64;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json;
+
+/// Prefix marking a string as base64 encoded compressed data.
+pub const COMPRESSED_PREFIX: &'static str = "gz:";
+
+/// Error decompressing data.
+#[derive(Debug)]
+pub enum DecompressError {
+    Base64(base64::DecodeError),
+    Io(io::Error),
+    Utf8(FromUtf8Error),
+    Json(serde_json::Error),
+}
+
+impl From<base64::DecodeError> for DecompressError {
+    fn from(e: base64::DecodeError) -> Self {
+        DecompressError::Base64(e)
+    }
+}
+
+impl From<io::Error> for DecompressError {
+    fn from(e: io::Error) -> Self {
+        DecompressError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for DecompressError {
+    fn from(e: FromUtf8Error) -> Self {
+        DecompressError::Utf8(e)
+    }
+}
+
+impl From<serde_json::Error> for DecompressError {
+    fn from(e: serde_json::Error) -> Self {
+        DecompressError::Json(e)
+    }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecompressError::Base64(ref e) => write!(f, "invalid base64 in compressed data: {}", e),
+            DecompressError::Io(ref e) => write!(f, "invalid compressed data: {}", e),
+            DecompressError::Utf8(ref e) => write!(f, "decompressed data is not UTF-8: {}", e),
+            DecompressError::Json(ref e) => write!(f, "invalid SockJS frame around compressed data: {}", e),
+        }
+    }
+}
+
+/// Decompresses a string if it starts with `gz:`, or returns it unchanged otherwise.
+pub fn decompress_prefixed(data: &str) -> Result<Cow<str>, DecompressError> {
+    if data.starts_with(COMPRESSED_PREFIX) {
+        let compressed = base64::decode(&data[COMPRESSED_PREFIX.len()..])?;
+
+        Ok(Cow::Owned(String::from_utf8(decompress(&compressed)?)?))
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+/// Decompresses gzip or zlib data, detecting which is used from the header.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut result = Vec::new();
+
+    if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(data).read_to_end(&mut result)?;
+    } else {
+        ZlibDecoder::new(data).read_to_end(&mut result)?;
+    }
+
+    Ok(result)
+}
+
+/// Decompresses any `gz:` prefixed messages inside of a SockJS frame, returning a frame which can be parsed normally.
+///
+/// Frames without compressed messages are returned unchanged.
+pub fn decompress_sockjs_frame(frame: &str) -> Result<Cow<str>, DecompressError> {
+    if !frame.contains(COMPRESSED_PREFIX) {
+        return Ok(Cow::Borrowed(frame));
+    }
+
+    if frame.starts_with(COMPRESSED_PREFIX) {
+        let decompressed = decompress_prefixed(frame)?.into_owned();
+        return Ok(Cow::Owned(decompress_sockjs_frame(&decompressed)?.into_owned()));
+    }
+
+    match frame.chars().next() {
+        // array of messages: a["message", "message"]
+        Some('a') => {
+            let messages = serde_json::from_str::<Vec<String>>(&frame[1..])?
+                .iter()
+                .map(|message| decompress_prefixed(message).map(Cow::into_owned))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Cow::Owned(format!("a{}", serde_json::to_string(&messages)?)))
+        }
+        // single message: m"message"
+        Some('m') => {
+            let message = serde_json::from_str::<String>(&frame[1..])?;
+
+            Ok(Cow::Owned(format!("m{}", serde_json::to_string(&decompress_prefixed(&message)?)?)))
+        }
+        _ => Ok(Cow::Borrowed(frame)),
+    }
+}
+
+/// Decompresses a binary websocket frame into the SockJS frame it contains.
+pub fn decompress_binary_frame(data: &[u8]) -> Result<String, DecompressError> {
+    let text = String::from_utf8(decompress(data)?)?;
+
+    Ok(decompress_sockjs_frame(&text)?.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{decompress, decompress_binary_frame, decompress_prefixed, decompress_sockjs_frame, DecompressError};
+
+    const MEMORY: &'static str = r#"{"creeps":{"Harvester1":{"role":"harvester"}}}"#;
+    /// `MEMORY`, zlib compressed and base64 encoded.
+    const MEMORY_ZLIB: &'static str = "eJyrVkouSk0tKFayqlbySCwqSy0uSS0yBPGK8nNSlayUMmCCSrW1tQB9FhCU";
+    /// `MEMORY`, gzip compressed and base64 encoded.
+    const MEMORY_GZIP: &'static str = "H4sIAAAAAAACA6tWSi5KTS0oVrKqVvJILCpLLS5JLTIE8Yryc1KVrJQyYIJKtbW1AG6WrXEuAAAA";
+
+    const MESSAGE: &'static str = r#"["user:5a1b/cpu",{"cpu":12,"memory":3456}]"#;
+    /// `MESSAGE`, zlib compressed and base64 encoded.
+    const MESSAGE_ZLIB: &'static str = "eJyLViotTi2yMk00TNJPLihV0qlWAlFWhkY6SrmpuflFlUpWxiamZrWxABysDPg=";
+
+    const FRAME: &'static str = r#"a["[\"user:5a1b/cpu\",{\"cpu\":12,\"memory\":3456}]"]"#;
+    /// `FRAME`, zlib compressed.
+    const FRAME_ZLIB: &'static [u8] = &[
+        120, 156, 75, 140, 86, 138, 142, 81, 42, 45, 78, 45, 178, 50, 77, 52, 76, 210, 79, 46, 40, 141, 81, 210, 169,
+        142, 81, 2, 51, 172, 12, 141, 116, 98, 148, 114, 83, 115, 243, 139, 42, 129, 60, 99, 19, 83, 179, 218, 88, 165,
+        88, 0, 198, 185, 16, 125,
+    ];
+    /// `FRAME`, zlib compressed and base64 encoded.
+    const FRAME_BASE64: &'static str = "eJxLjFaKjlEqLU4tsjJNNEzSTy4ojVHSqY5RAjOsDI10YpRyU3PziyqBPGMTU7PaWKVYAMa5EH0=";
+
+    #[test]
+    fn uncompressed_strings_are_borrowed() {
+        match decompress_prefixed(MEMORY).unwrap() {
+            Cow::Borrowed(data) => assert_eq!(data, MEMORY),
+            Cow::Owned(data) => panic!("expected borrowed data, found owned {}", data),
+        }
+    }
+
+    #[test]
+    fn prefixed_zlib_is_decompressed() {
+        let data = format!("gz:{}", MEMORY_ZLIB);
+        assert_eq!(decompress_prefixed(&data).unwrap(), MEMORY);
+    }
+
+    #[test]
+    fn prefixed_gzip_is_decompressed() {
+        let data = format!("gz:{}", MEMORY_GZIP);
+        assert_eq!(decompress_prefixed(&data).unwrap(), MEMORY);
+    }
+
+    #[test]
+    fn invalid_base64_is_an_error() {
+        match decompress_prefixed("gz:not*base64!") {
+            Err(DecompressError::Base64(_)) => (),
+            other => panic!("expected base64 error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated_zlib_is_an_error() {
+        assert!(decompress(&FRAME_ZLIB[..FRAME_ZLIB.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn truncated_prefixed_data_is_an_error() {
+        // "gz:" followed by only the start of `MEMORY` compressed, which is still valid base64.
+        let data = format!("gz:{}", &MEMORY_ZLIB[..MEMORY_ZLIB.len() / 4 * 2]);
+        match decompress_prefixed(&data) {
+            Err(DecompressError::Io(_)) => (),
+            other => panic!("expected io error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zlib_and_gzip_are_detected() {
+        assert_eq!(decompress(FRAME_ZLIB).unwrap(), FRAME.as_bytes());
+        let gzip = ::base64::decode(MEMORY_GZIP).unwrap();
+        assert_eq!(decompress(&gzip).unwrap(), MEMORY.as_bytes());
+    }
+
+    #[test]
+    fn frames_without_compression_are_borrowed() {
+        match decompress_sockjs_frame(FRAME).unwrap() {
+            Cow::Borrowed(frame) => assert_eq!(frame, FRAME),
+            Cow::Owned(frame) => panic!("expected borrowed frame, found owned {}", frame),
+        }
+    }
+
+    #[test]
+    fn array_frames_are_decompressed() {
+        let frame = format!(r#"a["gz:{}","[\"server-message\",\"hi\"]"]"#, MESSAGE_ZLIB);
+        let expected = format!(
+            "a{}",
+            ::serde_json::to_string(&[MESSAGE, r#"["server-message","hi"]"#]).unwrap()
+        );
+        assert_eq!(decompress_sockjs_frame(&frame).unwrap(), expected);
+    }
+
+    #[test]
+    fn message_frames_are_decompressed() {
+        let frame = format!(r#"m"gz:{}""#, MESSAGE_ZLIB);
+        let expected = format!("m{}", ::serde_json::to_string(MESSAGE).unwrap());
+        assert_eq!(decompress_sockjs_frame(&frame).unwrap(), expected);
+    }
+
+    #[test]
+    fn prefixed_frames_are_decompressed() {
+        let frame = format!("gz:{}", FRAME_BASE64);
+        assert_eq!(decompress_sockjs_frame(&frame).unwrap(), FRAME);
+    }
+
+    #[test]
+    fn malformed_frames_are_an_error() {
+        match decompress_sockjs_frame(r#"a["gz:"#) {
+            Err(DecompressError::Json(_)) => (),
+            other => panic!("expected json error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn binary_frames_are_decompressed() {
+        assert_eq!(decompress_binary_frame(FRAME_ZLIB).unwrap(), FRAME);
+    }
+
+    #[test]
+    fn truncated_binary_frames_are_an_error() {
+        match decompress_binary_frame(&FRAME_ZLIB[..10]) {
+            Err(DecompressError::Io(_)) => (),
+            other => panic!("expected io error, found {:?}", other),
+        }
+    }
+}
@@ -1,25 +1,47 @@
 use std::time::Duration;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::{fs, io};
 
 use screeps_api::{RoomName, TerrainGrid};
 use screeps_api::data::room_name::RoomNameAbsoluteCoordinates;
+use screeps_api::websocket::RoomMapViewUpdate;
 use futures_cpupool::CpuPool;
 use futures::{future, stream, Future, Stream};
 use tokio_core::reactor;
 
-use {directories, bincode, sled, time};
+use {directories, bincode, serde_json, sled, time};
+use user::UserProfile;
+use world::RoomStats;
 
 // TODO: cache per server connection.
 const OLD_DB_FILE_NAME: &'static str = "cache";
 
 const DB_FILE_NAME: &'static str = "cache-v0.2";
 
+/// Number of console commands kept in history.
+pub const CONSOLE_HISTORY_LENGTH: usize = 100;
+
 #[inline(always)]
 fn keep_terrain_for() -> time::Duration {
     time::Duration::days(1)
 }
 
+#[inline(always)]
+fn keep_map_stats_for() -> time::Duration {
+    time::Duration::days(1)
+}
+
+#[inline(always)]
+fn keep_map_views_for() -> time::Duration {
+    time::Duration::days(7)
+}
+
+#[inline(always)]
+fn keep_users_for() -> time::Duration {
+    time::Duration::days(7)
+}
+
 mod errors {
     use std::{fmt, io};
     use sled;
@@ -60,6 +82,17 @@ mod errors {
 
 pub use self::errors::CreationError;
 
+/// Statistics on the contents of the cache database.
+#[derive(Clone, Debug, Default)]
+pub struct CacheStats {
+    /// Number of entries stored, by kind of data.
+    pub entries: BTreeMap<&'static str, usize>,
+    /// Number of entries which could not be read.
+    pub unknown_entries: usize,
+    /// Total size of all keys and values, in bytes.
+    pub total_bytes: usize,
+}
+
 #[derive(Clone)]
 pub struct Cache {
     database: sled::Tree,
@@ -143,6 +176,44 @@ impl Cache {
         Ok(())
     }
 
+    /// Counts the entries currently stored in the cache.
+    pub fn stats(&self) -> impl Future<Item = CacheStats, Error = sled::Error<()>> {
+        let db = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let mut stats = CacheStats::default();
+
+            for result in db.iter() {
+                let (key, value) = result?;
+                stats.total_bytes += key.len() + value.len();
+
+                match ShardCacheKey::decode(&key) {
+                    Ok(parsed) => *stats.entries.entry(parsed.key.kind()).or_insert(0) += 1,
+                    Err(_) => stats.unknown_entries += 1,
+                }
+            }
+
+            Ok(stats)
+        })
+    }
+
+    /// Removes all entries from the cache, returning the number of entries removed.
+    pub fn purge(&self) -> impl Future<Item = usize, Error = sled::Error<()>> {
+        let db = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let keys = db.iter()
+                .map(|result| result.map(|(key, _)| key))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for key in &keys {
+                db.del(key)?;
+            }
+
+            Ok(keys.len())
+        })
+    }
+
     pub fn set_terrain(
         &self,
         server: &str,
@@ -200,6 +271,293 @@ impl Cache {
             Ok(parsed)
         })
     }
+
+    /// Stores the map stats of any number of rooms.
+    pub fn set_map_stats(
+        &self,
+        server: &str,
+        shard: Option<&str>,
+        stats: &HashMap<RoomName, RoomStats>,
+    ) -> impl Future<Item = (), Error = sled::Error<()>> {
+        let fetched = time::get_time();
+        let entries = stats
+            .iter()
+            .map(|(&room, data)| {
+                let key = ShardCacheKey::map_stats(server, shard, room).encode();
+                let to_store = CacheEntry {
+                    fetched: fetched,
+                    data: data,
+                };
+                let value = bincode::serialize(&to_store)
+                    .expect("expected serializing data using bincode to unequivocally succeed.");
+
+                (key, value)
+            })
+            .collect::<Vec<_>>();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            for (key, value) in entries {
+                sent_database.set(key, value)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Gets the map stats of rooms, leaving out any which aren't stored or were fetched longer than `max_age` ago.
+    pub fn get_map_stats(
+        &self,
+        server: &str,
+        shard: Option<&str>,
+        rooms: &[RoomName],
+        max_age: time::Duration,
+    ) -> impl Future<Item = HashMap<RoomName, RoomStats>, Error = sled::Error<()>> {
+        let keys = rooms
+            .iter()
+            .map(|&room| (room, ShardCacheKey::map_stats(server, shard, room).encode()))
+            .collect::<Vec<_>>();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let now = time::get_time();
+            let mut found = HashMap::new();
+
+            for (room, key) in keys {
+                if let Some(db_vector) = sent_database.get(&key)? {
+                    match bincode::deserialize_from::<_, CacheEntry<RoomStats>>(&mut &*db_vector) {
+                        Ok(entry) => if now - entry.fetched < max_age {
+                            found.insert(room, entry.data);
+                        },
+                        Err(e) => {
+                            warn!(
+                                "cache database entry found corrupted.\
+                                 \nEntry: (map stats:{})\
+                                 \nDecode error: {}\
+                                 \nRemoving data.",
+                                room, e
+                            );
+
+                            sent_database.del(&key)?;
+                        }
+                    }
+                }
+            }
+
+            Ok(found)
+        })
+    }
+
+    /// Stores user profiles, by id.
+    pub fn set_users(&self, server: &str, users: &[UserProfile]) -> impl Future<Item = (), Error = sled::Error<()>> {
+        let fetched = time::get_time();
+        let entries = users
+            .iter()
+            .map(|user| {
+                let key = ShardCacheKey::user(server, &*user.id).encode();
+                let to_store = CacheEntry {
+                    fetched: fetched,
+                    data: user,
+                };
+                let value = bincode::serialize(&to_store)
+                    .expect("expected serializing data using bincode to unequivocally succeed.");
+
+                (key, value)
+            })
+            .collect::<Vec<_>>();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            for (key, value) in entries {
+                sent_database.set(key, value)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Gets user profiles by id, leaving out any which aren't stored or were fetched longer than `max_age` ago.
+    pub fn get_users(
+        &self,
+        server: &str,
+        ids: &[String],
+        max_age: time::Duration,
+    ) -> impl Future<Item = HashMap<String, UserProfile>, Error = sled::Error<()>> {
+        let keys = ids.iter()
+            .map(|id| (id.clone(), ShardCacheKey::user(server, &**id).encode()))
+            .collect::<Vec<_>>();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let now = time::get_time();
+            let mut found = HashMap::new();
+
+            for (id, key) in keys {
+                if let Some(db_vector) = sent_database.get(&key)? {
+                    match bincode::deserialize_from::<_, CacheEntry<UserProfile>>(&mut &*db_vector) {
+                        Ok(entry) => if now - entry.fetched < max_age {
+                            found.insert(id, entry.data);
+                        },
+                        Err(e) => {
+                            warn!(
+                                "cache database entry found corrupted.\
+                                 \nEntry: (user:{})\
+                                 \nDecode error: {}\
+                                 \nRemoving data.",
+                                id, e
+                            );
+
+                            sent_database.del(&key)?;
+                        }
+                    }
+                }
+            }
+
+            Ok(found)
+        })
+    }
+
+    /// Stores the latest map view of a room, replacing any older one.
+    pub fn set_map_view(
+        &self,
+        server: &str,
+        shard: Option<&str>,
+        room_name: RoomName,
+        update: &RoomMapViewUpdate,
+    ) -> impl Future<Item = (), Error = sled::Error<()>> {
+        let key = ShardCacheKey::map_view(server, shard, room_name).encode();
+        let to_store = CacheEntry {
+            fetched: time::get_time(),
+            data: MapViewSnapshot::from(update),
+        };
+        let value =
+            bincode::serialize(&to_store).expect("expected serializing data using bincode to unequivocally succeed.");
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || sent_database.set(key, value))
+    }
+
+    /// Gets the last stored map views of rooms, and when each was received, leaving out any which aren't stored.
+    pub fn get_map_views(
+        &self,
+        server: &str,
+        shard: Option<&str>,
+        rooms: &[RoomName],
+    ) -> impl Future<Item = HashMap<RoomName, (time::Timespec, RoomMapViewUpdate)>, Error = sled::Error<()>> {
+        let keys = rooms
+            .iter()
+            .map(|&room| (room, ShardCacheKey::map_view(server, shard, room).encode()))
+            .collect::<Vec<_>>();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let mut found = HashMap::new();
+
+            for (room, key) in keys {
+                if let Some(db_vector) = sent_database.get(&key)? {
+                    let parsed = bincode::deserialize_from::<_, CacheEntry<MapViewSnapshot>>(&mut &*db_vector)
+                        .map_err(|e| e.to_string())
+                        .and_then(|entry| {
+                            let fetched = entry.fetched;
+                            entry
+                                .data
+                                .into_update()
+                                .map(|update| (fetched, update))
+                                .map_err(|e| e.to_string())
+                        });
+                    match parsed {
+                        Ok(view) => {
+                            found.insert(room, view);
+                        }
+                        Err(e) => {
+                            warn!(
+                                "cache database entry found corrupted.\
+                                 \nEntry: (map view:{})\
+                                 \nDecode error: {}\
+                                 \nRemoving data.",
+                                room, e
+                            );
+
+                            sent_database.del(&key)?;
+                        }
+                    }
+                }
+            }
+
+            Ok(found)
+        })
+    }
+
+    /// Gets previously run console commands for a server, oldest first.
+    pub fn get_console_history(&self, server: &str) -> impl Future<Item = Vec<String>, Error = sled::Error<()>> {
+        let key = ShardCacheKey::console_history(server).encode();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool
+            .spawn_fn(move || read_console_history(&sent_database, &key))
+    }
+
+    /// Adds a console command to the end of a server's history, removing the oldest commands if the history is full.
+    pub fn push_console_history(
+        &self,
+        server: &str,
+        expression: String,
+    ) -> impl Future<Item = (), Error = sled::Error<()>> {
+        let key = ShardCacheKey::console_history(server).encode();
+
+        let sent_database = self.database.clone();
+
+        self.access_pool.spawn_fn(move || {
+            let mut history = read_console_history(&sent_database, &key)?;
+
+            if history.last() != Some(&expression) {
+                history.push(expression);
+            }
+            if history.len() > CONSOLE_HISTORY_LENGTH {
+                let excess = history.len() - CONSOLE_HISTORY_LENGTH;
+                history.drain(..excess);
+            }
+
+            let to_store = CacheEntry {
+                fetched: time::get_time(),
+                data: history,
+            };
+
+            let value = bincode::serialize(&to_store)
+                .expect("expected serializing data using bincode to unequivocally succeed.");
+
+            sent_database.set(key, value)
+        })
+    }
+}
+
+fn read_console_history(db: &sled::Tree, key: &[u8]) -> Result<Vec<String>, sled::Error<()>> {
+    let parsed = match db.get(key)? {
+        Some(db_vector) => match bincode::deserialize_from::<_, CacheEntry<Vec<String>>>(&mut &*db_vector) {
+            Ok(v) => v.data,
+            Err(e) => {
+                warn!(
+                    "cache database entry found corrupted.\
+                     \nEntry: (console history)\
+                     \nDecode error: {}\
+                     \nRemoving data.",
+                    e
+                );
+
+                db.del(key)?;
+
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+
+    Ok(parsed)
 }
 
 fn cleanup_database(db: &sled::Tree) -> Result<(), sled::Error<()>> {
@@ -226,6 +584,14 @@ fn cleanup_database(db: &sled::Tree) -> Result<(), sled::Error<()>> {
             let keep_result = match parsed_key.key {
                 CacheKeyInner::Terrain(_) => bincode::deserialize::<CacheEntry<TerrainGrid>>(&value)
                     .map(|entry| now - entry.fetched < keep_terrain_for()),
+                // history is only ever trimmed, never expired.
+                CacheKeyInner::ConsoleHistory => bincode::deserialize::<CacheEntry<Vec<String>>>(&value).map(|_| true),
+                CacheKeyInner::MapStats(_) => bincode::deserialize::<CacheEntry<RoomStats>>(&value)
+                    .map(|entry| now - entry.fetched < keep_map_stats_for()),
+                CacheKeyInner::User(_) => bincode::deserialize::<CacheEntry<UserProfile>>(&value)
+                    .map(|entry| now - entry.fetched < keep_users_for()),
+                CacheKeyInner::MapView(_) => bincode::deserialize::<CacheEntry<MapViewSnapshot>>(&value)
+                    .map(|entry| now - entry.fetched < keep_map_views_for()),
             };
 
             match keep_result {
@@ -262,10 +628,78 @@ struct CacheEntry<T> {
     data: T,
 }
 
+/// A room's map view, as stored in the database.
+///
+/// `RoomMapViewUpdate` can only be read from the game's JSON format, so it's turned back into that when loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct MapViewSnapshot {
+    walls: Vec<(u32, u32)>,
+    roads: Vec<(u32, u32)>,
+    power_or_power_bank: Vec<(u32, u32)>,
+    portals: Vec<(u32, u32)>,
+    sources: Vec<(u32, u32)>,
+    minerals: Vec<(u32, u32)>,
+    controllers: Vec<(u32, u32)>,
+    keeper_lairs: Vec<(u32, u32)>,
+    users_objects: Vec<(String, Vec<(u32, u32)>)>,
+}
+
+impl<'a> From<&'a RoomMapViewUpdate> for MapViewSnapshot {
+    fn from(update: &'a RoomMapViewUpdate) -> Self {
+        MapViewSnapshot {
+            walls: update.walls.clone(),
+            roads: update.roads.clone(),
+            power_or_power_bank: update.power_or_power_bank.clone(),
+            portals: update.portals.clone(),
+            sources: update.sources.clone(),
+            minerals: update.minerals.clone(),
+            controllers: update.controllers.clone(),
+            keeper_lairs: update.keeper_lairs.clone(),
+            users_objects: update.users_objects.clone(),
+        }
+    }
+}
+
+impl MapViewSnapshot {
+    fn into_update(self) -> Result<RoomMapViewUpdate, serde_json::Error> {
+        let mut json = serde_json::Map::new();
+        json.insert("w".to_owned(), serde_json::to_value(self.walls)?);
+        json.insert("r".to_owned(), serde_json::to_value(self.roads)?);
+        json.insert("pb".to_owned(), serde_json::to_value(self.power_or_power_bank)?);
+        json.insert("p".to_owned(), serde_json::to_value(self.portals)?);
+        json.insert("s".to_owned(), serde_json::to_value(self.sources)?);
+        json.insert("m".to_owned(), serde_json::to_value(self.minerals)?);
+        json.insert("c".to_owned(), serde_json::to_value(self.controllers)?);
+        json.insert("k".to_owned(), serde_json::to_value(self.keeper_lairs)?);
+        for (user_id, positions) in self.users_objects {
+            json.insert(user_id, serde_json::to_value(positions)?);
+        }
+
+        serde_json::from_value(serde_json::Value::Object(json))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 enum CacheKeyInner {
     // NOTE: whenever adding a variant, the length return in 'encode' must be tested and updated.
     Terrain(RoomNameAbsoluteCoordinates),
+    ConsoleHistory,
+    MapStats(RoomNameAbsoluteCoordinates),
+    User(String),
+    MapView(RoomNameAbsoluteCoordinates),
+}
+
+impl CacheKeyInner {
+    /// Short name for the kind of data stored under this key.
+    fn kind(&self) -> &'static str {
+        match *self {
+            CacheKeyInner::Terrain(_) => "terrain",
+            CacheKeyInner::ConsoleHistory => "console history",
+            CacheKeyInner::MapStats(_) => "map stats",
+            CacheKeyInner::User(_) => "user",
+            CacheKeyInner::MapView(_) => "map view",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -294,6 +728,52 @@ impl<'a> ShardCacheKey<'a> {
         }
     }
 
+    fn map_stats<T, U>(server: T, shard: Option<U>, room_name: RoomName) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+        U: Into<Cow<'a, str>>,
+    {
+        ShardCacheKey {
+            server: server.into(),
+            shard: shard.map(Into::into),
+            key: CacheKeyInner::MapStats(room_name.into()),
+        }
+    }
+
+    fn map_view<T, U>(server: T, shard: Option<U>, room_name: RoomName) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+        U: Into<Cow<'a, str>>,
+    {
+        ShardCacheKey {
+            server: server.into(),
+            shard: shard.map(Into::into),
+            key: CacheKeyInner::MapView(room_name.into()),
+        }
+    }
+
+    fn user<T>(server: T, id: &str) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        ShardCacheKey {
+            server: server.into(),
+            shard: None,
+            key: CacheKeyInner::User(id.to_owned()),
+        }
+    }
+
+    fn console_history<T>(server: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        ShardCacheKey {
+            server: server.into(),
+            shard: None,
+            key: CacheKeyInner::ConsoleHistory,
+        }
+    }
+
     /// Returns bytes representing this cache key, encoded using `bincode`.
     fn encode(&self) -> Vec<u8> {
         bincode::serialize(self)
@@ -2,9 +2,14 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-use {screeps_api, time, websocket};
+use {screeps_api, serde_json, time, websocket, Url};
 
 use screeps_api::RoomName;
+
+use code::{ActiveBranch, Branch, CodeModules};
+use request::RequestId;
+use user::{UserProfile, UserQuery};
+use world::{RoomStats, RoomStatus, WorldSize};
 use screeps_api::websocket::types::room::objects::KnownRoomObject;
 
 #[derive(Default, Debug)]
@@ -15,33 +20,176 @@ pub struct MapCacheData {
     pub terrain: HashMap<RoomName, (time::Timespec, Option<screeps_api::TerrainGrid>)>,
     /// Map views, the Timespec is when the data was fetched.
     pub map_views: HashMap<RoomName, (time::Timespec, screeps_api::websocket::RoomMapViewUpdate)>,
-    /// Single current known view of a room.
+    /// Current known view of each room subscribed to in detail.
     ///
     /// TODO: keep track of history of room's we've subscribed to in the past and what tick each data was updated.
     /// TODO: possibly keep history for each for an 'instant replay' functionality.
     /// TODO: handle unknown room objects better: given that we know they have at least an 'x' and 'y' property, we
     /// could definitely do a question mark in the UI with a drop-down for JSON properties the object has.
-    pub detail_view: Option<(RoomName, HashMap<String, KnownRoomObject>)>,
+    pub detail_view: HashMap<RoomName, HashMap<String, KnownRoomObject>>,
+    /// Size of the current shard, if known. Rooms outside of it are never requested.
+    pub world_size: Option<WorldSize>,
+    /// Status of rooms on the current shard, the Timespec is when the status was fetched.
+    pub room_status: HashMap<RoomName, (time::Timespec, RoomStatus)>,
+    /// Owners and signs of rooms on the current shard, the Timespec is when the stats were fetched.
+    pub room_stats: HashMap<RoomName, (time::Timespec, RoomStats)>,
 }
 
 pub type MapCache = Rc<RefCell<MapCacheData>>;
 
+/// Server protocol version this client understands.
+pub const SUPPORTED_PROTOCOL: u32 = 14;
+
+/// Versions and time sent by the server when a websocket connection is opened.
+///
+/// Each is sent in a separate message, so any of them may be missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ServerInfo {
+    /// Version of the protocol the server speaks.
+    pub protocol: Option<u32>,
+    /// Version of the server package.
+    pub package: Option<u32>,
+    /// Server time, in milliseconds since the unix epoch.
+    pub time: Option<u64>,
+}
+
+impl ServerInfo {
+    /// Updates this with any values known in `other`.
+    pub fn update(&mut self, other: ServerInfo) {
+        if other.protocol.is_some() {
+            self.protocol = other.protocol;
+        }
+        if other.package.is_some() {
+            self.package = other.package;
+        }
+        if other.time.is_some() {
+            self.time = other.time;
+        }
+    }
+
+    /// Whether the server speaks a protocol other than the one this client understands.
+    pub fn protocol_mismatch(&self) -> bool {
+        self.protocol.map_or(false, |protocol| protocol != SUPPORTED_PROTOCOL)
+    }
+}
+
 #[derive(Debug)]
 pub enum NetworkEvent {
     Login {
+        id: RequestId,
         username: String,
         result: Result<(), screeps_api::Error>,
     },
     MyInfo {
+        id: RequestId,
         result: Result<screeps_api::MyInfo, screeps_api::Error>,
     },
     ShardList {
+        id: RequestId,
         result: Result<Option<Vec<screeps_api::ShardInfo>>, screeps_api::Error>,
     },
     RoomTerrain {
+        id: RequestId,
         room_name: screeps_api::RoomName,
         result: Result<screeps_api::TerrainGrid, screeps_api::Error>,
     },
+    WorldSize {
+        id: RequestId,
+        shard: Option<String>,
+        result: Result<WorldSize, screeps_api::Error>,
+    },
+    /// Versions of the server at `api_url`. Only `protocol` and `package` are filled in.
+    ServerVersion {
+        id: RequestId,
+        api_url: Url,
+        result: Result<ServerInfo, screeps_api::Error>,
+    },
+    GameTime {
+        id: RequestId,
+        shard: Option<String>,
+        result: Result<u32, screeps_api::Error>,
+    },
+    /// Status of each of `rooms`.
+    RoomStatus {
+        id: RequestId,
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+        result: Result<HashMap<RoomName, RoomStatus>, screeps_api::Error>,
+    },
+    /// Ownership of each of `rooms` which exists.
+    MapStats {
+        id: RequestId,
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+        result: Result<HashMap<RoomName, RoomStats>, screeps_api::Error>,
+    },
+    /// Profiles of users, in the same order as `queries`.
+    Users {
+        id: RequestId,
+        queries: Vec<UserQuery>,
+        result: Result<Vec<Option<UserProfile>>, screeps_api::Error>,
+    },
+    ConsoleCommand {
+        id: RequestId,
+        expression: String,
+        result: Result<(), screeps_api::Error>,
+    },
+    ConsoleHistory {
+        id: RequestId,
+        history: Vec<String>,
+    },
+    /// Map views stored in the disk cache for any of `rooms`, with when each was received.
+    MapViewSnapshots {
+        id: RequestId,
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+        views: HashMap<RoomName, (time::Timespec, screeps_api::websocket::RoomMapViewUpdate)>,
+    },
+    Memory {
+        id: RequestId,
+        shard: Option<String>,
+        path: String,
+        result: Result<serde_json::Value, screeps_api::Error>,
+    },
+    MemorySet {
+        id: RequestId,
+        shard: Option<String>,
+        path: String,
+        result: Result<(), screeps_api::Error>,
+    },
+    /// Contents of memory segments, in the same order as `segments`.
+    MemorySegments {
+        id: RequestId,
+        shard: Option<String>,
+        segments: Vec<u8>,
+        result: Result<Vec<String>, screeps_api::Error>,
+    },
+    MemorySegmentSet {
+        id: RequestId,
+        shard: Option<String>,
+        segment: u8,
+        result: Result<(), screeps_api::Error>,
+    },
+    Branches {
+        id: RequestId,
+        result: Result<Vec<Branch>, screeps_api::Error>,
+    },
+    Code {
+        id: RequestId,
+        branch: String,
+        result: Result<CodeModules, screeps_api::Error>,
+    },
+    CodeUploaded {
+        id: RequestId,
+        branch: String,
+        result: Result<(), screeps_api::Error>,
+    },
+    ActiveBranchSet {
+        id: RequestId,
+        branch: String,
+        target: ActiveBranch,
+        result: Result<(), screeps_api::Error>,
+    },
     WebsocketHttpError {
         error: screeps_api::Error,
     },
@@ -57,20 +205,108 @@ pub enum NetworkEvent {
     },
     RoomView {
         room_name: screeps_api::RoomName,
+        /// Shard the room is on, if the server has shards.
+        shard: Option<String>,
         result: screeps_api::websocket::RoomUpdate,
     },
+    /// Console output for the logged in user, from a single tick.
+    ConsoleOutput {
+        /// Shard the output is from, if the server has shards.
+        shard: Option<String>,
+        /// Messages logged with `console.log`.
+        messages: Vec<String>,
+        /// Results of console commands.
+        results: Vec<String>,
+        /// Error which occurred during the tick, if any.
+        error: Option<String>,
+    },
+    /// Resource usage of the logged in user's code during the last tick.
+    UserCpu {
+        /// CPU used, in milliseconds.
+        cpu: u32,
+        /// Size of `Memory`, in bytes.
+        memory: u32,
+    },
+    /// New value of a `Memory` path watched with `Request::WatchMemory`.
+    MemoryWatch {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// Server versions or time, received on connecting.
+    ServerInfo {
+        info: ServerInfo,
+    },
 }
 
 impl NetworkEvent {
+    /// Gets the id of the request this event is the direct result of.
+    ///
+    /// Websocket updates are not the result of any single request, and will return `None`.
+    pub fn request_id(&self) -> Option<RequestId> {
+        match *self {
+            NetworkEvent::Login { id, .. }
+            | NetworkEvent::MyInfo { id, .. }
+            | NetworkEvent::ShardList { id, .. }
+            | NetworkEvent::RoomTerrain { id, .. }
+            | NetworkEvent::WorldSize { id, .. }
+            | NetworkEvent::ServerVersion { id, .. }
+            | NetworkEvent::GameTime { id, .. }
+            | NetworkEvent::RoomStatus { id, .. }
+            | NetworkEvent::MapStats { id, .. }
+            | NetworkEvent::Users { id, .. }
+            | NetworkEvent::ConsoleCommand { id, .. }
+            | NetworkEvent::ConsoleHistory { id, .. }
+            | NetworkEvent::MapViewSnapshots { id, .. }
+            | NetworkEvent::Memory { id, .. }
+            | NetworkEvent::MemorySet { id, .. }
+            | NetworkEvent::MemorySegments { id, .. }
+            | NetworkEvent::MemorySegmentSet { id, .. }
+            | NetworkEvent::Branches { id, .. }
+            | NetworkEvent::Code { id, .. }
+            | NetworkEvent::CodeUploaded { id, .. }
+            | NetworkEvent::ActiveBranchSet { id, .. } => Some(id),
+            NetworkEvent::WebsocketHttpError { .. }
+            | NetworkEvent::WebsocketError { .. }
+            | NetworkEvent::WebsocketParseError { .. }
+            | NetworkEvent::MapView { .. }
+            | NetworkEvent::RoomView { .. }
+            | NetworkEvent::ConsoleOutput { .. }
+            | NetworkEvent::UserCpu { .. }
+            | NetworkEvent::MemoryWatch { .. }
+            | NetworkEvent::ServerInfo { .. } => None,
+        }
+    }
+
     pub fn error(&self) -> Option<&screeps_api::Error> {
         match *self {
             NetworkEvent::Login { ref result, .. } => result.as_ref().err(),
             NetworkEvent::MyInfo { ref result, .. } => result.as_ref().err(),
             NetworkEvent::ShardList { ref result, .. } => result.as_ref().err(),
             NetworkEvent::RoomTerrain { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::WorldSize { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::ServerVersion { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::GameTime { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::RoomStatus { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::MapStats { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::Users { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::ConsoleCommand { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::Memory { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::MemorySet { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::MemorySegments { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::MemorySegmentSet { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::Branches { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::Code { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::CodeUploaded { ref result, .. } => result.as_ref().err(),
+            NetworkEvent::ActiveBranchSet { ref result, .. } => result.as_ref().err(),
             NetworkEvent::WebsocketHttpError { ref error } => Some(error),
-            NetworkEvent::MapView { .. }
+            NetworkEvent::ConsoleHistory { .. }
+            | NetworkEvent::MapViewSnapshots { .. }
+            | NetworkEvent::MapView { .. }
             | NetworkEvent::RoomView { .. }
+            | NetworkEvent::ConsoleOutput { .. }
+            | NetworkEvent::UserCpu { .. }
+            | NetworkEvent::MemoryWatch { .. }
+            | NetworkEvent::ServerInfo { .. }
             | NetworkEvent::WebsocketError { .. }
             | NetworkEvent::WebsocketParseError { .. } => None,
         }
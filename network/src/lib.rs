@@ -17,10 +17,15 @@ extern crate futures_cpupool;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 extern crate sled;
 extern crate time;
 
+// Compression
+extern crate base64;
+extern crate flate2;
+
 // Logging
 #[macro_use]
 extern crate log;
@@ -30,19 +35,32 @@ pub mod event;
 pub mod memcache;
 pub mod diskcache;
 pub mod tokio;
+pub mod blocking;
+pub mod compression;
+pub mod code;
+pub mod world;
+pub mod user;
 
 use std::fmt;
-pub use url::Url;
+pub use url::{ParseError as UrlParseError, Url};
 
-pub use request::{LoginDetails, NotLoggedIn, Request, SelectedRooms};
-pub use event::{MapCache, MapCacheData, NetworkEvent};
-pub use memcache::{ErrorEvent, LoginState, MemCache};
+pub use request::{LoginDetails, NotLoggedIn, Request, RequestId, RoomRect, RoomRegion};
+pub use event::{MapCache, MapCacheData, NetworkEvent, ServerInfo, SUPPORTED_PROTOCOL};
+pub use memcache::{ConsoleBuffer, ConsoleLevel, ConsoleLine, ErrorEvent, LoginState, MemCache, ShardClock,
+                   TickClock, UsageHistory, UsageSample};
 pub use tokio::Handler as TokioHandler;
+pub use tokio::StreamHandler as TokioStreamHandler;
+pub use blocking::BlockingClient;
+pub use code::{ActiveBranch, Branch, CodeModule, CodeModules};
+pub use user::{Badge, UserProfile, UserQuery};
+pub use world::{MapUser, RoomControl, RoomSign, RoomStats, RoomStatus, WorldSize};
 
 /// The backend connection handler for handling requests. Interface for `memcache` module to use.
 pub trait ScreepsConnection {
     /// Send a request. Any and all errors will be returned in the future via poll()
-    fn send(&mut self, r: Request);
+    ///
+    /// The returned id will be present on any event which is the direct result of this request.
+    fn send(&mut self, r: Request) -> RequestId;
 
     /// Get the next available event if any, or return None if nothing new has happened.
     ///
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::collections::vec_deque;
+
+/// Queue holding at most `capacity` items, dropping the oldest items to make room for new ones.
+#[derive(Clone, Debug)]
+pub struct BoundedQueue<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> BoundedQueue<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        BoundedQueue {
+            items: VecDeque::with_capacity(capacity),
+            capacity: capacity,
+        }
+    }
+
+    /// Adds an item, removing the oldest item if the queue is full.
+    ///
+    /// With a capacity of zero, the item is dropped.
+    pub fn push(&mut self, item: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.items.len() >= self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    /// Iterates over items from oldest to newest.
+    pub fn iter(&self) -> vec_deque::Iter<T> {
+        self.items.iter()
+    }
+
+    /// Gets the newest item.
+    pub fn back(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Sets the maximum number of items, removing the oldest items if there are now too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.items.len() > capacity {
+            self.items.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundedQueue;
+
+    fn contents(queue: &BoundedQueue<u32>) -> Vec<u32> {
+        queue.iter().cloned().collect()
+    }
+
+    #[test]
+    fn push_drops_oldest_when_full() {
+        let mut queue = BoundedQueue::with_capacity(3);
+        for item in 0..5 {
+            queue.push(item);
+        }
+
+        assert_eq!(contents(&queue), vec![2, 3, 4]);
+        assert_eq!(queue.back(), Some(&4));
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut queue = BoundedQueue::with_capacity(0);
+        queue.push(1);
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.back(), None);
+    }
+
+    #[test]
+    fn shrinking_drops_oldest() {
+        let mut queue = BoundedQueue::with_capacity(5);
+        for item in 0..5 {
+            queue.push(item);
+        }
+        queue.set_capacity(2);
+
+        assert_eq!(contents(&queue), vec![3, 4]);
+        assert_eq!(queue.capacity(), 2);
+
+        queue.set_capacity(4);
+        queue.push(5);
+        queue.push(6);
+        queue.push(7);
+
+        assert_eq!(contents(&queue), vec![4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::vec_deque;
+
+use time;
+
+use super::bounded::BoundedQueue;
+
+/// Number of console lines kept by default.
+pub const DEFAULT_CONSOLE_CAPACITY: usize = 1000;
+
+/// Importance of a single console line.
+///
+/// Log messages are classified by common prefixes such as `[WARN]`, while tick errors are always `Error`
+/// and results of console commands are always `Result`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConsoleLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Result,
+}
+
+impl ConsoleLevel {
+    /// Guesses the level of a log message from its prefix.
+    pub fn of_log_message(message: &str) -> Self {
+        let start = message
+            .trim_left()
+            .chars()
+            .take(10)
+            .collect::<String>()
+            .to_lowercase();
+
+        if start.starts_with("[error") || start.starts_with("error") {
+            ConsoleLevel::Error
+        } else if start.starts_with("[warn") || start.starts_with("warn") {
+            ConsoleLevel::Warning
+        } else if start.starts_with("[debug") || start.starts_with("[trace") {
+            ConsoleLevel::Debug
+        } else {
+            ConsoleLevel::Info
+        }
+    }
+}
+
+/// Single line of console output.
+#[derive(Clone, Debug)]
+pub struct ConsoleLine {
+    /// Shard the line was output on, if the server has shards.
+    pub shard: Option<String>,
+    pub level: ConsoleLevel,
+    pub text: String,
+    /// Time the line was received.
+    pub received: time::Timespec,
+}
+
+/// Bounded buffer of console output, dropping the oldest lines when full.
+#[derive(Clone, Debug)]
+pub struct ConsoleBuffer {
+    lines: BoundedQueue<ConsoleLine>,
+}
+
+impl Default for ConsoleBuffer {
+    fn default() -> Self {
+        ConsoleBuffer::with_capacity(DEFAULT_CONSOLE_CAPACITY)
+    }
+}
+
+impl ConsoleBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        ConsoleBuffer {
+            lines: BoundedQueue::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a line, removing the oldest line if the buffer is full.
+    pub fn push(&mut self, shard: Option<String>, level: ConsoleLevel, text: String) {
+        self.lines.push(ConsoleLine {
+            shard: shard,
+            level: level,
+            text: text,
+            received: time::get_time(),
+        });
+    }
+
+    /// Iterates over lines from oldest to newest.
+    pub fn iter(&self) -> vec_deque::Iter<ConsoleLine> {
+        self.lines.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lines.capacity()
+    }
+
+    /// Sets the maximum number of lines, removing the oldest lines if there are now too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.lines.set_capacity(capacity);
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+impl<'a> IntoIterator for &'a ConsoleBuffer {
+    type Item = &'a ConsoleLine;
+    type IntoIter = vec_deque::Iter<'a, ConsoleLine>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -1,16 +1,20 @@
 use std::rc::Rc;
 use std::sync::Arc;
-use std::cell::Ref;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
 use screeps_api::{self, RoomName};
+use serde_json::Value;
 use time::{self, Duration};
 
-use super::{ErrorEvent, LoginState};
-use event::{MapCacheData, NetworkEvent};
-use request::{Request, SelectedRooms};
-use {ConnectionSettings, ScreepsConnection};
+use super::{ConsoleBuffer, ConsoleLevel, ErrorEvent, LoginState, TickClock, UsageHistory};
+use diskcache::CONSOLE_HISTORY_LENGTH;
+use code::{ActiveBranch, Branch, CodeModules};
+use event::{MapCacheData, NetworkEvent, ServerInfo};
+use request::{Request, RoomRect, RoomRegion};
+use user::{UserProfile, UserQuery};
+use world::{RoomStatus, WorldSize};
+use {ConnectionSettings, ScreepsConnection, Url};
 
 #[derive(Copy, Clone, Debug)]
 struct TimeoutValue<T> {
@@ -76,6 +80,13 @@ impl<T> TimeoutValue<T> {
         self.value.as_ref().map(|tuple| &tuple.0)
     }
 
+    /// Marks the value as out of date, so that it is re-requested while still being available.
+    fn expire(&mut self) {
+        if let Some((_, ref mut fetched)) = self.value {
+            *fetched = time::Timespec::new(0, 0);
+        }
+    }
+
     /// Resets the value to None.
     fn reset(&mut self) {
         self.value = None;
@@ -83,6 +94,23 @@ impl<T> TimeoutValue<T> {
     }
 }
 
+/// Number of rooms which can be viewed in detail at once, unless changed with `MemCache::set_max_detail_rooms`.
+pub const DEFAULT_MAX_DETAIL_ROOMS: usize = 4;
+/// Number of rooms subscribed to map views of at once, unless changed with `MemCache::set_max_map_rooms`.
+pub const DEFAULT_MAX_MAP_ROOMS: usize = 100;
+/// Fraction of the map room budget used for refreshing rooms outside of it, in rotation.
+const MAP_ROTATION_FRACTION: usize = 5;
+/// Seconds between moving map rotation on to the next rooms.
+const MAP_ROTATION_SECONDS: i64 = 10;
+/// Minutes before re-requesting the status of a room, to catch novice and respawn areas expiring.
+const ROOM_STATUS_MINUTES: i64 = 10;
+/// Minutes before re-requesting the owner of a room.
+const MAP_STATS_MINUTES: i64 = 10;
+/// Minutes before re-requesting a user's profile.
+const USER_MINUTES: i64 = 60;
+/// Seconds without a game time from room views before asking the server for it.
+const GAME_TIME_SECONDS: i64 = 10;
+
 #[derive(Default, Debug)]
 pub struct MemCache {
     login: TimeoutValue<()>,
@@ -90,8 +118,50 @@ pub struct MemCache {
     shard_list: TimeoutValue<Option<Vec<screeps_api::ShardInfo>>>,
     rooms: Rc<RefCell<MapCacheData>>,
     requested_rooms: HashMap<RoomName, time::Timespec>,
-    last_requested_room_info: Option<SelectedRooms>,
-    last_requested_focus_room: Option<RoomName>,
+    requested_room_status: HashMap<RoomName, time::Timespec>,
+    requested_map_stats: HashMap<RoomName, time::Timespec>,
+    /// Rooms map views have been requested from the disk cache for.
+    requested_map_views: HashMap<RoomName, time::Timespec>,
+    last_requested_room_info: Option<RoomRect>,
+    /// Shard of the current connection settings.
+    shard: Option<String>,
+    /// Size of each shard of the current server, or None for shards the server couldn't give the size of.
+    world_size: HashMap<Option<String>, TimeoutValue<Option<WorldSize>>>,
+    /// Rooms most recently subscribed to map views of.
+    map_rooms: Vec<RoomName>,
+    /// Limit on rooms subscribed to map views of at once, or None for `DEFAULT_MAX_MAP_ROOMS`.
+    max_map_rooms: Option<usize>,
+    /// How far through the rooms outside of the map budget the rotation is.
+    map_rotation: usize,
+    map_rotated_at: Option<time::Timespec>,
+    /// Whether the map was last viewed zoomed out, showing only room owners.
+    overview: bool,
+    /// Rooms most recently requested to be viewed in detail.
+    detail_rooms: Vec<RoomName>,
+    /// Limit on rooms viewed in detail at once, or None for `DEFAULT_MAX_DETAIL_ROOMS`.
+    max_detail_rooms: Option<usize>,
+    console: ConsoleBuffer,
+    console_history: TimeoutValue<Vec<String>>,
+    usage: UsageHistory,
+    tick_clock: TickClock,
+    requested_game_time: Option<time::Timespec>,
+    memory: HashMap<(Option<String>, String), TimeoutValue<Value>>,
+    segments: HashMap<(Option<String>, u8), TimeoutValue<String>>,
+    branches: TimeoutValue<Vec<Branch>>,
+    /// Modules in each code branch, by branch name.
+    code: HashMap<String, TimeoutValue<CodeModules>>,
+    server: Option<Url>,
+    server_info: ServerInfo,
+    /// Versions of servers by API url, fetched without logging in.
+    server_versions: HashMap<Url, TimeoutValue<ServerInfo>>,
+    /// Latest values of watched memory paths, and when they were received.
+    watched_memory: HashMap<String, (Value, time::Timespec)>,
+    /// User profiles by id, or None for ids which don't exist.
+    users: HashMap<String, TimeoutValue<Option<UserProfile>>>,
+    /// User ids by lowercase username, or None for usernames which don't exist.
+    user_ids: HashMap<String, TimeoutValue<Option<String>>>,
+    /// Lookups asked for since the cache was last aligned, which will be sent together.
+    user_lookups: Vec<UserQuery>,
 }
 
 pub struct NetworkedMemCache<'a, T: ScreepsConnection + 'a> {
@@ -106,13 +176,12 @@ impl MemCache {
 
     fn event(&mut self, event: NetworkEvent) -> Result<(), ErrorEvent> {
         match event {
-            NetworkEvent::Login {
-                username: _,
-                result,
-            } => self.login.event(result)?,
-            NetworkEvent::MyInfo { result } => self.my_info.event(result)?,
-            NetworkEvent::ShardList { result } => self.shard_list.event(result)?,
-            NetworkEvent::RoomTerrain { room_name, result } => {
+            NetworkEvent::Login { result, .. } => self.login.event(result)?,
+            NetworkEvent::MyInfo { result, .. } => self.my_info.event(result)?,
+            NetworkEvent::ShardList { result, .. } => self.shard_list.event(result)?,
+            NetworkEvent::RoomTerrain {
+                room_name, result, ..
+            } => {
                 let terrain = match result {
                     Ok(terrain) => Some(terrain),
                     Err(err) => {
@@ -128,22 +197,170 @@ impl MemCache {
                     .terrain
                     .insert(room_name, (time::get_time(), terrain));
             }
+            NetworkEvent::WorldSize { shard, result, .. } => {
+                let holder = self.world_size.entry(shard).or_insert_with(TimeoutValue::default);
+                match result {
+                    Ok(size) => {
+                        let _ = holder.event::<()>(Ok(Some(size)));
+                    }
+                    Err(e) => {
+                        // fall back to requesting every room rather than asking again and again.
+                        let _ = holder.event::<()>(Ok(None));
+                        return Err(e.into());
+                    }
+                }
+            }
+            NetworkEvent::ServerVersion { api_url, result, .. } => match result {
+                Ok(info) => {
+                    let _ = self.server_versions
+                        .entry(api_url)
+                        .or_insert_with(TimeoutValue::default)
+                        .event::<()>(Ok(info));
+                }
+                // urls are checked as they're typed in, so failures are expected. the request is left pending,
+                // which waits before trying again.
+                Err(e) => debug!("error occurred fetching version of {}: {:?}", api_url, e),
+            },
+            NetworkEvent::GameTime { shard, result, .. } => match result {
+                Ok(game_time) => self.tick_clock.observe(shard, game_time),
+                // only the header's tick counter goes without this, so it isn't worth showing.
+                Err(e) => warn!("error occurred fetching game time: {:?}", e),
+            },
+            NetworkEvent::RoomStatus {
+                shard,
+                rooms,
+                result,
+                ..
+            } => {
+                let mut statuses = match result {
+                    Ok(statuses) => statuses,
+                    Err(e) => {
+                        // a missing status only leaves rooms uncolored, so this isn't worth showing. the rooms stay
+                        // requested so they're tried again after the usual wait rather than right away.
+                        warn!("error occurred fetching room statuses: {:?}", e);
+                        return Ok(());
+                    }
+                };
+                for room_name in &rooms {
+                    self.requested_room_status.remove(room_name);
+                }
+                // statuses requested before switching shards are for the wrong room.
+                if shard == self.shard {
+                    let now = time::get_time();
+                    let mut data = self.rooms.borrow_mut();
+                    // rooms left out don't exist.
+                    for room_name in rooms {
+                        let status = statuses.remove(&room_name).unwrap_or(RoomStatus::Closed);
+                        data.room_status.insert(room_name, (now, status));
+                    }
+                }
+            }
+            NetworkEvent::MapStats {
+                shard,
+                rooms,
+                result,
+                ..
+            } => {
+                for room_name in &rooms {
+                    self.requested_map_stats.remove(room_name);
+                }
+                let mut stats = result?;
+                if shard == self.shard {
+                    let now = time::get_time();
+                    let mut data = self.rooms.borrow_mut();
+                    // rooms left out have nothing to own.
+                    for room_name in rooms {
+                        let room_stats = stats.remove(&room_name).unwrap_or_default();
+                        data.room_stats.insert(room_name, (now, room_stats));
+                    }
+                }
+            }
+            NetworkEvent::Users { queries, result, .. } => match result {
+                Ok(found) => for (query, user) in queries.into_iter().zip(found) {
+                    match query {
+                        UserQuery::Username(username) => {
+                            let id = user.as_ref().map(|user| user.id.clone());
+                            let _ = self.user_ids
+                                .entry(username.to_lowercase())
+                                .or_insert_with(TimeoutValue::default)
+                                .event::<()>(Ok(id));
+                        }
+                        UserQuery::Id(id) => if user.is_none() {
+                            let _ = self.users
+                                .entry(id)
+                                .or_insert_with(TimeoutValue::default)
+                                .event::<()>(Ok(None));
+                        },
+                    }
+                    if let Some(user) = user {
+                        let _ = self.user_ids
+                            .entry(user.username.to_lowercase())
+                            .or_insert_with(TimeoutValue::default)
+                            .event::<()>(Ok(Some(user.id.clone())));
+                        let _ = self.users
+                            .entry(user.id.clone())
+                            .or_insert_with(TimeoutValue::default)
+                            .event::<()>(Ok(Some(user)));
+                    }
+                },
+                Err(e) => {
+                    for query in queries {
+                        let holder = match query {
+                            UserQuery::Id(id) => self.users.get_mut(&id).map(|holder| &mut holder.last_send),
+                            UserQuery::Username(username) => self.user_ids
+                                .get_mut(&username.to_lowercase())
+                                .map(|holder| &mut holder.last_send),
+                        };
+                        if let Some(last_send) = holder {
+                            *last_send = None;
+                        }
+                    }
+                    return Err(e.into());
+                }
+            },
             NetworkEvent::MapView { room_name, result } => {
                 self.rooms
                     .borrow_mut()
                     .map_views
                     .insert(room_name, (time::get_time(), result));
             }
-            NetworkEvent::RoomView { room_name, result } => {
+            NetworkEvent::MapViewSnapshots {
+                shard, rooms, views, ..
+            } => {
+                for room_name in &rooms {
+                    self.requested_map_views.remove(room_name);
+                }
+                if shard == self.shard {
+                    let mut data = self.rooms.borrow_mut();
+                    for (room_name, view) in views {
+                        // updates received while the snapshot was loading are newer.
+                        data.map_views.entry(room_name).or_insert(view);
+                    }
+                }
+            }
+            NetworkEvent::RoomView {
+                room_name,
+                shard,
+                result,
+            } => {
                 use serde_json;
                 use std::collections::hash_map::Entry::*;
 
+                if let Some(game_time) = result.game_time {
+                    self.tick_clock.observe(shard, game_time);
+                }
+
+                // updates can still arrive for a short time after a room is unsubscribed from.
+                if !self.detail_rooms.contains(&room_name) {
+                    return Ok(());
+                }
+
                 let mut data = self.rooms.borrow_mut();
 
                 let mut new_detail_view = None;
 
-                match data.detail_view.as_mut() {
-                    Some(&mut (name, ref mut map)) if name == room_name => {
+                match data.detail_view.get_mut(&room_name) {
+                    Some(map) => {
                         for (id, obj_update) in result.objects.into_iter() {
                             if obj_update.is_null() {
                                 map.remove(&id);
@@ -174,7 +391,7 @@ impl MemCache {
                             }
                         }
                     }
-                    _ => {
+                    None => {
                         let new_map = result
                             .objects
                             .into_iter()
@@ -195,9 +412,97 @@ impl MemCache {
                 }
 
                 if let Some(view) = new_detail_view {
-                    data.detail_view = Some((room_name, view));
+                    data.detail_view.insert(room_name, view);
+                }
+            }
+            NetworkEvent::ConsoleCommand { result, .. } => result?,
+            NetworkEvent::ConsoleHistory { mut history, .. } => {
+                // commands sent before the history finished loading are newer than anything stored.
+                if let Some(sent) = self.console_history.get() {
+                    history.extend(sent.iter().cloned());
+                }
+                let _ = self.console_history.event::<()>(Ok(history));
+                self.trim_console_history();
+            }
+            NetworkEvent::ConsoleOutput {
+                shard,
+                messages,
+                results,
+                error,
+            } => {
+                for message in messages {
+                    let level = ConsoleLevel::of_log_message(&message);
+                    self.console.push(shard.clone(), level, message);
+                }
+                for result in results {
+                    self.console.push(shard.clone(), ConsoleLevel::Result, result);
+                }
+                if let Some(error) = error {
+                    self.console.push(shard, ConsoleLevel::Error, error);
+                }
+            }
+            NetworkEvent::UserCpu { cpu, memory } => self.usage.push(cpu, memory),
+            NetworkEvent::Memory {
+                shard, path, result, ..
+            } => self.memory
+                .entry((shard, path))
+                .or_insert_with(TimeoutValue::default)
+                .event(result)?,
+            NetworkEvent::MemorySet { shard, result, .. } => {
+                result?;
+                // setting one path can change any path above or below it, so re-request all of this shard.
+                for (_, holder) in self.memory.iter_mut().filter(|&(key, _)| key.0 == shard) {
+                    holder.expire();
+                }
+            }
+            NetworkEvent::MemorySegments {
+                shard,
+                segments,
+                result,
+                ..
+            } => match result {
+                Ok(data) => for (segment, data) in segments.into_iter().zip(data) {
+                    let _ = self.segments
+                        .entry((shard.clone(), segment))
+                        .or_insert_with(TimeoutValue::default)
+                        .event::<()>(Ok(data));
+                },
+                Err(e) => {
+                    for segment in segments {
+                        if let Some(holder) = self.segments.get_mut(&(shard.clone(), segment)) {
+                            holder.last_send = None;
+                        }
+                    }
+                    return Err(e.into());
+                }
+            },
+            NetworkEvent::MemorySegmentSet {
+                shard, segment, result, ..
+            } => {
+                result?;
+                if let Some(holder) = self.segments.get_mut(&(shard, segment)) {
+                    holder.expire();
+                }
+            }
+            NetworkEvent::Branches { result, .. } => self.branches.event(result)?,
+            NetworkEvent::Code { branch, result, .. } => self.code
+                .entry(branch)
+                .or_insert_with(TimeoutValue::default)
+                .event(result)?,
+            NetworkEvent::CodeUploaded { branch, result, .. } => {
+                result?;
+                if let Some(holder) = self.code.get_mut(&branch) {
+                    holder.expire();
                 }
             }
+            NetworkEvent::ActiveBranchSet { result, .. } => {
+                result?;
+                self.branches.expire();
+            }
+            NetworkEvent::MemoryWatch { path, value } => {
+                self.watched_memory.insert(path, (value, time::get_time()));
+            }
+            NetworkEvent::ServerInfo { info } => self.server_info.update(info),
             NetworkEvent::WebsocketError { error } => return Err(ErrorEvent::WebsocketError(error)),
             NetworkEvent::WebsocketHttpError { error } => return Err(ErrorEvent::ErrorOccurred(error)),
             NetworkEvent::WebsocketParseError { error } => return Err(ErrorEvent::WebsocketParse(error)),
@@ -216,6 +521,106 @@ impl MemCache {
         }
     }
 
+    fn trim_console_history(&mut self) {
+        if let Some(&mut (ref mut history, _)) = self.console_history.value.as_mut() {
+            if history.len() > CONSOLE_HISTORY_LENGTH {
+                let excess = history.len() - CONSOLE_HISTORY_LENGTH;
+                history.drain(..excess);
+            }
+        }
+    }
+
+    /// Gets console output received so far.
+    pub fn console(&self) -> &ConsoleBuffer {
+        &self.console
+    }
+
+    /// Sets the maximum number of console lines kept.
+    pub fn set_console_capacity(&mut self, capacity: usize) {
+        self.console.set_capacity(capacity);
+    }
+
+    /// Gets CPU and memory usage over recent ticks.
+    pub fn usage(&self) -> &UsageHistory {
+        &self.usage
+    }
+
+    /// Sets the number of ticks of usage kept.
+    pub fn set_usage_capacity(&mut self, capacity: usize) {
+        self.usage.set_capacity(capacity);
+    }
+
+    /// Gets the latest value of a watched memory path, and the time it was received.
+    pub fn watched_memory(&self, path: &str) -> Option<&(Value, time::Timespec)> {
+        self.watched_memory.get(path)
+    }
+
+    /// Gets the number of rooms which can be subscribed to map views of at once.
+    pub fn max_map_rooms(&self) -> usize {
+        self.max_map_rooms.unwrap_or(DEFAULT_MAX_MAP_ROOMS)
+    }
+
+    /// Sets the number of rooms which can be subscribed to map views of at once.
+    pub fn set_max_map_rooms(&mut self, max: usize) {
+        self.max_map_rooms = Some(max);
+        // force the next view to choose rooms again.
+        self.last_requested_room_info = None;
+    }
+
+    /// Chooses which rooms to subscribe to map views of.
+    ///
+    /// If there are more rooms than the budget, most of it goes to the rooms closest to the center. The remainder
+    /// cycles through the other rooms, moving on each time `rotate` is true.
+    fn choose_map_rooms(&mut self, rooms: RoomRect, rotate: bool) -> Vec<RoomName> {
+        let budget = self.max_map_rooms();
+        if rooms.len() <= budget {
+            return rooms.into_iter().collect();
+        }
+
+        let mut chosen = RoomRegion::from(rooms).center_out(rooms.center());
+
+        let rotating = if budget > 1 {
+            (budget / MAP_ROTATION_FRACTION).max(1)
+        } else {
+            0
+        };
+        let rest = chosen.split_off(budget - rotating);
+
+        if rotate {
+            self.map_rotation = self.map_rotation.wrapping_add(rotating);
+        }
+        let start = self.map_rotation % rest.len();
+        chosen.extend(rest.iter().cycle().skip(start).take(rotating).cloned());
+
+        chosen
+    }
+
+    /// Gets the number of rooms which can be viewed in detail at once.
+    pub fn max_detail_rooms(&self) -> usize {
+        self.max_detail_rooms.unwrap_or(DEFAULT_MAX_DETAIL_ROOMS)
+    }
+
+    /// Sets the number of rooms which can be viewed in detail at once. Rooms past this in the list given to
+    /// `view_rooms` are only shown in the map view.
+    pub fn set_max_detail_rooms(&mut self, max: usize) {
+        self.max_detail_rooms = Some(max);
+    }
+
+    /// Gets the latest game time and tick rate of each shard.
+    pub fn tick_clock(&self) -> &TickClock {
+        &self.tick_clock
+    }
+
+    /// Gets the API url of the server most recently connected to, if any.
+    pub fn server(&self) -> Option<&Url> {
+        self.server.as_ref()
+    }
+
+    /// Gets what the server has told us about itself.
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
     pub fn align<'a, T, F, E>(
         &'a mut self,
         handler: &'a mut T,
@@ -238,6 +643,11 @@ impl MemCache {
             }
         }
 
+        if !self.user_lookups.is_empty() {
+            let queries = ::std::mem::replace(&mut self.user_lookups, Vec::new());
+            handler.send(Request::find_users(queries));
+        }
+
         NetworkedMemCache {
             cache: self,
             handler: handler,
@@ -255,9 +665,287 @@ impl<'a, C: ScreepsConnection> NetworkedMemCache<'a, C> {
     }
 
     pub fn update_settings(&mut self, settings: ConnectionSettings) {
+        // the new settings may point at another server, which will send its own information on connecting.
+        if self.cache.server.as_ref() != Some(&settings.api_url) {
+            self.cache.world_size.clear();
+            self.cache.users.clear();
+            self.cache.user_ids.clear();
+            self.cache.user_lookups.clear();
+        }
+        self.cache.server = Some(settings.api_url.clone());
+        self.cache.server_info = ServerInfo::default();
+        // room status and world size are per shard.
+        self.cache.shard = settings.shard.clone();
+        self.cache.requested_room_status.clear();
+        self.cache.requested_map_stats.clear();
+        self.cache.requested_map_views.clear();
+        self.cache.last_requested_room_info = None;
+        {
+            let mut data = self.cache.rooms.borrow_mut();
+            data.world_size = None;
+            data.room_status.clear();
+            data.room_stats.clear();
+        }
+        self.cache.tick_clock.clear();
+        self.cache.requested_game_time = None;
+        // subscriptions are dropped when switching shards, so detail rooms need to be requested again.
+        self.cache.detail_rooms.clear();
+        self.cache.rooms.borrow_mut().detail_view.clear();
         self.handler.send(Request::ChangeSettings {
             settings: Arc::new(settings),
-        })
+        });
+    }
+
+    pub fn console(&self) -> &ConsoleBuffer {
+        self.cache.console()
+    }
+
+    pub fn usage(&self) -> &UsageHistory {
+        self.cache.usage()
+    }
+
+    pub fn server(&self) -> Option<&Url> {
+        self.cache.server()
+    }
+
+    pub fn tick_clock(&self) -> &TickClock {
+        self.cache.tick_clock()
+    }
+
+    pub fn max_detail_rooms(&self) -> usize {
+        self.cache.max_detail_rooms()
+    }
+
+    pub fn max_map_rooms(&self) -> usize {
+        self.cache.max_map_rooms()
+    }
+
+    pub fn server_info(&self) -> &ServerInfo {
+        self.cache.server_info()
+    }
+
+    /// Gets the protocol and package versions of the server at `api_url`, requesting them if they haven't been
+    /// yet. This works without logging in.
+    pub fn server_version(&mut self, api_url: &Url) -> Option<&ServerInfo> {
+        let holder = self.cache
+            .server_versions
+            .entry(api_url.clone())
+            .or_insert_with(TimeoutValue::default);
+        if holder.should_request(None, Duration::seconds(90)) {
+            self.handler.send(Request::server_version(api_url.clone()));
+            holder.requested();
+        }
+
+        holder.get()
+    }
+
+    /// Gets the user's code branches, re-requesting them every minute while this is being called.
+    pub fn branches(&mut self) -> Option<&[Branch]> {
+        let holder = &mut self.cache.branches;
+        if holder.should_request(Some(Duration::minutes(1)), Duration::seconds(90)) {
+            self.handler.send(Request::branches());
+            holder.requested();
+        }
+
+        holder.get().map(AsRef::as_ref)
+    }
+
+    /// Gets the modules in a code branch, re-requesting them every minute while this is being called.
+    pub fn code(&mut self, branch: &str) -> Option<&CodeModules> {
+        let holder = self.cache
+            .code
+            .entry(branch.to_owned())
+            .or_insert_with(TimeoutValue::default);
+        if holder.should_request(Some(Duration::minutes(1)), Duration::seconds(90)) {
+            self.handler.send(Request::get_code(branch.to_owned()));
+            holder.requested();
+        }
+
+        holder.get()
+    }
+
+    /// Makes a branch the one running in the world or the simulation room. Branches are refreshed once this
+    /// succeeds.
+    pub fn set_active_branch(&mut self, branch: &str, target: ActiveBranch) {
+        self.handler
+            .send(Request::set_active_branch(branch.to_owned(), target));
+    }
+
+    /// Gets the profile of a user by id, or None if it isn't known yet or the user doesn't exist.
+    ///
+    /// Each missing user is requested the next time the cache is aligned, and requested again once an hour while
+    /// this is being called. Users missing at the same time are sent as one request, but are still found one
+    /// call at a time.
+    pub fn user(&mut self, id: &str) -> Option<&UserProfile> {
+        let holder = self.cache
+            .users
+            .entry(id.to_owned())
+            .or_insert_with(TimeoutValue::default);
+        if holder.should_request(Some(Duration::minutes(USER_MINUTES)), Duration::seconds(90)) {
+            self.cache.user_lookups.push(UserQuery::Id(id.to_owned()));
+            holder.requested();
+        }
+
+        holder.get().and_then(Option::as_ref)
+    }
+
+    /// Gets the profile of a user by username, ignoring case.
+    pub fn user_by_name(&mut self, username: &str) -> Option<&UserProfile> {
+        let id = {
+            let holder = self.cache
+                .user_ids
+                .entry(username.to_lowercase())
+                .or_insert_with(TimeoutValue::default);
+            if holder.should_request(None, Duration::seconds(90)) {
+                self.cache
+                    .user_lookups
+                    .push(UserQuery::Username(username.to_owned()));
+                holder.requested();
+            }
+
+            holder.get().and_then(|id| id.clone())
+        };
+
+        match id {
+            Some(id) => self.user(&id),
+            None => None,
+        }
+    }
+
+    /// Holds a subscription to a websocket channel, sharing it with anything else holding the same channel.
+    ///
+    /// Each call should be matched with a call to `release_channel` once updates are no longer needed.
+    pub fn acquire_channel(&mut self, channel: &str) {
+        self.handler.send(Request::acquire_channel(channel.to_owned()));
+    }
+
+    pub fn release_channel(&mut self, channel: &str) {
+        self.handler.send(Request::release_channel(channel.to_owned()));
+    }
+
+    /// Starts watching a memory path, receiving its value each tick.
+    ///
+    /// Each call should be matched with a call to `unwatch_memory` once the value is no longer needed.
+    pub fn watch_memory(&mut self, path: &str) {
+        self.handler.send(Request::watch_memory(path.to_owned()));
+    }
+
+    pub fn unwatch_memory(&mut self, path: &str) {
+        self.handler.send(Request::unwatch_memory(path.to_owned()));
+    }
+
+    pub fn watched_memory(&self, path: &str) -> Option<&(Value, time::Timespec)> {
+        self.cache.watched_memory(path)
+    }
+
+    /// Gets previously run console commands, oldest first.
+    ///
+    /// This is loaded from the disk cache the first time it is called, and will be empty until then.
+    pub fn console_history(&mut self) -> &[String] {
+        let holder = &mut self.cache.console_history;
+        if holder.should_request(None, Duration::seconds(90)) {
+            self.handler.send(Request::console_history());
+            holder.requested();
+        }
+
+        holder.get().map(AsRef::as_ref).unwrap_or(&[])
+    }
+
+    /// Runs a console command on the given shard, or the current shard if `None`.
+    ///
+    /// The command is added to the console history immediately.
+    pub fn send_console_command(&mut self, shard: Option<String>, expression: String) {
+        // make sure stored history is requested before adding to it, so it is merged in rather than skipped.
+        self.console_history();
+        {
+            let holder = &mut self.cache.console_history;
+            let mut history = holder.value.take().map(|(history, _)| history).unwrap_or_default();
+            if history.last() != Some(&expression) {
+                history.push(expression.clone());
+            }
+            holder.value = Some((history, time::get_time()));
+        }
+        self.cache.trim_console_history();
+
+        self.handler.send(Request::console_command(shard, expression));
+    }
+
+    /// Gets the value in `Memory` at a dot-separated path, such as `creeps.John`, or all of `Memory` for an
+    /// empty path.
+    ///
+    /// Memory is re-requested every 10 seconds while this is being called.
+    pub fn memory(&mut self, shard: Option<&str>, path: &str) -> Option<&Value> {
+        let holder = self.cache
+            .memory
+            .entry((shard.map(ToOwned::to_owned), path.to_owned()))
+            .or_insert_with(TimeoutValue::default);
+        if holder.should_request(Some(Duration::seconds(10)), Duration::seconds(90)) {
+            self.handler
+                .send(Request::get_memory(shard.map(ToOwned::to_owned), path.to_owned()));
+            holder.requested();
+        }
+
+        holder.get()
+    }
+
+    /// Re-requests the value in `Memory` at a path, keeping the current value until the new one arrives.
+    pub fn refresh_memory(&mut self, shard: Option<&str>, path: &str) {
+        if let Some(holder) = self.cache
+            .memory
+            .get_mut(&(shard.map(ToOwned::to_owned), path.to_owned()))
+        {
+            holder.expire();
+            holder.last_send = None;
+        }
+        self.memory(shard, path);
+    }
+
+    /// Sets the value in `Memory` at a path. Cached memory for the shard is refreshed once this succeeds.
+    pub fn set_memory(&mut self, shard: Option<&str>, path: &str, value: &Value) {
+        self.handler
+            .send(Request::set_memory(shard.map(ToOwned::to_owned), path.to_owned(), value));
+    }
+
+    /// Gets the contents of a memory segment, re-requesting it every 30 seconds while this is being called.
+    pub fn memory_segment(&mut self, shard: Option<&str>, segment: u8) -> Option<&str> {
+        self.request_memory_segments(shard, &[segment]);
+
+        self.cached_memory_segment(shard, segment)
+    }
+
+    /// Gets the contents of a memory segment if it's been fetched, without requesting it.
+    pub fn cached_memory_segment(&self, shard: Option<&str>, segment: u8) -> Option<&str> {
+        self.cache
+            .segments
+            .get(&(shard.map(ToOwned::to_owned), segment))
+            .and_then(TimeoutValue::get)
+            .map(AsRef::as_ref)
+    }
+
+    /// Requests any of the given memory segments which are missing or out of date, all in one request.
+    pub fn request_memory_segments(&mut self, shard: Option<&str>, segments: &[u8]) {
+        let mut to_request = Vec::new();
+        for &segment in segments {
+            let holder = self.cache
+                .segments
+                .entry((shard.map(ToOwned::to_owned), segment))
+                .or_insert_with(TimeoutValue::default);
+            if holder.should_request(Some(Duration::seconds(30)), Duration::seconds(90)) {
+                holder.requested();
+                to_request.push(segment);
+            }
+        }
+
+        if !to_request.is_empty() {
+            self.handler
+                .send(Request::get_memory_segments(shard.map(ToOwned::to_owned), to_request));
+        }
+    }
+
+    /// Sets the contents of a memory segment. The cached contents are refreshed once this succeeds.
+    pub fn set_memory_segment(&mut self, shard: Option<&str>, segment: u8, data: String) {
+        self.handler
+            .send(Request::set_memory_segment(shard.map(ToOwned::to_owned), segment, data));
     }
 
     pub fn my_info(&mut self) -> Option<&screeps_api::MyInfo> {
@@ -280,30 +968,171 @@ impl<'a, C: ScreepsConnection> NetworkedMemCache<'a, C> {
         holder.get().map(|o| o.as_ref().map(AsRef::as_ref))
     }
 
-    pub fn view_rooms(&mut self, rooms: SelectedRooms, focused: Option<RoomName>) -> &Rc<RefCell<MapCacheData>> {
-        if Some(rooms) != self.cache.last_requested_room_info {
-            let borrowed = Ref::map(self.cache.rooms.borrow(), |cache| &cache.terrain);
-            let rerequest_if_before = time::get_time() - Duration::seconds(90);
-            for room_name in rooms {
-                if !borrowed.contains_key(&room_name) {
-                    let resend = match self.cache.requested_rooms.get(&room_name) {
+    /// Gets the size of the current shard, requesting it if it hasn't been yet.
+    ///
+    /// Returns `Some(None)` if the server couldn't give the size, and all rooms should be assumed to exist.
+    fn world_size(&mut self) -> Option<Option<WorldSize>> {
+        let shard = self.cache.shard.clone();
+        let holder = self.cache
+            .world_size
+            .entry(shard.clone())
+            .or_insert_with(TimeoutValue::default);
+        if holder.should_request(None, Duration::seconds(90)) {
+            self.handler.send(Request::world_size(shard));
+            holder.requested();
+        }
+
+        holder.get().cloned()
+    }
+
+    /// Requests the game time of the current shard if the tick clock hasn't moved on in a while.
+    fn request_game_time(&mut self, now: time::Timespec) {
+        let wait = Duration::seconds(GAME_TIME_SECONDS);
+        let stale = match self.cache.tick_clock.shard(self.cache.shard.as_ref().map(|s| &**s)) {
+            Some(clock) => clock.received + wait < now,
+            None => true,
+        };
+        let requested = match self.cache.requested_game_time {
+            Some(requested) => requested + wait > now,
+            None => false,
+        };
+        if stale && !requested {
+            self.cache.requested_game_time = Some(now);
+            self.handler.send(Request::game_time(self.cache.shard.clone()));
+        }
+    }
+
+    /// Subscribes to map views of `rooms`, and to full detail of the first rooms in `detail`, up to the maximum
+    /// number of detail rooms.
+    ///
+    /// Only the rooms closest to the center of `rooms` are subscribed to, up to the map room budget. The rest keep
+    /// showing their last known map view, and are subscribed to a few at a time in rotation to refresh them. Rooms
+    /// which haven't had a map view yet show the one last stored in the disk cache, if any.
+    ///
+    /// Nothing is requested for rooms outside of the world, so map views wait until the world size is known.
+    ///
+    /// When `overview` is true, no map views are subscribed to: the map is zoomed out far enough that room owners
+    /// from map stats are all that's shown.
+    ///
+    /// The game time of the current shard is also requested every few seconds while no detail room is sending it,
+    /// keeping the tick clock going.
+    pub fn view_rooms(
+        &mut self,
+        rooms: RoomRect,
+        detail: &[RoomName],
+        overview: bool,
+    ) -> &Rc<RefCell<MapCacheData>> {
+        let now = time::get_time();
+        self.request_game_time(now);
+        let rotate = match self.cache.map_rotated_at {
+            Some(last) => now - last >= Duration::seconds(MAP_ROTATION_SECONDS),
+            None => true,
+        };
+
+        let changed = Some(rooms) != self.cache.last_requested_room_info || overview != self.cache.overview;
+        let world_size = if changed || rotate {
+            self.world_size()
+        } else {
+            None
+        };
+
+        if let Some(world_size) = world_size {
+            self.cache.rooms.borrow_mut().world_size = world_size;
+            let rooms_in_world = match world_size {
+                Some(size) => rooms.intersection(&size.rooms()),
+                None => Some(rooms),
+            };
+
+            {
+                let borrowed = self.cache.rooms.borrow();
+                let rerequest_if_before = now - Duration::seconds(90);
+                let should_send = |requested: &HashMap<RoomName, time::Timespec>, room_name: RoomName| {
+                    match requested.get(&room_name) {
                         Some(v) => v < &rerequest_if_before,
                         None => true,
+                    }
+                };
+                let mut status_rooms = Vec::new();
+                let mut stats_rooms = Vec::new();
+                let mut snapshot_rooms = Vec::new();
+                for room_name in rooms_in_world.into_iter().flat_map(|rect| rect) {
+                    let terrain_missing = !borrowed.terrain.contains_key(&room_name);
+                    if terrain_missing && should_send(&self.cache.requested_rooms, room_name) {
+                        self.cache.requested_rooms.insert(room_name, now);
+                        self.handler.send(Request::room_terrain(room_name));
+                    }
+
+                    let status_expired = match borrowed.room_status.get(&room_name) {
+                        Some(&(fetched, _)) => fetched + Duration::minutes(ROOM_STATUS_MINUTES) < now,
+                        None => true,
                     };
+                    if status_expired && should_send(&self.cache.requested_room_status, room_name) {
+                        self.cache.requested_room_status.insert(room_name, now);
+                        status_rooms.push(room_name);
+                    }
 
-                    if resend {
-                        self.cache
-                            .requested_rooms
-                            .insert(room_name, time::get_time());
-                        self.handler.send(Request::room_terrain(room_name));
+                    let stats_expired = match borrowed.room_stats.get(&room_name) {
+                        Some(&(fetched, _)) => fetched + Duration::minutes(MAP_STATS_MINUTES) < now,
+                        None => true,
+                    };
+                    if stats_expired && should_send(&self.cache.requested_map_stats, room_name) {
+                        self.cache.requested_map_stats.insert(room_name, now);
+                        stats_rooms.push(room_name);
+                    }
+
+                    let view_missing = !overview && !borrowed.map_views.contains_key(&room_name);
+                    if view_missing && should_send(&self.cache.requested_map_views, room_name) {
+                        self.cache.requested_map_views.insert(room_name, now);
+                        snapshot_rooms.push(room_name);
                     }
                 }
+                if !status_rooms.is_empty() {
+                    self.handler
+                        .send(Request::room_status(self.cache.shard.clone(), status_rooms));
+                }
+                if !stats_rooms.is_empty() {
+                    self.handler
+                        .send(Request::map_stats(self.cache.shard.clone(), stats_rooms));
+                }
+                if !snapshot_rooms.is_empty() {
+                    self.handler
+                        .send(Request::map_view_snapshots(self.cache.shard.clone(), snapshot_rooms));
+                }
+            }
+
+            if rotate {
+                self.cache.map_rotated_at = Some(now);
+            }
+            let map_rooms = match rooms_in_world {
+                Some(rooms_in_world) if !overview => self.cache.choose_map_rooms(rooms_in_world, rotate),
+                _ => Vec::new(),
+            };
+            if map_rooms != self.cache.map_rooms {
+                self.handler.send(Request::subscribe_map_view(map_rooms.clone()));
+                self.cache.map_rooms = map_rooms;
             }
-            self.handler.send(Request::subscribe_map_view(rooms));
+            self.cache.last_requested_room_info = Some(rooms);
+            self.cache.overview = overview;
         }
-        if focused != self.cache.last_requested_focus_room {
-            self.handler.send(Request::focus_room(focused));
+
+        let mut detail_rooms = Vec::with_capacity(detail.len());
+        for &room_name in detail {
+            if !detail_rooms.contains(&room_name) {
+                detail_rooms.push(room_name);
+            }
         }
+        detail_rooms.truncate(self.cache.max_detail_rooms());
+
+        if detail_rooms != self.cache.detail_rooms {
+            self.cache
+                .rooms
+                .borrow_mut()
+                .detail_view
+                .retain(|room_name, _| detail_rooms.contains(room_name));
+            self.handler.send(Request::detail_rooms(detail_rooms.clone()));
+            self.cache.detail_rooms = detail_rooms;
+        }
+
         &self.cache.rooms
     }
 }
@@ -2,9 +2,16 @@ use std::fmt;
 
 use {screeps_api, websocket};
 
-pub use self::memory::{MemCache, NetworkedMemCache};
+pub use self::console::{ConsoleBuffer, ConsoleLevel, ConsoleLine};
+pub use self::memory::{MemCache, NetworkedMemCache, DEFAULT_MAX_DETAIL_ROOMS, DEFAULT_MAX_MAP_ROOMS};
+pub use self::usage::{UsageHistory, UsageSample};
+pub use self::tick_clock::{ShardClock, TickClock};
 
+mod bounded;
+mod console;
 mod memory;
+mod usage;
+mod tick_clock;
 
 pub enum ErrorEvent {
     NotLoggedIn,
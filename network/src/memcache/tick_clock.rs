@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use time::{self, Duration, Timespec};
+
+/// Weight given to each new tick duration when updating the running average.
+const AVERAGE_WEIGHT: f64 = 0.2;
+/// Gaps between updates longer than this many seconds per tick are assumed to be pauses in receiving updates,
+/// and aren't counted towards the average.
+const MAX_TICK_SECONDS: f64 = 60.0;
+
+/// Latest known game time of a single shard.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShardClock {
+    /// Most recent game time seen.
+    pub game_time: u32,
+    /// Time the most recent game time was first seen.
+    pub received: Timespec,
+    /// Average tick duration, in seconds, or None if only one tick has been seen.
+    pub tick_seconds: Option<f64>,
+}
+
+impl ShardClock {
+    /// Predicts when the next tick will arrive.
+    pub fn next_tick(&self) -> Option<Timespec> {
+        self.tick_seconds
+            .map(|seconds| self.received + Duration::milliseconds((seconds * 1000.0) as i64))
+    }
+
+    /// Estimates how far through the current tick `now` is, from 0 just after the last tick arrived to 1 when the
+    /// next tick is expected.
+    ///
+    /// Stays at 1 while the next tick is late.
+    pub fn tick_progress(&self, now: Timespec) -> Option<f64> {
+        self.tick_seconds.map(|seconds| {
+            let elapsed = seconds_between(self.received, now);
+            (elapsed / seconds).max(0.0).min(1.0)
+        })
+    }
+}
+
+/// Tracks the game time of each shard as updates come in, and estimates how long ticks take.
+#[derive(Clone, Debug, Default)]
+pub struct TickClock {
+    shards: HashMap<Option<String>, ShardClock>,
+}
+
+fn seconds_between(start: Timespec, end: Timespec) -> f64 {
+    (end - start).num_milliseconds() as f64 / 1000.0
+}
+
+impl TickClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that an update from `game_time` was received just now.
+    ///
+    /// Updates from a tick which has already been seen are ignored, since every subscribed room sends one.
+    pub fn observe(&mut self, shard: Option<String>, game_time: u32) {
+        self.observe_at(shard, game_time, time::get_time());
+    }
+
+    /// Records that an update from `game_time` was received at `now`.
+    pub fn observe_at(&mut self, shard: Option<String>, game_time: u32, now: Timespec) {
+        use std::collections::hash_map::Entry;
+
+        match self.shards.entry(shard) {
+            Entry::Occupied(entry) => {
+                let clock = entry.into_mut();
+                if game_time <= clock.game_time {
+                    return;
+                }
+
+                let ticks = (game_time - clock.game_time) as f64;
+                let seconds = seconds_between(clock.received, now) / ticks;
+                if seconds > 0.0 && seconds < MAX_TICK_SECONDS {
+                    clock.tick_seconds = Some(match clock.tick_seconds {
+                        Some(average) => average + (seconds - average) * AVERAGE_WEIGHT,
+                        None => seconds,
+                    });
+                }
+                clock.game_time = game_time;
+                clock.received = now;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(ShardClock {
+                    game_time: game_time,
+                    received: now,
+                    tick_seconds: None,
+                });
+            }
+        }
+    }
+
+    /// Gets the clock for a shard, if any updates have been received from it.
+    pub fn shard(&self, shard: Option<&str>) -> Option<&ShardClock> {
+        // TODO: avoid allocating here.
+        self.shards.get(&shard.map(ToOwned::to_owned))
+    }
+
+    /// Gets the latest known game time of a shard.
+    pub fn game_time(&self, shard: Option<&str>) -> Option<u32> {
+        self.shard(shard).map(|clock| clock.game_time)
+    }
+
+    /// Gets the average duration of a tick on a shard, in seconds.
+    pub fn tick_seconds(&self, shard: Option<&str>) -> Option<f64> {
+        self.shard(shard).and_then(|clock| clock.tick_seconds)
+    }
+
+    /// Predicts when the next tick will arrive on a shard.
+    pub fn next_tick(&self, shard: Option<&str>) -> Option<Timespec> {
+        self.shard(shard).and_then(ShardClock::next_tick)
+    }
+
+    /// Estimates how far through the current tick a shard is right now, from 0 to 1.
+    ///
+    /// Renderers can use this to interpolate movement between the last two ticks.
+    pub fn tick_progress(&self, shard: Option<&str>) -> Option<f64> {
+        self.shard(shard)
+            .and_then(|clock| clock.tick_progress(time::get_time()))
+    }
+
+    /// Forgets all shards, for when connecting to a different server.
+    pub fn clear(&mut self) {
+        self.shards.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, Timespec};
+
+    use super::{TickClock, AVERAGE_WEIGHT, MAX_TICK_SECONDS};
+
+    /// Gets a time `millis` milliseconds after an arbitrary start.
+    fn at(millis: i64) -> Timespec {
+        Timespec::new(1_500_000_000, 0) + Duration::milliseconds(millis)
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        match actual {
+            Some(actual) => assert!(
+                (actual - expected).abs() < 1e-9,
+                "expected {}, found {}",
+                expected,
+                actual
+            ),
+            None => panic!("expected {}, found None", expected),
+        }
+    }
+
+    #[test]
+    fn first_tick_has_no_average() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+
+        assert_eq!(clock.game_time(None), Some(10));
+        assert_eq!(clock.tick_seconds(None), None);
+        assert_eq!(clock.next_tick(None), None);
+        assert_eq!(clock.shard(None).unwrap().tick_progress(at(500)), None);
+    }
+
+    #[test]
+    fn second_tick_sets_average() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+        clock.observe_at(None, 11, at(3000));
+
+        assert_eq!(clock.game_time(None), Some(11));
+        assert_close(clock.tick_seconds(None), 3.0);
+        assert_eq!(clock.next_tick(None), Some(at(6000)));
+    }
+
+    #[test]
+    fn later_ticks_move_average_by_weight() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+        clock.observe_at(None, 11, at(3000));
+        clock.observe_at(None, 12, at(7000));
+
+        assert_close(clock.tick_seconds(None), 3.0 + (4.0 - 3.0) * AVERAGE_WEIGHT);
+
+        clock.observe_at(None, 13, at(9000));
+        let average = 3.0 + (4.0 - 3.0) * AVERAGE_WEIGHT;
+        assert_close(clock.tick_seconds(None), average + (2.0 - average) * AVERAGE_WEIGHT);
+    }
+
+    #[test]
+    fn skipped_ticks_are_averaged_per_tick() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+        clock.observe_at(None, 13, at(6000));
+
+        assert_close(clock.tick_seconds(None), 2.0);
+    }
+
+    #[test]
+    fn pauses_are_not_averaged() {
+        let max_millis = (MAX_TICK_SECONDS * 1000.0) as i64;
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+        clock.observe_at(None, 11, at(3000));
+
+        // a gap of exactly the maximum counts as a pause.
+        clock.observe_at(None, 12, at(3000 + max_millis));
+        assert_close(clock.tick_seconds(None), 3.0);
+        assert_eq!(clock.game_time(None), Some(12));
+
+        // the pause is skipped, rather than the next tick being measured from before it.
+        clock.observe_at(None, 13, at(3000 + max_millis + 3000));
+        assert_close(clock.tick_seconds(None), 3.0);
+
+        // a pause before the second tick leaves no average at all.
+        clock.observe_at(Some("shard1".to_owned()), 10, at(0));
+        clock.observe_at(Some("shard1".to_owned()), 11, at(2 * max_millis));
+        assert_eq!(clock.tick_seconds(Some("shard1")), None);
+        assert_eq!(clock.game_time(Some("shard1")), Some(11));
+    }
+
+    #[test]
+    fn duplicate_and_older_ticks_are_ignored() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+
+        // every subscribed room sends an update for the same tick.
+        clock.observe_at(None, 10, at(2000));
+        assert_eq!(clock.shard(None).unwrap().received, at(0));
+
+        clock.observe_at(None, 9, at(2500));
+        assert_eq!(clock.game_time(None), Some(10));
+        assert_eq!(clock.shard(None).unwrap().received, at(0));
+        assert_eq!(clock.tick_seconds(None), None);
+
+        // the next tick is measured from when its previous tick was first seen.
+        clock.observe_at(None, 11, at(3000));
+        assert_close(clock.tick_seconds(None), 3.0);
+    }
+
+    #[test]
+    fn instant_ticks_are_not_averaged() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(1000));
+        clock.observe_at(None, 11, at(1000));
+
+        assert_eq!(clock.game_time(None), Some(11));
+        assert_eq!(clock.tick_seconds(None), None);
+    }
+
+    #[test]
+    fn tick_progress_is_clamped() {
+        let mut clock = TickClock::new();
+        clock.observe_at(None, 10, at(0));
+        clock.observe_at(None, 11, at(2000));
+        let shard = clock.shard(None).unwrap();
+
+        assert_close(shard.tick_progress(at(1000)), 0.0);
+        assert_close(shard.tick_progress(at(2000)), 0.0);
+        assert_close(shard.tick_progress(at(2500)), 0.25);
+        assert_close(shard.tick_progress(at(3000)), 0.5);
+        assert_close(shard.tick_progress(at(4000)), 1.0);
+        // the next tick is late.
+        assert_close(shard.tick_progress(at(10_000)), 1.0);
+    }
+
+    #[test]
+    fn shards_are_tracked_separately() {
+        let mut clock = TickClock::new();
+        clock.observe_at(Some("shard0".to_owned()), 100, at(0));
+        clock.observe_at(Some("shard1".to_owned()), 5, at(0));
+        clock.observe_at(Some("shard0".to_owned()), 101, at(3000));
+
+        assert_eq!(clock.game_time(Some("shard0")), Some(101));
+        assert_eq!(clock.game_time(Some("shard1")), Some(5));
+        assert_eq!(clock.game_time(None), None);
+        assert_close(clock.tick_seconds(Some("shard0")), 3.0);
+        assert_eq!(clock.tick_seconds(Some("shard1")), None);
+
+        clock.clear();
+        assert_eq!(clock.game_time(Some("shard0")), None);
+    }
+}
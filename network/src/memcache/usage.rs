@@ -0,0 +1,84 @@
+use std::collections::vec_deque;
+
+use time;
+
+use super::bounded::BoundedQueue;
+
+/// Number of usage samples kept by default, one for each tick.
+pub const DEFAULT_USAGE_SAMPLES: usize = 300;
+
+/// Resource usage during a single tick.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UsageSample {
+    /// CPU used, in milliseconds.
+    pub cpu: u32,
+    /// Size of `Memory`, in bytes.
+    pub memory: u32,
+    /// Time the sample was received.
+    pub received: time::Timespec,
+}
+
+/// Rolling window of the most recent usage samples.
+#[derive(Clone, Debug)]
+pub struct UsageHistory {
+    samples: BoundedQueue<UsageSample>,
+}
+
+impl Default for UsageHistory {
+    fn default() -> Self {
+        UsageHistory::with_capacity(DEFAULT_USAGE_SAMPLES)
+    }
+}
+
+impl UsageHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        UsageHistory {
+            samples: BoundedQueue::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a sample, removing the oldest sample if the window is full.
+    pub fn push(&mut self, cpu: u32, memory: u32) {
+        self.samples.push(UsageSample {
+            cpu: cpu,
+            memory: memory,
+            received: time::get_time(),
+        });
+    }
+
+    /// Iterates over samples from oldest to newest.
+    pub fn iter(&self) -> vec_deque::Iter<UsageSample> {
+        self.samples.iter()
+    }
+
+    /// Gets the most recent sample.
+    pub fn last(&self) -> Option<&UsageSample> {
+        self.samples.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.capacity()
+    }
+
+    /// Sets the number of samples kept, removing the oldest samples if there are now too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.samples.set_capacity(capacity);
+    }
+}
+
+impl<'a> IntoIterator for &'a UsageHistory {
+    type Item = &'a UsageSample;
+    type IntoIter = vec_deque::Iter<'a, UsageSample>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -4,9 +4,35 @@ use std::fmt;
 
 use screeps_api::RoomName;
 
-use ConnectionSettings;
+use {ConnectionSettings, Url};
+use code::{ActiveBranch, CodeModules};
+use user::UserQuery;
 use self::Request::*;
 
+/// Identifier for a single request sent through a `ScreepsConnection`.
+///
+/// Events which are the direct result of a request carry the same identifier, see `NetworkEvent::request_id`.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Creates the identifier given to the first request made by a connection.
+    pub(crate) fn first() -> Self {
+        RequestId(0)
+    }
+
+    /// Gets the identifier following this one.
+    pub(crate) fn next(self) -> Self {
+        RequestId(self.0.wrapping_add(1))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
 /// Error for not being logged in, and trying to send a query requiring authentication.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct NotLoggedIn;
@@ -45,13 +71,18 @@ impl fmt::Debug for LoginDetails {
     }
 }
 
+/// A rectangle of rooms, including `start` and excluding `end`.
+///
+/// `start` is always the corner with the smallest coordinates, so a rectangle where `end` isn't larger on both
+/// axes contains no rooms.
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
-pub struct SelectedRooms {
+pub struct RoomRect {
     pub start: RoomName,
     pub end: RoomName,
 }
 
-impl SelectedRooms {
+impl RoomRect {
+    /// Creates the rectangle between two corners, which may be given in any order.
     #[inline]
     pub fn new(rooms: Range<RoomName>) -> Self {
         use std::cmp::{max, min};
@@ -65,69 +96,302 @@ impl SelectedRooms {
             y_coord: max(rooms.start.y_coord, rooms.end.y_coord),
         };
 
-        SelectedRooms {
+        RoomRect {
             start: start,
             end: end,
         }
     }
 
+    #[inline]
+    pub fn width(&self) -> i32 {
+        (self.end.x_coord - self.start.x_coord).max(0)
+    }
+
+    #[inline]
+    pub fn height(&self) -> i32 {
+        (self.end.y_coord - self.start.y_coord).max(0)
+    }
+
+    /// Number of rooms in the rectangle.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.width() as usize * self.height() as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
     #[inline]
     pub fn contains(&self, room: &RoomName) -> bool {
-        (self.start.x_coord < room.x_coord && room.x_coord < self.end.x_coord)
-            && (self.start.y_coord < room.y_coord && room.y_coord < self.end.y_coord)
+        (self.start.x_coord <= room.x_coord && room.x_coord < self.end.x_coord)
+            && (self.start.y_coord <= room.y_coord && room.y_coord < self.end.y_coord)
     }
-}
 
-impl IntoIterator for SelectedRooms {
-    type Item = RoomName;
-    type IntoIter = IterSelectedRooms;
+    /// The room in the middle of the rectangle, rounding towards `start` when there's no single middle room.
+    #[inline]
+    pub fn center(&self) -> RoomName {
+        self.start + (self.width() / 2, self.height() / 2)
+    }
 
-    fn into_iter(mut self) -> IterSelectedRooms {
-        if self.start.x_coord > self.end.x_coord {
-            ::std::mem::swap(&mut self.start.x_coord, &mut self.end.x_coord);
+    /// Gets the rooms in both rectangles, or None if they don't overlap.
+    pub fn intersection(&self, other: &RoomRect) -> Option<RoomRect> {
+        use std::cmp::{max, min};
+
+        let result = RoomRect {
+            start: RoomName {
+                x_coord: max(self.start.x_coord, other.start.x_coord),
+                y_coord: max(self.start.y_coord, other.start.y_coord),
+            },
+            end: RoomName {
+                x_coord: min(self.end.x_coord, other.end.x_coord),
+                y_coord: min(self.end.y_coord, other.end.y_coord),
+            },
+        };
+
+        if result.is_empty() {
+            None
+        } else {
+            Some(result)
         }
-        if self.start.y_coord > self.end.y_coord {
-            ::std::mem::swap(&mut self.start.x_coord, &mut self.end.x_coord);
+    }
+
+    /// Gets the rooms in this rectangle but not in `other`, as up to four non-overlapping rectangles.
+    pub fn difference(&self, other: &RoomRect) -> Vec<RoomRect> {
+        let overlap = match self.intersection(other) {
+            Some(overlap) => overlap,
+            None => {
+                return if self.is_empty() { Vec::new() } else { vec![*self] };
+            }
+        };
+
+        let corner = |x_coord, y_coord| RoomName {
+            x_coord: x_coord,
+            y_coord: y_coord,
+        };
+
+        // full-width strips above and below the overlap, then the remains of the overlap's rows to either side.
+        let pieces = [
+            RoomRect {
+                start: self.start,
+                end: corner(self.end.x_coord, overlap.start.y_coord),
+            },
+            RoomRect {
+                start: corner(self.start.x_coord, overlap.end.y_coord),
+                end: self.end,
+            },
+            RoomRect {
+                start: corner(self.start.x_coord, overlap.start.y_coord),
+                end: corner(overlap.start.x_coord, overlap.end.y_coord),
+            },
+            RoomRect {
+                start: corner(overlap.end.x_coord, overlap.start.y_coord),
+                end: corner(self.end.x_coord, overlap.end.y_coord),
+            },
+        ];
+
+        pieces.iter().filter(|rect| !rect.is_empty()).cloned().collect()
+    }
+
+    /// Iterates over the rooms starting at the center and circling outwards, clockwise.
+    pub fn spiral(&self) -> SpiralRooms {
+        SpiralRooms {
+            rect: *self,
+            center: self.center(),
+            radius: 0,
+            step: 0,
+            remaining: self.len(),
         }
+    }
+}
+
+impl IntoIterator for RoomRect {
+    type Item = RoomName;
+    type IntoIter = RoomRectIter;
 
-        IterSelectedRooms {
-            start_x: self.start.x_coord,
+    /// Iterates over the rooms row by row.
+    fn into_iter(self) -> RoomRectIter {
+        RoomRectIter {
+            rect: self,
             current: self.start,
-            end: self.end,
         }
     }
 }
 
-pub struct IterSelectedRooms {
-    start_x: i32,
+/// Iterator over the rooms in a `RoomRect`, row by row.
+#[derive(Clone, Debug)]
+pub struct RoomRectIter {
+    rect: RoomRect,
     current: RoomName,
-    end: RoomName,
 }
 
-impl Iterator for IterSelectedRooms {
+impl Iterator for RoomRectIter {
+    type Item = RoomName;
+
+    fn next(&mut self) -> Option<RoomName> {
+        if self.rect.is_empty() || self.current.y_coord >= self.rect.end.y_coord {
+            return None;
+        }
+
+        let item = self.current;
+        self.current.x_coord += 1;
+        if self.current.x_coord >= self.rect.end.x_coord {
+            self.current.x_coord = self.rect.start.x_coord;
+            self.current.y_coord += 1;
+        }
+        Some(item)
+    }
+}
+
+/// Iterator over the rooms in a `RoomRect`, from the center outwards. See `RoomRect::spiral`.
+#[derive(Clone, Debug)]
+pub struct SpiralRooms {
+    rect: RoomRect,
+    center: RoomName,
+    /// Distance of the current ring from the center, on the furthest axis.
+    radius: i32,
+    /// Position along the current ring, which has `8 * radius` rooms.
+    step: i32,
+    remaining: usize,
+}
+
+impl Iterator for SpiralRooms {
     type Item = RoomName;
 
     fn next(&mut self) -> Option<RoomName> {
-        match (
-            self.current.x_coord == self.end.x_coord,
-            self.current.y_coord == self.end.y_coord,
-        ) {
-            (false, _) => {
-                let item = self.current;
-                self.current.x_coord += 1;
-                Some(item)
+        while self.remaining > 0 {
+            let r = self.radius;
+            let s = self.step;
+            let (x, y) = if r == 0 {
+                (0, 0)
+            } else if s < 2 * r {
+                (s - r, -r)
+            } else if s < 4 * r {
+                (r, s - 3 * r)
+            } else if s < 6 * r {
+                (5 * r - s, r)
+            } else {
+                (-r, 7 * r - s)
+            };
+
+            self.step += 1;
+            if self.step >= (8 * r).max(1) {
+                self.radius += 1;
+                self.step = 0;
             }
-            (true, false) => {
-                let item = self.current;
-                self.current.y_coord += 1;
-                self.current.x_coord = self.start_x;
-                Some(item)
+
+            let room = self.center + (x, y);
+            if self.rect.contains(&room) {
+                self.remaining -= 1;
+                return Some(room);
             }
-            (true, true) => None,
         }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Any set of rooms, stored as non-overlapping rectangles.
+#[derive(Clone, Debug, Default)]
+pub struct RoomRegion {
+    rects: Vec<RoomRect>,
+}
+
+impl RoomRegion {
+    /// Creates an empty region.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rectangles making up this region. None of them overlap, and none are empty.
+    pub fn rects(&self) -> &[RoomRect] {
+        &self.rects
+    }
+
+    /// Number of rooms in the region.
+    pub fn len(&self) -> usize {
+        self.rects.iter().map(RoomRect::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn contains(&self, room: &RoomName) -> bool {
+        self.rects.iter().any(|rect| rect.contains(room))
+    }
+
+    /// Gets the rooms in either region.
+    pub fn union(&self, other: &RoomRegion) -> RoomRegion {
+        let mut result = self.clone();
+        result.rects.extend(other.difference(self).rects);
+        result
+    }
+
+    /// Gets the rooms in both regions.
+    pub fn intersection(&self, other: &RoomRegion) -> RoomRegion {
+        // the pieces can't overlap since neither side's rectangles overlap each other.
+        let rects = self.rects
+            .iter()
+            .flat_map(|a| other.rects.iter().filter_map(move |b| a.intersection(b)))
+            .collect();
+
+        RoomRegion { rects: rects }
+    }
+
+    /// Gets the rooms in this region but not in `other`.
+    pub fn difference(&self, other: &RoomRegion) -> RoomRegion {
+        let mut rects = self.rects.clone();
+        for removed in &other.rects {
+            rects = rects
+                .iter()
+                .flat_map(|rect| rect.difference(removed))
+                .collect();
+        }
+
+        RoomRegion { rects: rects }
+    }
+
+    /// Iterates over the rooms one rectangle at a time, each row by row.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = RoomName> + 'a {
+        self.rects.iter().flat_map(|&rect| rect)
+    }
+
+    /// Lists the rooms in order of distance from `center`, nearest first.
+    ///
+    /// Rooms the same distance away are in the same order `iter` gives them.
+    pub fn center_out(&self, center: RoomName) -> Vec<RoomName> {
+        let mut rooms = self.iter().collect::<Vec<_>>();
+        rooms.sort_by_key(|room| {
+            let x = room.x_coord - center.x_coord;
+            let y = room.y_coord - center.y_coord;
+            x * x + y * y
+        });
+        rooms
+    }
+}
+
+impl From<RoomRect> for RoomRegion {
+    fn from(rect: RoomRect) -> Self {
+        let rects = if rect.is_empty() { Vec::new() } else { vec![rect] };
+
+        RoomRegion { rects: rects }
+    }
+}
+
+impl PartialEq for RoomRegion {
+    /// Regions are equal if they contain the same rooms, however they're split up.
+    fn eq(&self, other: &RoomRegion) -> bool {
+        self.difference(other).is_empty() && other.difference(self).is_empty()
     }
 }
 
+impl Eq for RoomRegion {}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Request {
     Login,
@@ -136,8 +400,90 @@ pub enum Request {
     ChangeSettings { settings: Arc<ConnectionSettings> },
     Exit,
     RoomTerrain { room_name: RoomName },
-    SetMapSubscribes { rooms: SelectedRooms },
-    SetFocusRoom { room: Option<RoomName> },
+    /// Gets the number of rooms across a shard, or the connection's shard if `None`.
+    WorldSize { shard: Option<String> },
+    /// Gets the protocol and package versions of a server, which needn't be the one connected to.
+    ///
+    /// This doesn't need a login, so it can be used to describe a server before logging in to it.
+    ServerVersion { api_url: Url },
+    /// Gets the current game time of a shard, or the connection's shard if `None`.
+    GameTime { shard: Option<String> },
+    /// Gets whether each of `rooms` is open, closed, or in a novice or respawn area.
+    ///
+    /// Rooms are fetched in batches, but the result is sent as a single event.
+    RoomStatus {
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+    },
+    /// Gets the owner, reservation and sign of any number of rooms.
+    ///
+    /// Rooms are fetched in batches, but the result is sent as a single event.
+    MapStats {
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+    },
+    /// Looks up the profiles of users, in order. Users which don't exist are found as `None`.
+    ///
+    /// The server finds one user per call, so each user which isn't in the disk cache takes a call of its own.
+    FindUsers { queries: Vec<UserQuery> },
+    /// Subscribes to map view updates for exactly these rooms, unsubscribing from any others.
+    SetMapSubscribes { rooms: Vec<RoomName> },
+    /// Subscribes to full detail updates for exactly these rooms, unsubscribing from any others.
+    SetDetailRooms { rooms: Vec<RoomName> },
+    /// Holds a subscription to a websocket channel, such as `room:W1N1`.
+    ///
+    /// Subscriptions are reference counted and shared with the map and detail room subscriptions: each
+    /// `AcquireChannel` should be matched by a `ReleaseChannel`, and the channel is only unsubscribed from once
+    /// nothing holds it.
+    AcquireChannel { channel: String },
+    /// Releases a subscription held with `AcquireChannel`.
+    ReleaseChannel { channel: String },
+    /// Subscribes to live updates of a dot-separated `Memory` path, sent each tick as `MemoryWatch` events.
+    ///
+    /// Watches are reference counted: each `WatchMemory` should be matched by an `UnwatchMemory`.
+    WatchMemory { path: String },
+    /// Removes a watch added with `WatchMemory`.
+    UnwatchMemory { path: String },
+    /// Runs a console command. Output will arrive through console updates rather than as the result.
+    ConsoleCommand { shard: Option<String>, expression: String },
+    /// Loads previously run console commands from the disk cache.
+    ConsoleHistory,
+    /// Loads the last map views stored in the disk cache for any number of rooms. Rooms without a stored map view
+    /// are left out of the result.
+    MapViewSnapshots {
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+    },
+    /// Gets part of `Memory`. An empty path gets all of `Memory`.
+    GetMemory { shard: Option<String>, path: String },
+    /// Sets part of `Memory`. The value is JSON text, or empty to delete the path.
+    SetMemory {
+        shard: Option<String>,
+        path: String,
+        value: String,
+    },
+    /// Gets raw memory segments by ID, from 0 to 99.
+    GetMemorySegments {
+        shard: Option<String>,
+        segments: Vec<u8>,
+    },
+    /// Sets the contents of a raw memory segment.
+    SetMemorySegment {
+        shard: Option<String>,
+        segment: u8,
+        data: String,
+    },
+    /// Lists the user's code branches.
+    Branches,
+    /// Gets the modules in a code branch.
+    GetCode { branch: String },
+    /// Replaces the modules in a code branch, creating the branch if it doesn't exist.
+    UploadCode {
+        branch: String,
+        modules: Arc<CodeModules>,
+    },
+    /// Makes a branch the one running in the world or the simulation room.
+    SetActiveBranch { branch: String, target: ActiveBranch },
 }
 
 impl Request {
@@ -159,12 +505,143 @@ impl Request {
         }
     }
 
-    pub fn subscribe_map_view(rooms: SelectedRooms) -> Self {
-        SetMapSubscribes { rooms: rooms }
+    pub fn world_size(shard: Option<String>) -> Self {
+        WorldSize { shard: shard }
+    }
+
+    pub fn server_version(api_url: Url) -> Self {
+        ServerVersion { api_url: api_url }
+    }
+
+    pub fn game_time(shard: Option<String>) -> Self {
+        GameTime { shard: shard }
+    }
+
+    pub fn room_status(shard: Option<String>, rooms: Vec<RoomName>) -> Self {
+        RoomStatus {
+            shard: shard,
+            rooms: rooms,
+        }
+    }
+
+    pub fn map_stats(shard: Option<String>, rooms: Vec<RoomName>) -> Self {
+        MapStats {
+            shard: shard,
+            rooms: rooms,
+        }
+    }
+
+    pub fn find_users(queries: Vec<UserQuery>) -> Self {
+        FindUsers { queries: queries }
+    }
+
+    pub fn subscribe_map_view<I: IntoIterator<Item = RoomName>>(rooms: I) -> Self {
+        SetMapSubscribes {
+            rooms: rooms.into_iter().collect(),
+        }
     }
 
     pub fn focus_room(room_name: Option<RoomName>) -> Self {
-        SetFocusRoom { room: room_name }
+        SetDetailRooms {
+            rooms: room_name.into_iter().collect(),
+        }
+    }
+
+    pub fn detail_rooms(rooms: Vec<RoomName>) -> Self {
+        SetDetailRooms { rooms: rooms }
+    }
+
+    pub fn acquire_channel(channel: String) -> Self {
+        AcquireChannel { channel: channel }
+    }
+
+    pub fn release_channel(channel: String) -> Self {
+        ReleaseChannel { channel: channel }
+    }
+
+    pub fn watch_memory(path: String) -> Self {
+        WatchMemory { path: path }
+    }
+
+    pub fn unwatch_memory(path: String) -> Self {
+        UnwatchMemory { path: path }
+    }
+
+    /// Creates a console command request, run on the given shard or the connection's shard if `None`.
+    pub fn console_command(shard: Option<String>, expression: String) -> Self {
+        ConsoleCommand {
+            shard: shard,
+            expression: expression,
+        }
+    }
+
+    pub fn console_history() -> Self {
+        ConsoleHistory
+    }
+
+    pub fn map_view_snapshots(shard: Option<String>, rooms: Vec<RoomName>) -> Self {
+        MapViewSnapshots {
+            shard: shard,
+            rooms: rooms,
+        }
+    }
+
+    /// Creates a request for the `Memory` at a dot-separated path, such as `creeps.John`.
+    pub fn get_memory(shard: Option<String>, path: String) -> Self {
+        GetMemory {
+            shard: shard,
+            path: path,
+        }
+    }
+
+    /// Creates a request for the contents of any number of memory segments.
+    ///
+    /// Segments are fetched in batches, but the result is sent as a single event.
+    pub fn get_memory_segments(shard: Option<String>, segments: Vec<u8>) -> Self {
+        GetMemorySegments {
+            shard: shard,
+            segments: segments,
+        }
+    }
+
+    /// Creates a request setting the contents of a memory segment.
+    pub fn set_memory_segment(shard: Option<String>, segment: u8, data: String) -> Self {
+        SetMemorySegment {
+            shard: shard,
+            segment: segment,
+            data: data,
+        }
+    }
+
+    pub fn branches() -> Self {
+        Branches
+    }
+
+    pub fn get_code(branch: String) -> Self {
+        GetCode { branch: branch }
+    }
+
+    pub fn upload_code(branch: String, modules: CodeModules) -> Self {
+        UploadCode {
+            branch: branch,
+            modules: Arc::new(modules),
+        }
+    }
+
+    pub fn set_active_branch(branch: String, target: ActiveBranch) -> Self {
+        SetActiveBranch {
+            branch: branch,
+            target: target,
+        }
+    }
+
+    /// Creates a request setting the `Memory` at a dot-separated path.
+    pub fn set_memory(shard: Option<String>, path: String, value: &::serde_json::Value) -> Self {
+        SetMemory {
+            shard: shard,
+            path: path,
+            value: value.to_string(),
+        }
     }
 
     pub fn change_settings(settings: ConnectionSettings) -> Self {
@@ -173,3 +650,287 @@ impl Request {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use screeps_api::RoomName;
+
+    use super::{RoomRect, RoomRegion};
+
+    /// Number of random cases each property is checked against.
+    const CASES: usize = 500;
+
+    /// Small xorshift generator, so the properties are checked against the same cases every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Gets a number from `low` to `high`, inclusive.
+        fn range(&mut self, low: i32, high: i32) -> i32 {
+            low + (self.next() % (high - low + 1) as u64) as i32
+        }
+
+        fn room(&mut self) -> RoomName {
+            RoomName {
+                x_coord: self.range(-6, 6),
+                y_coord: self.range(-6, 6),
+            }
+        }
+
+        /// Gets a rectangle near the origin, which is sometimes empty.
+        fn rect(&mut self) -> RoomRect {
+            let start = self.room();
+            let end = start + (self.range(0, 6), self.range(0, 6));
+            RoomRect::new(start..end)
+        }
+
+        fn region(&mut self) -> RoomRegion {
+            let mut region = RoomRegion::new();
+            for _ in 0..self.range(0, 4) {
+                region = region.union(&self.rect().into());
+            }
+            region
+        }
+    }
+
+    fn rng() -> Rng {
+        Rng(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Every room which could be in a generated rectangle, and a border around them.
+    fn all_rooms() -> Vec<RoomName> {
+        let mut rooms = Vec::new();
+        for x_coord in -8..14 {
+            for y_coord in -8..14 {
+                rooms.push(RoomName {
+                    x_coord: x_coord,
+                    y_coord: y_coord,
+                });
+            }
+        }
+        rooms
+    }
+
+    fn room_set<I: IntoIterator<Item = RoomName>>(rooms: I) -> HashSet<RoomName> {
+        rooms.into_iter().collect()
+    }
+
+    /// Checks that none of a region's rectangles are empty, and that no two overlap.
+    fn assert_well_formed(region: &RoomRegion) {
+        let rects = region.rects();
+        for (idx, a) in rects.iter().enumerate() {
+            assert!(!a.is_empty(), "empty rect {:?} in {:?}", a, region);
+            for b in &rects[idx + 1..] {
+                assert!(a.intersection(b).is_none(), "{:?} and {:?} overlap in {:?}", a, b, region);
+            }
+        }
+    }
+
+    #[test]
+    fn rect_contains_matches_iter() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let rect = rng.rect();
+            let rooms = rect.into_iter().collect::<Vec<_>>();
+            assert_eq!(rooms.len(), rect.len());
+            assert_eq!(room_set(rooms.clone()).len(), rooms.len(), "duplicate rooms in {:?}", rect);
+            let rooms = room_set(rooms);
+            for room in all_rooms() {
+                assert_eq!(rect.contains(&room), rooms.contains(&room), "{} in {:?}", room, rect);
+            }
+        }
+    }
+
+    #[test]
+    fn rect_includes_start_and_excludes_end() {
+        let rect = RoomRect::new(
+            RoomName {
+                x_coord: -2,
+                y_coord: 1,
+            }..RoomName {
+                x_coord: 3,
+                y_coord: 3,
+            },
+        );
+
+        assert!(rect.contains(&rect.start));
+        assert!(rect.contains(&(rect.end + (-1, -1))));
+        assert!(!rect.contains(&rect.end));
+        assert!(!rect.contains(&(rect.end + (-1, 0))));
+        assert!(!rect.contains(&(rect.end + (0, -1))));
+        assert!(!rect.contains(&(rect.start + (-1, 0))));
+        assert!(!rect.contains(&(rect.start + (0, -1))));
+    }
+
+    #[test]
+    fn rect_keeps_axes_apart() {
+        // five rooms wide and two high: swapping x and y would include (0, 4) and exclude (4, 0).
+        let rect = RoomRect::new(
+            RoomName {
+                x_coord: 0,
+                y_coord: 0,
+            }..RoomName {
+                x_coord: 5,
+                y_coord: 2,
+            },
+        );
+
+        assert_eq!(rect.width(), 5);
+        assert_eq!(rect.height(), 2);
+        assert!(rect.contains(&RoomName {
+            x_coord: 4,
+            y_coord: 1,
+        }));
+        assert!(!rect.contains(&RoomName {
+            x_coord: 1,
+            y_coord: 4,
+        }));
+
+        let rooms = rect.into_iter().collect::<Vec<_>>();
+        assert_eq!(rooms.len(), 10);
+        assert_eq!(
+            rooms[1],
+            RoomName {
+                x_coord: 1,
+                y_coord: 0,
+            }
+        );
+        assert_eq!(
+            rooms[5],
+            RoomName {
+                x_coord: 0,
+                y_coord: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn rect_new_orders_corners() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = rng.room();
+            let b = rng.room();
+            assert_eq!(RoomRect::new(a..b), RoomRect::new(b..a));
+        }
+    }
+
+    #[test]
+    fn rect_intersection_and_difference() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = rng.rect();
+            let b = rng.rect();
+
+            let intersection = a.intersection(&b);
+            if let Some(rect) = intersection {
+                assert!(!rect.is_empty());
+            }
+            let difference = a.difference(&b);
+            assert!(difference.len() <= 4);
+
+            let difference = RoomRegion { rects: difference };
+            assert_well_formed(&difference);
+            for room in all_rooms() {
+                let in_a = a.contains(&room);
+                let in_b = b.contains(&room);
+                assert_eq!(intersection.map_or(false, |rect| rect.contains(&room)), in_a && in_b);
+                assert_eq!(difference.contains(&room), in_a && !in_b);
+            }
+        }
+    }
+
+    #[test]
+    fn spiral_yields_every_room_once() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let rect = rng.rect();
+            let spiral = rect.spiral().collect::<Vec<_>>();
+            assert_eq!(spiral.len(), rect.len());
+            assert_eq!(room_set(spiral.clone()), room_set(rect));
+            if !rect.is_empty() {
+                assert_eq!(spiral[0], rect.center());
+            }
+        }
+    }
+
+    #[test]
+    fn region_operations_match_sets() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = rng.region();
+            let b = rng.region();
+            assert_well_formed(&a);
+            assert_well_formed(&b);
+
+            let union = a.union(&b);
+            let intersection = a.intersection(&b);
+            let difference = a.difference(&b);
+            assert_well_formed(&union);
+            assert_well_formed(&intersection);
+            assert_well_formed(&difference);
+
+            for room in all_rooms() {
+                let in_a = a.contains(&room);
+                let in_b = b.contains(&room);
+                assert_eq!(union.contains(&room), in_a || in_b);
+                assert_eq!(intersection.contains(&room), in_a && in_b);
+                assert_eq!(difference.contains(&room), in_a && !in_b);
+            }
+        }
+    }
+
+    #[test]
+    fn region_len_matches_iter() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let region = rng.region();
+            let rooms = region.iter().collect::<Vec<_>>();
+            assert_eq!(rooms.len(), region.len());
+            assert_eq!(room_set(rooms.clone()).len(), rooms.len());
+            let rooms = room_set(rooms);
+            for room in all_rooms() {
+                assert_eq!(region.contains(&room), rooms.contains(&room));
+            }
+        }
+    }
+
+    #[test]
+    fn center_out_yields_every_room_once_nearest_first() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let region = rng.region();
+            let center = rng.room();
+            let rooms = region.center_out(center);
+            assert_eq!(rooms.len(), region.len());
+            assert_eq!(room_set(rooms.clone()), room_set(region.iter()));
+
+            let distance = |room: &RoomName| {
+                let x = room.x_coord - center.x_coord;
+                let y = room.y_coord - center.y_coord;
+                x * x + y * y
+            };
+            for pair in rooms.windows(2) {
+                assert!(distance(&pair[0]) <= distance(&pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn region_equality_ignores_split() {
+        let mut rng = rng();
+        for _ in 0..CASES {
+            let a = rng.region();
+            let b = rng.region();
+            assert_eq!(a.union(&b), b.union(&a));
+            assert_eq!(a.union(&a), a);
+        }
+    }
+}
@@ -3,25 +3,46 @@ use std::rc::Rc;
 use std::cell::{Ref, RefCell};
 use std::ops::Deref;
 use std::sync::Arc;
+use std::collections::HashMap;
 
 use std::sync::mpsc::Sender as StdSender;
 use futures::sync::mpsc::Sender as BoundedFuturesSender;
 
 use futures::{future, Future, Sink};
+use futures::future::Loop;
 use tokio_core::reactor::{Handle, Timeout};
-use hyper::StatusCode;
+use hyper::{Method, StatusCode};
+use serde_json::Value;
 
-use screeps_api::{self, TokenStorage};
+use screeps_api::{self, RoomName, TokenStorage};
 
 use hyper;
+use time;
 
 use event::NetworkEvent;
+use request::RequestId;
+use user::UserQuery;
 
 use diskcache;
 use {ConnectionSettings, Notify};
 
-use super::types::HttpRequest;
-use super::utils;
+use super::types::{HttpRequest, Tagged};
+use super::{raw, utils};
+
+/// Maximum number of memory segments fetched in a single call.
+const MAX_SEGMENTS_PER_CALL: usize = 10;
+/// Most rooms to ask for the map stats of in a single call.
+const MAX_MAP_STATS_PER_CALL: usize = 100;
+
+/// Map stats in the disk cache are used instead of fetching them again for this long.
+fn map_stats_fresh_for() -> time::Duration {
+    time::Duration::minutes(10)
+}
+
+/// User profiles in the disk cache are used instead of fetching them again for this long.
+fn users_fresh_for() -> time::Duration {
+    time::Duration::hours(1)
+}
 
 pub struct Executor<N, C, H, T> {
     pub handle: Handle,
@@ -30,6 +51,8 @@ pub struct Executor<N, C, H, T> {
     pub executor_return: BoundedFuturesSender<Executor<N, C, H, T>>,
     pub settings: Rc<RefCell<Arc<ConnectionSettings>>>,
     pub client: screeps_api::Api<C, H, T>,
+    /// HTTP client shared with `client`, for endpoints `screeps_api` does not support.
+    pub raw_client: H,
     pub disk_cache: diskcache::Cache,
 }
 
@@ -59,11 +82,12 @@ impl<N, C, H, T> Executor<N, C, H, T>
 where
     C: hyper::client::Connect,
     H: screeps_api::HyperClient<C> + 'static + Clone,
-    T: TokenStorage,
+    T: TokenStorage + Clone + 'static,
     N: Notify,
 {
     fn exec_network(
         self,
+        id: RequestId,
         request: HttpRequest,
     ) -> Box<Future<Item = (Self, HttpRequest, NetworkEvent), Error = HttpExecError<N, C, H, T>> + 'static> {
         match request {
@@ -76,6 +100,7 @@ where
                         self.client.login(&*settings.username, &*settings.password)
                     }.then(move |result| {
                         let event = NetworkEvent::Login {
+                            id: id,
                             username: username,
                             result: result.map(|logged_in| logged_in.return_to(&self.client.tokens)),
                         };
@@ -85,22 +110,26 @@ where
                 )
             }
             HttpRequest::MyInfo => {
-                let execute = |executor: Self| match executor.client.my_info() {
+                let execute = move |executor: Self| match executor.client.my_info() {
                     Ok(future) => Ok(future.then(move |result| {
                         future::ok((
                             executor,
                             HttpRequest::MyInfo,
-                            NetworkEvent::MyInfo { result: result },
+                            NetworkEvent::MyInfo {
+                                id: id,
+                                result: result,
+                            },
                         ))
                     })),
                     Err(e) => Err((executor, e)),
                 };
 
-                let handle_err = |executor: Self, login_error| {
+                let handle_err = move |executor: Self, login_error| {
                     future::ok((
                         executor,
                         HttpRequest::MyInfo,
                         NetworkEvent::MyInfo {
+                            id: id,
                             result: Err(login_error),
                         },
                     ))
@@ -113,6 +142,7 @@ where
                     self,
                     HttpRequest::ShardList,
                     NetworkEvent::ShardList {
+                        id: id,
                         result: match result {
                             Ok(v) => Ok(Some(v)),
                             Err(e) => match *e.kind() {
@@ -171,6 +201,7 @@ where
                                 room_name: room_name,
                             },
                             NetworkEvent::RoomTerrain {
+                                id: id,
                                 room_name: room_name,
                                 result: result,
                             },
@@ -178,6 +209,534 @@ where
                     })
                 }))
             }
+            HttpRequest::WorldSize { shard } => {
+                let request = HttpRequest::WorldSize {
+                    shard: shard.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let query = query_shard.map(|query_shard| vec![("shard", query_shard)]).unwrap_or_default();
+
+                self.exec_raw(request, Method::Get, "game/world-size", query, None, move |result| {
+                    NetworkEvent::WorldSize {
+                        id: id,
+                        shard: shard,
+                        result: result.and_then(|json| raw::world_size(&json)),
+                    }
+                })
+            }
+            HttpRequest::ServerVersion { api_url } => {
+                let request = HttpRequest::ServerVersion {
+                    api_url: api_url.clone(),
+                };
+
+                Box::new(
+                    raw::call_anonymous(&self.raw_client, &api_url, "version").then(move |result| {
+                        let event = NetworkEvent::ServerVersion {
+                            id: id,
+                            api_url: api_url,
+                            result: result.and_then(|json| raw::server_version(&json)),
+                        };
+
+                        future::ok((self, request, event))
+                    }),
+                )
+            }
+            HttpRequest::GameTime { shard } => {
+                let request = HttpRequest::GameTime {
+                    shard: shard.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let query = query_shard.map(|query_shard| vec![("shard", query_shard)]).unwrap_or_default();
+
+                self.exec_raw(request, Method::Get, "game/time", query, None, move |result| {
+                    NetworkEvent::GameTime {
+                        id: id,
+                        shard: shard,
+                        result: result.and_then(|json| raw::game_time(&json)),
+                    }
+                })
+            }
+            HttpRequest::RoomStatus { shard, rooms } => {
+                let request = HttpRequest::RoomStatus {
+                    shard: shard.clone(),
+                    rooms: rooms.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                // map stats carry each room's status along with the stat asked for.
+                let now = time::get_time();
+
+                Box::new(
+                    self.fetch_map_stats(query_shard, rooms.clone(), move |json| raw::room_statuses(json, now))
+                        .map(move |(executor, result)| {
+                            (
+                                executor,
+                                request,
+                                NetworkEvent::RoomStatus {
+                                    id: id,
+                                    shard: shard,
+                                    rooms: rooms,
+                                    result: result,
+                                },
+                            )
+                        }),
+                )
+            }
+            HttpRequest::MapStats { shard, rooms } => {
+                let request = HttpRequest::MapStats {
+                    shard: shard.clone(),
+                    rooms: rooms.clone(),
+                };
+                let server = self.client.url.as_str().to_owned();
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let cached = self.disk_cache.get_map_stats(
+                    &server,
+                    query_shard.as_ref().map(|s| &**s),
+                    &rooms,
+                    map_stats_fresh_for(),
+                );
+
+                Box::new(cached.then(move |cache_result| {
+                    let mut found = cache_result.unwrap_or_else(|e| {
+                        warn!("error occurred fetching map stats cache: {:?}", e);
+                        HashMap::new()
+                    });
+                    let missing = rooms
+                        .iter()
+                        .filter(|room_name| !found.contains_key(room_name))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    self.fetch_map_stats(query_shard.clone(), missing, raw::map_stats)
+                        .map(move |(executor, result)| {
+                            let result = result.map(|fetched| {
+                                if !fetched.is_empty() {
+                                    executor.handle.spawn(
+                                        executor
+                                            .disk_cache
+                                            .set_map_stats(&server, query_shard.as_ref().map(|s| &**s), &fetched)
+                                            .then(|result| {
+                                                if let Err(e) = result {
+                                                    warn!("error occurred storing to map stats cache: {:?}", e);
+                                                }
+                                                Ok(())
+                                            }),
+                                    );
+                                }
+                                found.extend(fetched);
+                                found
+                            });
+
+                            (
+                                executor,
+                                request,
+                                NetworkEvent::MapStats {
+                                    id: id,
+                                    shard: shard,
+                                    rooms: rooms,
+                                    result: result,
+                                },
+                            )
+                        })
+                }))
+            }
+            HttpRequest::FindUsers { queries } => {
+                let request = HttpRequest::FindUsers {
+                    queries: queries.clone(),
+                };
+                let server = self.client.url.as_str().to_owned();
+                let ids = queries
+                    .iter()
+                    .filter_map(|query| match *query {
+                        UserQuery::Id(ref id) => Some(id.clone()),
+                        UserQuery::Username(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                let cached = self.disk_cache.get_users(&server, &ids, users_fresh_for());
+
+                Box::new(cached.then(move |cache_result| {
+                    let cached = cache_result.unwrap_or_else(|e| {
+                        warn!("error occurred fetching user cache: {:?}", e);
+                        HashMap::new()
+                    });
+
+                    // users asked for more than once are only looked up once.
+                    let mut found = HashMap::new();
+                    let mut missing = Vec::new();
+                    for query in &queries {
+                        if found.contains_key(query) || missing.contains(query) {
+                            continue;
+                        }
+                        let cached_user = match *query {
+                            UserQuery::Id(ref id) => cached.get(id),
+                            UserQuery::Username(_) => None,
+                        };
+                        match cached_user {
+                            Some(user) => {
+                                found.insert(query.clone(), Some(user.clone()));
+                            }
+                            None => missing.push(query.clone()),
+                        }
+                    }
+
+                    // the server only finds one user per call. each call is made in turn, since each needs the
+                    // login token.
+                    let fetch_all = future::loop_fn(
+                        (self, missing.into_iter(), found, Vec::new()),
+                        move |(executor, mut remaining, mut found, mut fetched)| {
+                            let query = match remaining.next() {
+                                Some(query) => query,
+                                None => {
+                                    let done = future::ok(Loop::Break((executor, Ok((found, fetched)))));
+                                    return Box::new(done) as Box<Future<Item = _, Error = _>>;
+                                }
+                            };
+                            let param = match query {
+                                UserQuery::Id(ref id) => ("id", id.clone()),
+                                UserQuery::Username(ref username) => ("username", username.clone()),
+                            };
+
+                            Box::new(
+                                executor
+                                    .call_raw(Method::Get, "user/find", vec![param], None)
+                                    .map(move |(executor, result)| {
+                                        match result.and_then(|json| raw::user_profile(&json)) {
+                                            Ok(user) => {
+                                                fetched.push(user.clone());
+                                                found.insert(query, Some(user));
+                                            }
+                                            Err(ref e) if raw::is_user_not_found(e) => {
+                                                found.insert(query, None);
+                                            }
+                                            Err(e) => return Loop::Break((executor, Err(e))),
+                                        }
+                                        Loop::Continue((executor, remaining, found, fetched))
+                                    }),
+                            ) as Box<Future<Item = _, Error = _>>
+                        },
+                    );
+
+                    fetch_all.map(move |(executor, result)| {
+                        let result = result.map(|(found, fetched)| {
+                            if !fetched.is_empty() {
+                                executor.handle.spawn(executor.disk_cache.set_users(&server, &fetched).then(
+                                    |result| {
+                                        if let Err(e) = result {
+                                            warn!("error occurred storing to user cache: {:?}", e);
+                                        }
+                                        Ok(())
+                                    },
+                                ));
+                            }
+                            queries
+                                .iter()
+                                .map(|query| found.get(query).cloned().unwrap_or(None))
+                                .collect()
+                        });
+
+                        (
+                            executor,
+                            request,
+                            NetworkEvent::Users {
+                                id: id,
+                                queries: queries,
+                                result: result,
+                            },
+                        )
+                    })
+                }))
+            }
+            HttpRequest::ConsoleCommand { shard, expression } => {
+                let request = HttpRequest::ConsoleCommand {
+                    shard: shard.clone(),
+                    expression: expression.clone(),
+                };
+                let body = json!({
+                    "expression": expression,
+                    "shard": shard.or_else(|| self.settings.borrow().shard.clone()),
+                });
+
+                Box::new(
+                    self.call_raw(Method::Post, "user/console", Vec::new(), Some(body))
+                        .map(move |(executor, result)| {
+                            if result.is_ok() {
+                                executor.handle.spawn(
+                                    executor
+                                        .disk_cache
+                                        .push_console_history(executor.client.url.as_ref(), expression.clone())
+                                        .then(|result| {
+                                            if let Err(e) = result {
+                                                warn!("error occurred storing console history: {:?}", e);
+                                            }
+                                            Ok(())
+                                        }),
+                                );
+                            }
+
+                            let event = NetworkEvent::ConsoleCommand {
+                                id: id,
+                                expression: expression,
+                                result: result.map(|_| ()),
+                            };
+                            (executor, request, event)
+                        }),
+                )
+            }
+            HttpRequest::ConsoleHistory => {
+                let cache_req = self.disk_cache.get_console_history(self.client.url.as_ref());
+
+                Box::new(cache_req.then(move |result| {
+                    let history = result.unwrap_or_else(|e| {
+                        warn!("error occurred fetching console history from cache: {:?}", e);
+                        Vec::new()
+                    });
+
+                    future::ok((
+                        self,
+                        HttpRequest::ConsoleHistory,
+                        NetworkEvent::ConsoleHistory {
+                            id: id,
+                            history: history,
+                        },
+                    ))
+                }))
+            }
+            HttpRequest::MapViewSnapshots { shard, rooms } => {
+                let request = HttpRequest::MapViewSnapshots {
+                    shard: shard.clone(),
+                    rooms: rooms.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let cache_req = self.disk_cache.get_map_views(
+                    self.client.url.as_ref(),
+                    query_shard.as_ref().map(|s| &**s),
+                    &rooms,
+                );
+
+                Box::new(cache_req.then(move |result| {
+                    let views = result.unwrap_or_else(|e| {
+                        warn!("error occurred fetching map views from cache: {:?}", e);
+                        HashMap::new()
+                    });
+
+                    future::ok((
+                        self,
+                        request,
+                        NetworkEvent::MapViewSnapshots {
+                            id: id,
+                            shard: shard,
+                            rooms: rooms,
+                            views: views,
+                        },
+                    ))
+                }))
+            }
+            HttpRequest::GetMemory { shard, path } => {
+                let request = HttpRequest::GetMemory {
+                    shard: shard.clone(),
+                    path: path.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let mut query = vec![("path", path.clone())];
+                if let Some(query_shard) = query_shard {
+                    query.push(("shard", query_shard));
+                }
+
+                self.exec_raw(request, Method::Get, "user/memory", query, None, move |result| {
+                    NetworkEvent::Memory {
+                        id: id,
+                        shard: shard,
+                        path: path,
+                        result: result.and_then(|json| raw::memory_data(&json)),
+                    }
+                })
+            }
+            HttpRequest::SetMemory { shard, path, value } => {
+                let request = HttpRequest::SetMemory {
+                    shard: shard.clone(),
+                    path: path.clone(),
+                    value: value.clone(),
+                };
+                let parsed = if value.is_empty() {
+                    Ok(Value::Null)
+                } else {
+                    ::serde_json::from_str::<Value>(&value)
+                };
+                let parsed = match parsed {
+                    Ok(v) => v,
+                    Err(e) => {
+                        let event = NetworkEvent::MemorySet {
+                            id: id,
+                            shard: shard,
+                            path: path,
+                            result: Err(e.into()),
+                        };
+                        return Box::new(future::ok((self, request, event)));
+                    }
+                };
+                let body = json!({
+                    "path": path,
+                    "value": parsed,
+                    "shard": shard.clone().or_else(|| self.settings.borrow().shard.clone()),
+                });
+
+                self.exec_raw(request, Method::Post, "user/memory", Vec::new(), Some(body), move |result| {
+                    NetworkEvent::MemorySet {
+                        id: id,
+                        shard: shard,
+                        path: path,
+                        result: result.map(|_| ()),
+                    }
+                })
+            }
+            HttpRequest::GetMemorySegments { shard, segments } => {
+                let request = HttpRequest::GetMemorySegments {
+                    shard: shard.clone(),
+                    segments: segments.clone(),
+                };
+                let query_shard = shard.clone().or_else(|| self.settings.borrow().shard.clone());
+                let chunks = segments
+                    .chunks(MAX_SEGMENTS_PER_CALL)
+                    .map(|chunk| chunk.to_vec())
+                    .collect::<Vec<_>>();
+
+                // fetch each batch in turn, since each call needs the login token.
+                let fetch_all = future::loop_fn(
+                    (self, chunks.into_iter(), Vec::with_capacity(segments.len())),
+                    move |(executor, mut chunks, mut fetched)| match chunks.next() {
+                        Some(chunk) => {
+                            let ids = chunk.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+                            let mut query = vec![("segment", ids)];
+                            if let Some(ref query_shard) = query_shard {
+                                query.push(("shard", query_shard.clone()));
+                            }
+
+                            Box::new(
+                                executor
+                                    .call_raw(Method::Get, "user/memory-segment", query, None)
+                                    .map(move |(executor, result)| {
+                                        match result.and_then(|json| raw::segment_data(&json, &chunk)) {
+                                            Ok(data) => {
+                                                fetched.extend(data);
+                                                Loop::Continue((executor, chunks, fetched))
+                                            }
+                                            Err(e) => Loop::Break((executor, Err(e))),
+                                        }
+                                    }),
+                            ) as Box<Future<Item = _, Error = _>>
+                        }
+                        None => {
+                            let done = future::ok(Loop::Break((executor, Ok(fetched))));
+                            Box::new(done) as Box<Future<Item = _, Error = _>>
+                        }
+                    },
+                );
+
+                Box::new(fetch_all.map(move |(executor, result)| {
+                    (
+                        executor,
+                        request,
+                        NetworkEvent::MemorySegments {
+                            id: id,
+                            shard: shard,
+                            segments: segments,
+                            result: result,
+                        },
+                    )
+                }))
+            }
+            HttpRequest::SetMemorySegment { shard, segment, data } => {
+                let request = HttpRequest::SetMemorySegment {
+                    shard: shard.clone(),
+                    segment: segment,
+                    data: data.clone(),
+                };
+                let body = json!({
+                    "segment": segment,
+                    "data": data,
+                    "shard": shard.clone().or_else(|| self.settings.borrow().shard.clone()),
+                });
+
+                self.exec_raw(
+                    request,
+                    Method::Post,
+                    "user/memory-segment",
+                    Vec::new(),
+                    Some(body),
+                    move |result| NetworkEvent::MemorySegmentSet {
+                        id: id,
+                        shard: shard,
+                        segment: segment,
+                        result: result.map(|_| ()),
+                    },
+                )
+            }
+            HttpRequest::Branches => self.exec_raw(
+                HttpRequest::Branches,
+                Method::Get,
+                "user/branches",
+                Vec::new(),
+                None,
+                move |result| NetworkEvent::Branches {
+                    id: id,
+                    result: result.and_then(|json| raw::branch_list(&json)),
+                },
+            ),
+            HttpRequest::GetCode { branch } => {
+                let request = HttpRequest::GetCode {
+                    branch: branch.clone(),
+                };
+                let query = vec![("branch", branch.clone())];
+
+                self.exec_raw(request, Method::Get, "user/code", query, None, move |result| {
+                    NetworkEvent::Code {
+                        id: id,
+                        branch: branch,
+                        result: result.and_then(|json| raw::code_modules(&json)),
+                    }
+                })
+            }
+            HttpRequest::UploadCode { branch, modules } => {
+                let body = json!({
+                    "branch": branch,
+                    "modules": raw::code_modules_json(&modules),
+                });
+                let request = HttpRequest::UploadCode {
+                    branch: branch.clone(),
+                    modules: modules,
+                };
+
+                self.exec_raw(request, Method::Post, "user/code", Vec::new(), Some(body), move |result| {
+                    NetworkEvent::CodeUploaded {
+                        id: id,
+                        branch: branch,
+                        result: result.map(|_| ()),
+                    }
+                })
+            }
+            HttpRequest::SetActiveBranch { branch, target } => {
+                let request = HttpRequest::SetActiveBranch {
+                    branch: branch.clone(),
+                    target: target,
+                };
+                let body = json!({
+                    "branch": branch,
+                    "activeName": target.api_name(),
+                });
+
+                self.exec_raw(
+                    request,
+                    Method::Post,
+                    "user/set-active-branch",
+                    Vec::new(),
+                    Some(body),
+                    move |result| NetworkEvent::ActiveBranchSet {
+                        id: id,
+                        branch: branch,
+                        target: target,
+                        result: result.map(|_| ()),
+                    },
+                )
+            }
             HttpRequest::ChangeSettings { settings } => {
                 {
                     // TODO: this is full of possible race conditions if we have other
@@ -211,8 +770,116 @@ where
         }
     }
 
-    pub fn execute(self, request: HttpRequest) -> impl Future<Item = (), Error = ()> + 'static {
-        self.exec_network(request).then(
+    /// Executes a raw API call with `raw::call`, logging in first if there is no token.
+    fn call_raw(
+        self,
+        method: Method,
+        endpoint: &'static str,
+        query: Vec<(&'static str, String)>,
+        body: Option<Value>,
+    ) -> Box<Future<Item = (Self, Result<Value, screeps_api::Error>), Error = HttpExecError<N, C, H, T>> + 'static>
+    {
+        let execute = move |executor: Self| {
+            let call = {
+                let query = query
+                    .iter()
+                    .map(|&(key, ref value)| (key, &**value))
+                    .collect::<Vec<_>>();
+                raw::call(
+                    &executor.client,
+                    &executor.raw_client,
+                    method.clone(),
+                    endpoint,
+                    &query,
+                    body.clone(),
+                )
+            };
+
+            match call {
+                Ok(future) => Ok(future.then(move |result| future::ok((executor, result)))),
+                Err(e) => Err((executor, e)),
+            }
+        };
+
+        let handle_err = move |executor: Self, login_error| future::ok((executor, Err(login_error)));
+
+        utils::execute_or_login_and_execute(self, execute, handle_err)
+    }
+
+    /// Executes a raw API call, creating the event to send from the JSON response or the error.
+    /// Fetches map stats for `rooms` in batches of `MAX_MAP_STATS_PER_CALL`, reading each response with `parse`.
+    fn fetch_map_stats<S, F>(
+        self,
+        shard: Option<String>,
+        rooms: Vec<RoomName>,
+        parse: F,
+    ) -> Box<
+        Future<Item = (Self, Result<HashMap<RoomName, S>, screeps_api::Error>), Error = HttpExecError<N, C, H, T>>
+            + 'static,
+    >
+    where
+        S: 'static,
+        F: Fn(&Value) -> Result<HashMap<RoomName, S>, screeps_api::Error> + 'static,
+    {
+        let chunks = rooms
+            .chunks(MAX_MAP_STATS_PER_CALL)
+            .map(|chunk| chunk.to_vec())
+            .collect::<Vec<_>>();
+        let parse = Rc::new(parse);
+
+        // fetch each batch in turn, since each call needs the login token.
+        Box::new(future::loop_fn(
+            (self, chunks.into_iter(), HashMap::new()),
+            move |(executor, mut chunks, mut fetched)| match chunks.next() {
+                Some(chunk) => {
+                    let body = json!({
+                        "rooms": chunk.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                        "statName": "owner0",
+                        "shard": shard,
+                    });
+                    let parse = parse.clone();
+
+                    Box::new(
+                        executor
+                            .call_raw(Method::Post, "game/map-stats", Vec::new(), Some(body))
+                            .map(move |(executor, result)| match result.and_then(|json| parse(&json)) {
+                                Ok(stats) => {
+                                    fetched.extend(stats);
+                                    Loop::Continue((executor, chunks, fetched))
+                                }
+                                Err(e) => Loop::Break((executor, Err(e))),
+                            }),
+                    ) as Box<Future<Item = _, Error = _>>
+                }
+                None => {
+                    let done = future::ok(Loop::Break((executor, Ok(fetched))));
+                    Box::new(done) as Box<Future<Item = _, Error = _>>
+                }
+            },
+        ))
+    }
+
+    fn exec_raw<F>(
+        self,
+        request: HttpRequest,
+        method: Method,
+        endpoint: &'static str,
+        query: Vec<(&'static str, String)>,
+        body: Option<Value>,
+        make_event: F,
+    ) -> Box<Future<Item = (Self, HttpRequest, NetworkEvent), Error = HttpExecError<N, C, H, T>> + 'static>
+    where
+        F: FnOnce(Result<Value, screeps_api::Error>) -> NetworkEvent + 'static,
+    {
+        Box::new(
+            self.call_raw(method, endpoint, query, body)
+                .map(move |(executor, result)| (executor, request, make_event(result))),
+        )
+    }
+
+    pub fn execute(self, request: Tagged<HttpRequest>) -> impl Future<Item = (), Error = ()> + 'static {
+        let Tagged { id, request, reply } = request;
+        self.exec_network(id, request).then(
             move |result| -> Box<Future<Item = (), Error = ()> + 'static> {
                 let exec = match result {
                     Ok((exec, request, event)) => {
@@ -229,23 +896,29 @@ where
                                     return Box::new(timeout.then(|_| {
                                         debug!("5-second timeout finished.");
 
-                                        exec.execute(request)
+                                        exec.execute(Tagged::new(id, request, reply))
                                     }));
                                 }
                             }
                         }
 
-                        match exec.send_results.send(event) {
-                            Ok(_) => {
-                                trace!("successfully finished a request.");
-                                let result = exec.notify.wakeup();
-                                if let Err(_) = result {
-                                    warn!("failed to wake up main event loop after sending result successfully.")
+                        match reply {
+                            Some(reply) => match reply.send(event) {
+                                Ok(()) => trace!("successfully finished a request."),
+                                Err(_) => debug!("receiver for result of request {} was dropped.", id),
+                            },
+                            None => match exec.send_results.send(event) {
+                                Ok(_) => {
+                                    trace!("successfully finished a request.");
+                                    let result = exec.notify.wakeup();
+                                    if let Err(_) = result {
+                                        warn!("failed to wake up main event loop after sending result successfully.")
+                                    }
                                 }
-                            }
-                            Err(_) => {
-                                warn!("failed to send the result of a request.");
-                            }
+                                Err(_) => {
+                                    warn!("failed to send the result of a request.");
+                                }
+                            },
                         }
                         exec
                     }
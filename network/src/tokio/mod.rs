@@ -10,6 +10,7 @@ use std::sync::mpsc::Receiver as StdReceiver;
 use futures::sync::mpsc as futures_mpsc;
 use futures::sync::mpsc::UnboundedSender as FuturesSender;
 use futures::sync::mpsc::UnboundedReceiver as FuturesReceiver;
+use futures::sync::oneshot;
 
 use futures::{future, Future, Stream};
 use tokio_core::reactor::{Core, Remote};
@@ -19,7 +20,7 @@ use screeps_api::{self, ArcTokenStorage};
 use {hyper, hyper_tls, tokio_core};
 
 use event::NetworkEvent;
-use request::Request;
+use request::{Request, RequestId};
 use {ConnectionSettings, Notify, ScreepsConnection};
 use diskcache;
 
@@ -27,8 +28,13 @@ mod types;
 mod http;
 mod ws;
 mod utils;
+mod raw;
+mod stream;
+mod subscriptions;
 
-use self::types::{GenericRequest, HttpRequest, WebsocketRequest};
+pub use self::stream::{StreamHandler, TaskNotify};
+
+use self::types::{GenericRequest, HttpRequest, Tagged, WebsocketRequest};
 
 pub struct Handler<N> {
     /// Receiver and sender interacting with the current threaded handler.
@@ -45,21 +51,23 @@ pub struct Handler<N> {
     disk_cache: diskcache::Cache,
     /// Window proxy in case we need to restart handler thread.
     notify: N,
+    /// Id to give to the next request sent.
+    next_id: RequestId,
 }
 
 #[derive(Debug)]
 struct HandlerHandles {
     remote: Remote,
-    http_send: FuturesSender<HttpRequest>,
-    ws_send: FuturesSender<WebsocketRequest>,
+    http_send: FuturesSender<Tagged<HttpRequest>>,
+    ws_send: FuturesSender<Tagged<WebsocketRequest>>,
     recv: StdReceiver<NetworkEvent>,
 }
 
 impl HandlerHandles {
     fn new(
         remote: Remote,
-        http_send: FuturesSender<HttpRequest>,
-        ws_send: FuturesSender<WebsocketRequest>,
+        http_send: FuturesSender<Tagged<HttpRequest>>,
+        ws_send: FuturesSender<Tagged<WebsocketRequest>>,
         recv: StdReceiver<NetworkEvent>,
     ) -> Self {
         HandlerHandles {
@@ -70,21 +78,23 @@ impl HandlerHandles {
         }
     }
 
-    fn send(&mut self, request: Request) -> Result<(), Request> {
+    fn send(&mut self, request: Tagged<Request>) -> Result<(), Tagged<Request>> {
+        let Tagged { id, request, reply } = request;
         match request.into() {
             GenericRequest::Http(r) => self.http_send
-                .unbounded_send(r)
-                .map_err(|e| e.into_inner().into()),
+                .unbounded_send(Tagged::new(id, r, reply))
+                .map_err(|e| e.into_inner().map(Into::into)),
             GenericRequest::Websocket(r) => self.ws_send
-                .unbounded_send(r)
-                .map_err(|e| e.into_inner().into()),
+                .unbounded_send(Tagged::new(id, r, reply))
+                .map_err(|e| e.into_inner().map(Into::into)),
+            // the HTTP side of the request is the one which will produce a result, if any.
             GenericRequest::Both(hr, wr) => self.http_send
-                .unbounded_send(hr)
-                .map_err(|e| e.into_inner().into())
+                .unbounded_send(Tagged::new(id, hr, reply))
+                .map_err(|e| e.into_inner().map(Into::into))
                 .and_then(|()| {
                     self.ws_send
-                        .unbounded_send(wr)
-                        .map_err(|e| e.into_inner().into())
+                        .unbounded_send(Tagged::new(id, wr, None))
+                        .map_err(|e| e.into_inner().map(Into::into))
                 }),
         }
     }
@@ -100,8 +110,15 @@ impl<N> Handler<N> {
             // TODO: handle this gracefully
             disk_cache: diskcache::Cache::load().expect("loading the disk cache failed."),
             notify: notify,
+            next_id: RequestId::first(),
         }
     }
+
+    fn next_id(&mut self) -> RequestId {
+        let id = self.next_id;
+        self.next_id = id.next();
+        id
+    }
 }
 
 impl<N: Notify> Handler<N> {
@@ -149,8 +166,20 @@ impl<N: Notify> Handler<N> {
     }
 }
 
-impl<N: Notify> ScreepsConnection for Handler<N> {
-    fn send(&mut self, request: Request) {
+impl<N: Notify> Handler<N> {
+    /// Sends a request, and returns a receiver which will resolve with the event resulting from it.
+    ///
+    /// The resulting event is sent only to the returned receiver, and will not be returned from `poll()`.
+    /// Requests which do not directly produce an event, such as subscription changes, will resolve
+    /// to `Canceled` once they have been processed.
+    pub fn send_with_response(&mut self, request: Request) -> (RequestId, oneshot::Receiver<NetworkEvent>) {
+        let id = self.next_id();
+        let (reply_send, reply_recv) = oneshot::channel();
+        self.send_tagged(Tagged::new(id, request, Some(reply_send)));
+        (id, reply_recv)
+    }
+
+    fn send_tagged(&mut self, request: Tagged<Request>) {
         // TODO: find out how to get panic info from the threaded thread, and report that we had to reconnect!
         let request_retry = match self.handles {
             Some(ref mut handles) => match handles.send(request) {
@@ -169,6 +198,14 @@ impl<N: Notify> ScreepsConnection for Handler<N> {
                 .expect("expected freshly started handler to still be running");
         }
     }
+}
+
+impl<N: Notify> ScreepsConnection for Handler<N> {
+    fn send(&mut self, request: Request) -> RequestId {
+        let id = self.next_id();
+        self.send_tagged(Tagged::new(id, request, None));
+        id
+    }
 
     fn poll(&mut self) -> Option<NetworkEvent> {
         let (evt, reset) = match self.handles {
@@ -193,13 +230,14 @@ impl<N> fmt::Debug for Handler<N> {
             .field("settings", &self.settings)
             .field("tokens", &self.tokens)
             .field("notify", &"<non-debug>")
+            .field("next_id", &self.next_id)
             .finish()
     }
 }
 
 struct ThreadedHandler<N> {
-    http_recv: FuturesReceiver<HttpRequest>,
-    ws_recv: FuturesReceiver<WebsocketRequest>,
+    http_recv: FuturesReceiver<Tagged<HttpRequest>>,
+    ws_recv: FuturesReceiver<Tagged<WebsocketRequest>>,
     send: StdSender<NetworkEvent>,
     notify: N,
     settings: Arc<ConnectionSettings>,
@@ -208,8 +246,8 @@ struct ThreadedHandler<N> {
 }
 impl<N: Notify> ThreadedHandler<N> {
     fn new(
-        http_recv: FuturesReceiver<HttpRequest>,
-        ws_recv: FuturesReceiver<WebsocketRequest>,
+        http_recv: FuturesReceiver<Tagged<HttpRequest>>,
+        ws_recv: FuturesReceiver<Tagged<WebsocketRequest>>,
         send: StdSender<NetworkEvent>,
         notify: N,
         tokens: ArcTokenStorage,
@@ -273,10 +311,12 @@ impl<N: Notify> ThreadedHandler<N> {
             )
             .build(&handle);
 
+        let raw_client = hyper.clone();
+
         let mut client = screeps_api::Api::with_url_and_tokens(hyper, settings_rc.borrow().api_url.clone(), tokens)
             .expect("expected already parsed URL to parse as URL");
 
-        struct StopAndClearPool(HttpRequest);
+        struct StopAndClearPool(Tagged<HttpRequest>);
 
         let ws_executor = ws::Executor::new(
             handle.clone(),
@@ -284,6 +324,7 @@ impl<N: Notify> ThreadedHandler<N> {
             client.clone(),
             settings,
             notify.clone(),
+            disk_cache.clone(),
         );
 
         // WS executor can just run in the background. Since there's only one
@@ -307,6 +348,7 @@ impl<N: Notify> ThreadedHandler<N> {
                             executor_return: cloned_send,
                             settings: settings_rc.clone(),
                             client: client.clone(),
+                            raw_client: raw_client.clone(),
                             disk_cache: disk_cache.clone(),
                         })
                         .expect("expected newly created channel to still be in scope")
@@ -322,7 +364,7 @@ impl<N: Notify> ThreadedHandler<N> {
                     .zip(exec_pool_recv.by_ref())
                     .map_err(|()| panic!("expected futures::mpsc::sync::Receiver stream to never return an error."))
                     .for_each(|(request, executor)| {
-                        if let HttpRequest::ChangeSettings { .. } = request {
+                        if let HttpRequest::ChangeSettings { .. } = request.request {
                             exec_pool_send
                                 .clone()
                                 .start_send(executor)
@@ -0,0 +1,771 @@
+//! Raw HTTP calls for endpoints which `screeps_api` does not support yet.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::{future, Future, Stream};
+use hyper::{self, Method, StatusCode};
+use hyper::header::ContentType;
+use base64;
+use serde_json::{self, Value};
+use time::Timespec;
+
+use screeps_api::{self, NoToken, RoomName, TokenStorage};
+use screeps_api::error::ApiError;
+use url::Url;
+
+use code::{Branch, CodeModule, CodeModules};
+use compression;
+use event::ServerInfo;
+use user::{Badge, UserProfile};
+use world::{MapUser, RoomControl, RoomSign, RoomStats, RoomStatus, WorldSize};
+
+/// Makes an authenticated call to an API endpoint, resolving to the JSON body of the response.
+///
+/// `endpoint` is relative to the API url, for example `user/console`. The token used is given back to the token
+/// storage once the response arrives, or replaced with the fresh token the server sent along with it. It's also
+/// given back if the request fails without a response.
+///
+/// Responses with a non-success status code, or with an `"error"` property, are turned into errors.
+pub fn call<C, H, T>(
+    api: &screeps_api::Api<C, H, T>,
+    client: &H,
+    method: Method,
+    endpoint: &str,
+    query: &[(&str, &str)],
+    body: Option<Value>,
+) -> Result<Box<Future<Item = Value, Error = screeps_api::Error>>, NoToken>
+where
+    C: hyper::client::Connect,
+    H: screeps_api::HyperClient<C>,
+    T: TokenStorage + Clone + 'static,
+{
+    let mut url = api.url
+        .join(endpoint)
+        .expect("expected hardcoded endpoint to be a valid URL segment");
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    let uri = url.as_str()
+        .parse::<hyper::Uri>()
+        .expect("expected valid URL to parse as a URI");
+
+    let token = api.tokens.take_token().ok_or(NoToken)?;
+
+    let mut request = hyper::Request::new(method, uri);
+    request.headers_mut().set_raw("X-Token", token.to_vec());
+    request.headers_mut().set_raw("X-Username", token.to_vec());
+    if let Some(body) = body {
+        request.headers_mut().set(ContentType::json());
+        request.set_body(serde_json::to_vec(&body).expect("expected serializing a JSON value to succeed"));
+    }
+
+    let tokens = api.tokens.clone();
+
+    Ok(Box::new(client.request(request).then(move |result| match result {
+        Ok(response) => {
+            let new_token = response
+                .headers()
+                .get_raw("X-Token")
+                .and_then(|raw| raw.one())
+                .map(|bytes| Arc::new(bytes.to_vec().into_boxed_slice()));
+
+            tokens.return_token(new_token.unwrap_or(token));
+
+            let status = response.status();
+            future::Either::A(
+                response
+                    .body()
+                    .concat2()
+                    .map_err(screeps_api::Error::from)
+                    .and_then(move |body| parse_response(status, &body)),
+            )
+        }
+        Err(e) => {
+            // no response means the token wasn't used up, so it can be used for the next call.
+            tokens.return_token(token);
+            future::Either::B(future::err(screeps_api::Error::from(e)))
+        }
+    })))
+}
+
+/// Makes a GET request to an API endpoint which doesn't need a login, such as `version`, resolving to the JSON
+/// body of the response.
+///
+/// `endpoint` is relative to `api_url`, which needn't be the url of any logged in connection.
+pub fn call_anonymous<C, H>(
+    client: &H,
+    api_url: &Url,
+    endpoint: &str,
+) -> Box<Future<Item = Value, Error = screeps_api::Error>>
+where
+    C: hyper::client::Connect,
+    H: screeps_api::HyperClient<C>,
+{
+    let uri = api_url
+        .join(endpoint)
+        .expect("expected hardcoded endpoint to be a valid URL segment")
+        .as_str()
+        .parse::<hyper::Uri>()
+        .expect("expected valid URL to parse as a URI");
+
+    Box::new(
+        client
+            .request(hyper::Request::new(Method::Get, uri))
+            .map_err(screeps_api::Error::from)
+            .and_then(|response| {
+                let status = response.status();
+                response
+                    .body()
+                    .concat2()
+                    .map_err(screeps_api::Error::from)
+                    .and_then(move |body| parse_response(status, &body))
+            }),
+    )
+}
+
+fn parse_response(status: StatusCode, body: &[u8]) -> Result<Value, screeps_api::Error> {
+    if status == StatusCode::Unauthorized {
+        return Err(screeps_api::ErrorKind::Unauthorized.into());
+    }
+    if !status.is_success() {
+        return Err(screeps_api::ErrorKind::StatusCode(status).into());
+    }
+
+    let json = serde_json::from_slice::<Value>(body).map_err(screeps_api::Error::from)?;
+
+    if let Some(error) = json.get("error").and_then(Value::as_str) {
+        return Err(generic_error(error.to_owned()));
+    }
+
+    Ok(json)
+}
+
+fn generic_error(message: String) -> screeps_api::Error {
+    screeps_api::ErrorKind::Api(ApiError::GenericError(message)).into()
+}
+
+/// Reads the `data` property of a `Memory` response, which is compressed JSON.
+///
+/// Paths which don't exist in `Memory` result in `null`.
+pub fn memory_data(json: &Value) -> Result<Value, screeps_api::Error> {
+    match json.get("data") {
+        Some(&Value::String(ref data)) => {
+            let text = compression::decompress_prefixed(data)
+                .map_err(|e| generic_error(format!("failed to decompress memory: {}", e)))?;
+
+            serde_json::from_str(&text).map_err(screeps_api::Error::from)
+        }
+        Some(other) => Ok(other.clone()),
+        None => Ok(Value::Null),
+    }
+}
+
+/// Reads the `data` property of a memory segment response, with one string for each segment requested.
+///
+/// Segments which have never been set are read as empty strings.
+pub fn segment_data(json: &Value, segments: &[u8]) -> Result<Vec<String>, screeps_api::Error> {
+    let as_string = |value: &Value| match *value {
+        Value::String(ref data) => Ok(data.clone()),
+        Value::Null => Ok(String::new()),
+        ref other => Err(generic_error(format!("expected memory segment to be a string, found {}", other))),
+    };
+
+    let data = match json.get("data") {
+        Some(&Value::Array(ref list)) => list.iter().map(as_string).collect::<Result<Vec<_>, _>>()?,
+        Some(single) => vec![as_string(single)?],
+        None => Vec::new(),
+    };
+
+    if data.len() != segments.len() {
+        return Err(generic_error(format!(
+            "expected {} memory segments, found {}",
+            segments.len(),
+            data.len()
+        )));
+    }
+
+    Ok(data)
+}
+
+/// Reads the `list` property of a branch list response.
+pub fn branch_list(json: &Value) -> Result<Vec<Branch>, screeps_api::Error> {
+    let list = json.get("list")
+        .and_then(Value::as_array)
+        .ok_or_else(|| generic_error("expected branch list response to contain a list".to_owned()))?;
+
+    list.iter()
+        .map(|branch| {
+            let name = branch
+                .get("branch")
+                .and_then(Value::as_str)
+                .ok_or_else(|| generic_error(format!("expected branch to have a name, found {}", branch)))?;
+            let flag = |key: &str| branch.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+            Ok(Branch {
+                name: name.to_owned(),
+                active_world: flag("activeWorld"),
+                active_sim: flag("activeSim"),
+            })
+        })
+        .collect()
+}
+
+/// Reads the `modules` property of a code response.
+///
+/// Binary modules are sent as objects with a base64 `binary` property, and all other modules as strings.
+pub fn code_modules(json: &Value) -> Result<CodeModules, screeps_api::Error> {
+    let modules = json.get("modules")
+        .and_then(Value::as_object)
+        .ok_or_else(|| generic_error("expected code response to contain modules".to_owned()))?;
+
+    modules
+        .iter()
+        .map(|(name, module)| {
+            let module = match *module {
+                Value::String(ref text) => CodeModule::Text(text.clone()),
+                _ => match module.get("binary").and_then(Value::as_str) {
+                    Some(data) => CodeModule::Binary(base64::decode(data).map_err(|e| {
+                        generic_error(format!("failed to decode binary module {}: {}", name, e))
+                    })?),
+                    None => return Err(generic_error(format!("unknown format for module {}: {}", name, module))),
+                },
+            };
+            Ok((name.clone(), module))
+        })
+        .collect()
+}
+
+/// Creates the `modules` property of a code upload.
+pub fn code_modules_json(modules: &CodeModules) -> Value {
+    let map = modules
+        .iter()
+        .map(|(name, module)| {
+            let value = match *module {
+                CodeModule::Text(ref text) => Value::String(text.clone()),
+                CodeModule::Binary(ref data) => json!({ "binary": base64::encode(data) }),
+            };
+            (name.clone(), value)
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    Value::Object(map)
+}
+
+/// Reads a world size response.
+pub fn world_size(json: &Value) -> Result<WorldSize, screeps_api::Error> {
+    let dimension = |key: &str| {
+        json.get(key)
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+            .ok_or_else(|| generic_error(format!("expected world size response to contain {}", key)))
+    };
+
+    Ok(WorldSize {
+        width: dimension("width")?,
+        height: dimension("height")?,
+    })
+}
+
+/// Reads the protocol and package versions from a version response.
+pub fn server_version(json: &Value) -> Result<ServerInfo, screeps_api::Error> {
+    let version = |key: &str| json.get(key).and_then(Value::as_u64).map(|version| version as u32);
+
+    let protocol = version("protocol")
+        .ok_or_else(|| generic_error("expected version response to contain protocol".to_owned()))?;
+
+    Ok(ServerInfo {
+        protocol: Some(protocol),
+        package: version("package"),
+        time: None,
+    })
+}
+
+/// Reads the `time` property of a game time response.
+pub fn game_time(json: &Value) -> Result<u32, screeps_api::Error> {
+    json.get("time")
+        .and_then(Value::as_u64)
+        .map(|time| time as u32)
+        .ok_or_else(|| generic_error("expected game time response to contain time".to_owned()))
+}
+
+/// Reads the status of each room in the `stats` property of a map stats response, as of `now`.
+///
+/// Rooms which don't exist are left out. Rooms which haven't opened yet are closed, and novice and respawn areas
+/// which have expired are normal.
+pub fn room_statuses(json: &Value, now: Timespec) -> Result<HashMap<RoomName, RoomStatus>, screeps_api::Error> {
+    let stats = json.get("stats")
+        .and_then(Value::as_object)
+        .ok_or_else(|| generic_error("expected map stats response to contain stats".to_owned()))?;
+
+    stats
+        .iter()
+        .map(|(name, room)| {
+            let room_name = RoomName::new(name)
+                .map_err(|e| generic_error(format!("invalid room name {} in map stats: {}", name, e)))?;
+
+            Ok((room_name, room_status(room, now)))
+        })
+        .collect()
+}
+
+fn room_status(room: &Value, now: Timespec) -> RoomStatus {
+    // all times are in milliseconds since the unix epoch.
+    let time = |key: &str| {
+        room.get(key)
+            .and_then(Value::as_f64)
+            .map(|millis| Timespec::new((millis / 1000.0) as i64, ((millis % 1000.0) * 1_000_000.0) as i32))
+            .and_then(|time| if time > now { Some(time) } else { None })
+    };
+
+    match room.get("status").and_then(Value::as_str) {
+        Some("normal") | None => match (time("openTime"), time("novice"), time("respawnArea")) {
+            (Some(_), _, _) => RoomStatus::Closed,
+            (None, Some(until), _) => RoomStatus::Novice { until: until },
+            (None, None, Some(until)) => RoomStatus::Respawn { until: until },
+            (None, None, None) => RoomStatus::Normal,
+        },
+        Some(_) => RoomStatus::Closed,
+    }
+}
+
+/// Reads the `stats` property of a map stats response, resolving user ids with its `users` property.
+///
+/// Rooms which don't exist are left out.
+pub fn map_stats(json: &Value) -> Result<HashMap<RoomName, RoomStats>, screeps_api::Error> {
+    let stats = json.get("stats")
+        .and_then(Value::as_object)
+        .ok_or_else(|| generic_error("expected map stats response to contain stats".to_owned()))?;
+    let users = json.get("users").and_then(Value::as_object);
+
+    let user = |id: &Value| {
+        id.as_str().map(|id| MapUser {
+            id: id.to_owned(),
+            username: users
+                .and_then(|users| users.get(id))
+                .and_then(|user| user.get("username"))
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned),
+        })
+    };
+
+    stats
+        .iter()
+        .map(|(name, room)| {
+            let room_name = RoomName::new(name)
+                .map_err(|e| generic_error(format!("invalid room name {} in map stats: {}", name, e)))?;
+
+            // reservations are sent as ownership at level 0.
+            let control = room.get("own").and_then(|own| {
+                let user = own.get("user").and_then(&user)?;
+                Some(match own.get("level").and_then(Value::as_u64).unwrap_or(0) {
+                    0 => RoomControl::Reserved { user: user },
+                    level => RoomControl::Owned {
+                        user: user,
+                        level: level as u32,
+                    },
+                })
+            });
+            let sign = room.get("sign").and_then(|sign| {
+                Some(RoomSign {
+                    user: sign.get("user").and_then(&user)?,
+                    text: sign.get("text").and_then(Value::as_str)?.to_owned(),
+                    game_time: sign.get("time").and_then(Value::as_u64).unwrap_or(0) as u32,
+                })
+            });
+
+            Ok((
+                room_name,
+                RoomStats {
+                    control: control,
+                    sign: sign,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Reads the `user` property of a find user response.
+pub fn user_profile(json: &Value) -> Result<UserProfile, screeps_api::Error> {
+    let user = json.get("user")
+        .ok_or_else(|| generic_error("expected find user response to contain a user".to_owned()))?;
+    let string = |key: &str| {
+        user.get(key)
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| generic_error(format!("expected user to have {}, found {}", key, user)))
+    };
+
+    let badge = user.get("badge").and_then(|badge| {
+        let color = |key: &str| badge.get(key).and_then(Value::as_str).map(ToOwned::to_owned);
+
+        Some(Badge {
+            // custom badges have an object with their paths instead of a pattern number.
+            pattern: badge.get("type").and_then(Value::as_u64).unwrap_or(0) as u32,
+            colors: [color("color1")?, color("color2")?, color("color3")?],
+            param: badge.get("param").and_then(Value::as_i64).unwrap_or(0) as i32,
+            flip: badge.get("flip").and_then(Value::as_bool).unwrap_or(false),
+        })
+    });
+
+    Ok(UserProfile {
+        id: string("_id")?,
+        username: string("username")?,
+        gcl_points: user.get("gcl").and_then(Value::as_f64).unwrap_or(0.0) as u64,
+        badge: badge,
+    })
+}
+
+/// Whether an error is the server saying a user doesn't exist.
+pub fn is_user_not_found(error: &screeps_api::Error) -> bool {
+    match *error.kind() {
+        screeps_api::ErrorKind::Api(ApiError::GenericError(ref message)) => message == "user not found",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+    use serde_json::{self, Value};
+    use time::Timespec;
+
+    use screeps_api::{self, RoomName};
+
+    use code::{Branch, CodeModule, CodeModules};
+    use user::{Badge, UserProfile};
+    use world::{MapUser, RoomControl, RoomSign, RoomStats, RoomStatus, WorldSize};
+
+    use super::{branch_list, code_modules, code_modules_json, is_user_not_found, map_stats, memory_data,
+                parse_response, room_statuses, segment_data, user_profile, world_size};
+
+    fn parse(json: &str) -> Value {
+        serde_json::from_str(json).expect("expected test response to be valid JSON")
+    }
+
+    #[test]
+    fn memory_is_decompressed() {
+        // the server gzips memory values, and marks them with a "gz:" prefix.
+        let json = parse(
+            r#"{"ok": 1, "data": "gz:H4sIAAAAAAACA6tWSi5KTS0oVrKqVvJILCpLLS5JLTIE8Yryc1KVrJQyYIJKtbW1AG6WrXEuAAAA"}"#,
+        );
+        let expected = parse(r#"{"creeps": {"Harvester1": {"role": "harvester"}}}"#);
+        assert_eq!(memory_data(&json).unwrap(), expected);
+    }
+
+    #[test]
+    fn uncompressed_memory_is_read_as_is() {
+        let json = parse(r#"{"ok": 1, "data": 15}"#);
+        assert_eq!(memory_data(&json).unwrap(), Value::from(15));
+    }
+
+    #[test]
+    fn missing_memory_paths_are_null() {
+        let json = parse(r#"{"ok": 1}"#);
+        assert_eq!(memory_data(&json).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn invalid_compressed_memory_is_an_error() {
+        let json = parse(r#"{"ok": 1, "data": "gz:not*base64!"}"#);
+        assert!(memory_data(&json).is_err());
+    }
+
+    #[test]
+    fn single_segments_are_read() {
+        let json = parse(r#"{"ok": 1, "data": "remote mining: W1N1, W2N1"}"#);
+        assert_eq!(segment_data(&json, &[3]).unwrap(), vec!["remote mining: W1N1, W2N1"]);
+    }
+
+    #[test]
+    fn multiple_segments_are_read_in_order() {
+        let json = parse(r#"{"ok": 1, "data": ["first", null, "third"]}"#);
+        assert_eq!(segment_data(&json, &[0, 1, 2]).unwrap(), vec!["first", "", "third"]);
+    }
+
+    #[test]
+    fn unset_segments_are_empty() {
+        let json = parse(r#"{"ok": 1, "data": null}"#);
+        assert_eq!(segment_data(&json, &[7]).unwrap(), vec![""]);
+    }
+
+    #[test]
+    fn missing_segments_are_an_error() {
+        let json = parse(r#"{"ok": 1, "data": ["first"]}"#);
+        assert!(segment_data(&json, &[0, 1]).is_err());
+    }
+
+    #[test]
+    fn non_string_segments_are_an_error() {
+        let json = parse(r#"{"ok": 1, "data": [15]}"#);
+        assert!(segment_data(&json, &[0]).is_err());
+    }
+
+    #[test]
+    fn branches_are_read() {
+        let json = parse(
+            r#"{"ok": 1, "list": [
+                {"_id": "5a1b", "branch": "default", "activeWorld": true, "activeSim": false},
+                {"_id": "5a1c", "branch": "experiment", "activeWorld": false, "activeSim": true},
+                {"_id": "5a1d", "branch": "old"}
+            ]}"#,
+        );
+        assert_eq!(
+            branch_list(&json).unwrap(),
+            vec![
+                Branch {
+                    name: "default".to_owned(),
+                    active_world: true,
+                    active_sim: false,
+                },
+                Branch {
+                    name: "experiment".to_owned(),
+                    active_world: false,
+                    active_sim: true,
+                },
+                Branch {
+                    name: "old".to_owned(),
+                    active_world: false,
+                    active_sim: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn branches_without_names_are_an_error() {
+        let json = parse(r#"{"ok": 1, "list": [{"_id": "5a1b", "activeWorld": true}]}"#);
+        assert!(branch_list(&json).is_err());
+    }
+
+    #[test]
+    fn text_and_binary_modules_are_read() {
+        // binary modules are base64 encoded, this one being the header of a WebAssembly module.
+        let json = parse(
+            r#"{"ok": 1, "branch": "default", "modules": {
+                "main": "module.exports.loop = function() {};",
+                "compiled": {"binary": "AGFzbQEAAAA="}
+            }}"#,
+        );
+        let modules = code_modules(&json).unwrap();
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules["main"], CodeModule::Text("module.exports.loop = function() {};".to_owned()));
+        assert_eq!(modules["compiled"], CodeModule::Binary(vec![0, 97, 115, 109, 1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn invalid_binary_modules_are_an_error() {
+        let json = parse(r#"{"ok": 1, "modules": {"compiled": {"binary": "not*base64!"}}}"#);
+        assert!(code_modules(&json).is_err());
+    }
+
+    #[test]
+    fn unknown_modules_are_an_error() {
+        let json = parse(r#"{"ok": 1, "modules": {"main": 15}}"#);
+        assert!(code_modules(&json).is_err());
+    }
+
+    #[test]
+    fn modules_are_uploaded_as_they_are_read() {
+        let mut modules = CodeModules::new();
+        modules.insert("main".to_owned(), CodeModule::Text("require('compiled');".to_owned()));
+        modules.insert("compiled".to_owned(), CodeModule::Binary(vec![0, 97, 115, 109, 1, 0, 0, 0]));
+
+        let uploaded = code_modules_json(&modules);
+        assert_eq!(uploaded, parse(r#"{"main": "require('compiled');", "compiled": {"binary": "AGFzbQEAAAA="}}"#));
+        assert_eq!(code_modules(&json!({ "modules": uploaded })).unwrap(), modules);
+    }
+
+    #[test]
+    fn world_size_is_read() {
+        let json = parse(r#"{"ok": 1, "width": 202, "height": 202}"#);
+        assert_eq!(
+            world_size(&json).unwrap(),
+            WorldSize {
+                width: 202,
+                height: 202,
+            }
+        );
+    }
+
+    #[test]
+    fn incomplete_world_sizes_are_an_error() {
+        let json = parse(r#"{"ok": 1, "width": 202}"#);
+        assert!(world_size(&json).is_err());
+    }
+
+    #[test]
+    fn room_statuses_are_read_from_map_stats() {
+        // times are in milliseconds: novice and respawn areas which have ended, and rooms which have opened, are
+        // normal.
+        let now = Timespec::new(1_500_000_000, 0);
+        let json = parse(
+            r#"{"ok": 1, "stats": {
+                "W1N1": {"status": "normal", "novice": null, "respawnArea": null, "openTime": null},
+                "W2N1": {"status": "normal", "novice": 1600000000000},
+                "W3N1": {"status": "normal", "respawnArea": 1600000000500},
+                "W4N1": {"status": "normal", "novice": 1400000000000, "respawnArea": 1400000000000},
+                "W5N1": {"status": "normal", "openTime": 1600000000000, "novice": 1600000000000},
+                "W6N1": {"status": "normal", "openTime": 1400000000000},
+                "W7N1": {"status": "out of borders"},
+                "W8N1": {}
+            }, "users": {}}"#,
+        );
+        let statuses = room_statuses(&json, now).unwrap();
+        let status = |name: &str| statuses[&RoomName::new(name).unwrap()];
+
+        assert_eq!(statuses.len(), 8);
+        assert_eq!(status("W1N1"), RoomStatus::Normal);
+        assert_eq!(
+            status("W2N1"),
+            RoomStatus::Novice {
+                until: Timespec::new(1_600_000_000, 0),
+            }
+        );
+        assert_eq!(
+            status("W3N1"),
+            RoomStatus::Respawn {
+                until: Timespec::new(1_600_000_000, 500_000_000),
+            }
+        );
+        assert_eq!(status("W4N1"), RoomStatus::Normal);
+        assert_eq!(status("W5N1"), RoomStatus::Closed);
+        assert_eq!(status("W6N1"), RoomStatus::Normal);
+        assert_eq!(status("W7N1"), RoomStatus::Closed);
+        assert_eq!(status("W8N1"), RoomStatus::Normal);
+    }
+
+    #[test]
+    fn room_statuses_without_stats_are_an_error() {
+        let json = parse(r#"{"ok": 1}"#);
+        assert!(room_statuses(&json, Timespec::new(1_500_000_000, 0)).is_err());
+    }
+
+    #[test]
+    fn invalid_room_names_are_an_error() {
+        let json = parse(r#"{"ok": 1, "stats": {"not a room": {"status": "normal"}}}"#);
+        assert!(room_statuses(&json, Timespec::new(1_500_000_000, 0)).is_err());
+    }
+
+    #[test]
+    fn map_stats_are_read() {
+        let json = parse(
+            r#"{"ok": 1, "stats": {
+                "W1N1": {"status": "normal", "own": {"user": "5a1b", "level": 8},
+                         "sign": {"user": "5a1c", "text": "hello", "time": 123456, "datetime": 1500000000000}},
+                "W2N1": {"status": "normal", "own": {"user": "5a1c", "level": 0}},
+                "W3N1": {"status": "normal", "own": {"user": "5a1d", "level": 3}},
+                "W4N1": {"status": "normal"}
+            }, "users": {
+                "5a1b": {"_id": "5a1b", "username": "daboross", "badge": {}},
+                "5a1c": {"_id": "5a1c", "username": "someone"}
+            }}"#,
+        );
+        let stats = map_stats(&json).unwrap();
+        let room_stats = |name: &str| stats[&RoomName::new(name).unwrap()].clone();
+        let user = |id: &str, username: Option<&str>| MapUser {
+            id: id.to_owned(),
+            username: username.map(ToOwned::to_owned),
+        };
+
+        assert_eq!(stats.len(), 4);
+        assert_eq!(
+            room_stats("W1N1"),
+            RoomStats {
+                control: Some(RoomControl::Owned {
+                    user: user("5a1b", Some("daboross")),
+                    level: 8,
+                }),
+                sign: Some(RoomSign {
+                    user: user("5a1c", Some("someone")),
+                    text: "hello".to_owned(),
+                    game_time: 123456,
+                }),
+            }
+        );
+        // reservations are sent as level 0 ownership.
+        assert_eq!(
+            room_stats("W2N1").control,
+            Some(RoomControl::Reserved {
+                user: user("5a1c", Some("someone")),
+            })
+        );
+        // users left out of `users` have no username.
+        assert_eq!(
+            room_stats("W3N1").control,
+            Some(RoomControl::Owned {
+                user: user("5a1d", None),
+                level: 3,
+            })
+        );
+        assert_eq!(room_stats("W4N1"), RoomStats::default());
+    }
+
+    #[test]
+    fn map_stats_without_stats_are_an_error() {
+        let json = parse(r#"{"ok": 1, "users": {}}"#);
+        assert!(map_stats(&json).is_err());
+    }
+
+    #[test]
+    fn user_profiles_are_read() {
+        let json = parse(
+            r##"{"ok": 1, "user": {"_id": "5a1b", "username": "daboross", "gcl": 1234567.5, "badge": {
+                "type": 19, "color1": "#260d0d", "color2": "#6b2e41", "color3": "#ffe56d", "param": -100, "flip": true
+            }}}"##,
+        );
+        assert_eq!(
+            user_profile(&json).unwrap(),
+            UserProfile {
+                id: "5a1b".to_owned(),
+                username: "daboross".to_owned(),
+                gcl_points: 1234567,
+                badge: Some(Badge {
+                    pattern: 19,
+                    colors: ["#260d0d".to_owned(), "#6b2e41".to_owned(), "#ffe56d".to_owned()],
+                    param: -100,
+                    flip: true,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn custom_badges_have_no_pattern() {
+        let json = parse(
+            r##"{"ok": 1, "user": {"_id": "5a1b", "username": "daboross", "badge": {
+                "type": {"path1": "M 0 0", "path2": "M 1 1"}, "color1": "#000000", "color2": "#111111",
+                "color3": "#222222", "param": 0, "flip": false
+            }}}"##,
+        );
+        let profile = user_profile(&json).unwrap();
+        assert_eq!(profile.gcl_points, 0);
+        assert_eq!(profile.badge.map(|badge| badge.pattern), Some(0));
+    }
+
+    #[test]
+    fn users_without_badges_are_read() {
+        let json = parse(r#"{"ok": 1, "user": {"_id": "5a1b", "username": "daboross", "gcl": 0}}"#);
+        assert_eq!(user_profile(&json).unwrap().badge, None);
+    }
+
+    #[test]
+    fn users_without_ids_are_an_error() {
+        let json = parse(r#"{"ok": 1, "user": {"username": "daboross"}}"#);
+        assert!(user_profile(&json).is_err());
+    }
+
+    #[test]
+    fn missing_users_are_recognized() {
+        let error = parse_response(StatusCode::Ok, br#"{"error": "user not found"}"#).unwrap_err();
+        assert!(is_user_not_found(&error));
+
+        let error = parse_response(StatusCode::Ok, br#"{"error": "invalid params"}"#).unwrap_err();
+        assert!(!is_user_not_found(&error));
+
+        let error = parse_response(StatusCode::Unauthorized, b"").unwrap_err();
+        assert!(!is_user_not_found(&error));
+
+        let error = screeps_api::Error::from(screeps_api::ErrorKind::StatusCode(StatusCode::NotFound));
+        assert!(!is_user_not_found(&error));
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use futures::{Async, AsyncSink, Poll, Sink, StartSend, Stream};
+use futures::sync::oneshot;
+use futures::task::AtomicTask;
+
+use event::NetworkEvent;
+use request::{Request, RequestId};
+use {ConnectionSettings, Disconnected, Notify, ScreepsConnection};
+
+use super::Handler;
+
+/// Notify implementation which wakes up the last task to poll a `StreamHandler`.
+#[derive(Clone, Default)]
+pub struct TaskNotify(Arc<AtomicTask>);
+
+impl Notify for TaskNotify {
+    fn wakeup(&self) -> Result<(), Disconnected> {
+        self.0.notify();
+        Ok(())
+    }
+}
+
+/// Handler which exposes network events as a `Stream` and accepts requests as a `Sink`.
+///
+/// This is meant for running the network handler outside of a window event loop, for instance in bots or
+/// command line tools. The stream ends once a `Request::Exit` has been sent and the handler thread has exited.
+pub struct StreamHandler {
+    handler: Handler<TaskNotify>,
+    notify: TaskNotify,
+    exit_sent: bool,
+}
+
+impl StreamHandler {
+    /// Creates a new handler with the given settings.
+    pub fn new(settings: ConnectionSettings) -> Self {
+        let notify = TaskNotify::default();
+
+        StreamHandler {
+            handler: Handler::new(settings, notify.clone()),
+            notify: notify,
+            exit_sent: false,
+        }
+    }
+
+    /// Sends a request directly, without going through the `Sink` interface.
+    pub fn send(&mut self, request: Request) -> RequestId {
+        if let Request::Exit = request {
+            self.exit_sent = true;
+        }
+        self.handler.send(request)
+    }
+
+    /// Sends a request, and returns a receiver which will resolve with the event resulting from it.
+    ///
+    /// See `Handler::send_with_response`.
+    pub fn send_with_response(&mut self, request: Request) -> (RequestId, oneshot::Receiver<NetworkEvent>) {
+        if let Request::Exit = request {
+            self.exit_sent = true;
+        }
+        self.handler.send_with_response(request)
+    }
+}
+
+impl Stream for StreamHandler {
+    type Item = NetworkEvent;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<NetworkEvent>, ()> {
+        // register before checking for events so that an event sent in between is not missed.
+        self.notify.0.register();
+
+        match self.handler.poll() {
+            Some(event) => Ok(Async::Ready(Some(event))),
+            None => if self.exit_sent && self.handler.handles.is_none() {
+                Ok(Async::Ready(None))
+            } else {
+                Ok(Async::NotReady)
+            },
+        }
+    }
+}
+
+impl Sink for StreamHandler {
+    type SinkItem = Request;
+    type SinkError = ();
+
+    fn start_send(&mut self, request: Request) -> StartSend<Request, ()> {
+        self.send(request);
+
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), ()> {
+        // requests are handed off to the handler thread immediately.
+        Ok(Async::Ready(()))
+    }
+}
@@ -0,0 +1,229 @@
+//! Reference counted websocket channel subscriptions.
+use std::collections::{HashMap, HashSet};
+
+use serde_json;
+use websocket;
+
+/// Most channels subscribed to at once by default.
+///
+/// The server drops connections which subscribe to too many channels, so channels past this are only subscribed
+/// to once others are released.
+pub const DEFAULT_SUBSCRIPTION_LIMIT: usize = 200;
+
+#[derive(Copy, Clone, Debug)]
+struct Held {
+    /// Number of times acquired without being released.
+    count: usize,
+    /// When the channel was first acquired, for choosing which channels to subscribe to once over the limit.
+    order: u64,
+}
+
+/// Tracks which channels are wanted and which the server has been told about, so that subscriptions shared between
+/// several users are only sent once.
+///
+/// Acquiring and releasing only changes what's wanted: `flush` creates the message which brings the server up to
+/// date.
+#[derive(Debug)]
+pub struct SubscriptionManager {
+    held: HashMap<String, Held>,
+    /// Channels the server has been told to subscribe to on the current connection.
+    active: HashSet<String>,
+    next_order: u64,
+    limit: usize,
+    /// Whether the last flush left channels unsubscribed because of the limit.
+    over_limit: bool,
+}
+
+impl Default for SubscriptionManager {
+    fn default() -> Self {
+        SubscriptionManager::with_limit(DEFAULT_SUBSCRIPTION_LIMIT)
+    }
+}
+
+impl SubscriptionManager {
+    pub fn with_limit(limit: usize) -> Self {
+        SubscriptionManager {
+            held: HashMap::new(),
+            active: HashSet::new(),
+            next_order: 0,
+            limit: limit,
+            over_limit: false,
+        }
+    }
+
+    /// Adds a reference to a channel, returning true if it wasn't already held.
+    pub fn acquire(&mut self, channel: String) -> bool {
+        let order = self.next_order;
+        let held = self.held.entry(channel).or_insert(Held {
+            count: 0,
+            order: order,
+        });
+        held.count += 1;
+        if held.count == 1 {
+            self.next_order += 1;
+        }
+        held.count == 1
+    }
+
+    /// Removes a reference to a channel, returning true if it's no longer held.
+    ///
+    /// Releasing a channel which isn't held does nothing.
+    pub fn release(&mut self, channel: &str) -> bool {
+        let remove = match self.held.get_mut(channel) {
+            Some(held) => {
+                held.count -= 1;
+                held.count == 0
+            }
+            None => {
+                warn!("released channel {} which was not held", channel);
+                return false;
+            }
+        };
+        if remove {
+            self.held.remove(channel);
+        }
+        remove
+    }
+
+    /// Whether no channels are held.
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Forgets which channels the server knows about, for when a new connection is opened.
+    ///
+    /// The next `flush` will subscribe to every held channel again.
+    pub fn disconnected(&mut self) {
+        self.active.clear();
+    }
+
+    /// Creates a single message subscribing to newly held channels and unsubscribing from released ones, or None
+    /// if the server is already up to date.
+    ///
+    /// The server is assumed to receive the message: channels are marked as active immediately.
+    pub fn flush(&mut self) -> Option<websocket::OwnedMessage> {
+        let mut actions = Vec::new();
+
+        let released = self.active
+            .iter()
+            .filter(|channel| !self.held.contains_key(*channel))
+            .cloned()
+            .collect::<Vec<_>>();
+        for channel in released {
+            self.active.remove(&channel);
+            actions.push(format!("unsubscribe {}", channel));
+        }
+
+        let mut waiting = self.held
+            .iter()
+            .filter(|&(channel, _)| !self.active.contains(channel))
+            .map(|(channel, held)| (held.order, channel.clone()))
+            .collect::<Vec<_>>();
+        waiting.sort();
+
+        let room = self.limit.saturating_sub(self.active.len());
+        let over_limit = waiting.len() > room;
+        if over_limit && !self.over_limit {
+            warn!(
+                "holding {} channels, over the limit of {}: some will not be subscribed to",
+                self.held.len(),
+                self.limit
+            );
+        }
+        self.over_limit = over_limit;
+
+        for (_, channel) in waiting.into_iter().take(room) {
+            actions.push(format!("subscribe {}", channel));
+            self.active.insert(channel);
+        }
+
+        if actions.is_empty() {
+            None
+        } else {
+            debug!("updating subscriptions: {:?}", actions);
+            let text = serde_json::to_string(&actions).expect("expected serializing a list of strings to succeed");
+            Some(websocket::OwnedMessage::Text(text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use websocket::OwnedMessage;
+
+    use super::SubscriptionManager;
+
+    /// Reads the subscribe and unsubscribe actions from a flushed message, or none if nothing was flushed.
+    fn actions(message: Option<OwnedMessage>) -> Vec<String> {
+        match message {
+            Some(OwnedMessage::Text(text)) => serde_json::from_str(&text).expect("expected a list of actions"),
+            Some(other) => panic!("expected a text message, found {:?}", other),
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn channels_are_subscribed_once() {
+        let mut manager = SubscriptionManager::default();
+        assert!(manager.acquire("room:W1N1".to_owned()));
+        assert!(!manager.acquire("room:W1N1".to_owned()));
+        assert_eq!(actions(manager.flush()), vec!["subscribe room:W1N1"]);
+        assert_eq!(actions(manager.flush()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn channels_stay_subscribed_until_every_reference_is_released() {
+        let mut manager = SubscriptionManager::default();
+        manager.acquire("room:W1N1".to_owned());
+        manager.acquire("room:W1N1".to_owned());
+        manager.flush();
+
+        assert!(!manager.release("room:W1N1"));
+        assert!(!manager.is_empty());
+        assert_eq!(actions(manager.flush()), Vec::<String>::new());
+
+        assert!(manager.release("room:W1N1"));
+        assert!(manager.is_empty());
+        assert_eq!(actions(manager.flush()), vec!["unsubscribe room:W1N1"]);
+    }
+
+    #[test]
+    fn channels_released_before_flushing_are_never_sent() {
+        let mut manager = SubscriptionManager::default();
+        manager.acquire("room:W1N1".to_owned());
+        manager.release("room:W1N1");
+        assert!(manager.flush().is_none());
+    }
+
+    #[test]
+    fn releasing_unheld_channels_does_nothing() {
+        let mut manager = SubscriptionManager::default();
+        assert!(!manager.release("room:W1N1"));
+        assert!(manager.flush().is_none());
+    }
+
+    #[test]
+    fn channels_over_the_limit_wait_for_others_to_be_released() {
+        let mut manager = SubscriptionManager::with_limit(2);
+        manager.acquire("room:W1N1".to_owned());
+        manager.acquire("room:W2N1".to_owned());
+        manager.acquire("room:W3N1".to_owned());
+        assert_eq!(actions(manager.flush()), vec!["subscribe room:W1N1", "subscribe room:W2N1"]);
+        assert_eq!(actions(manager.flush()), Vec::<String>::new());
+
+        manager.release("room:W1N1");
+        assert_eq!(actions(manager.flush()), vec!["unsubscribe room:W1N1", "subscribe room:W3N1"]);
+    }
+
+    #[test]
+    fn everything_is_subscribed_again_after_disconnecting() {
+        let mut manager = SubscriptionManager::default();
+        manager.acquire("room:W1N1".to_owned());
+        manager.acquire("user:5a1b/cpu".to_owned());
+        manager.flush();
+
+        manager.disconnected();
+        assert_eq!(actions(manager.flush()), vec!["subscribe room:W1N1", "subscribe user:5a1b/cpu"]);
+    }
+}
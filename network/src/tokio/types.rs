@@ -1,9 +1,43 @@
 use std::sync::Arc;
 
+use futures::sync::oneshot;
+
 use screeps_api;
 
-use request::{Request, SelectedRooms};
-use ConnectionSettings;
+use code::{ActiveBranch, CodeModules};
+use event::NetworkEvent;
+use request::{Request, RequestId};
+use user::UserQuery;
+use {ConnectionSettings, Url};
+
+/// A request tagged with its id, and optionally a channel to send the result to instead of the main event channel.
+#[derive(Debug)]
+pub struct Tagged<R> {
+    pub id: RequestId,
+    pub request: R,
+    pub reply: Option<oneshot::Sender<NetworkEvent>>,
+}
+
+impl<R> Tagged<R> {
+    pub fn new(id: RequestId, request: R, reply: Option<oneshot::Sender<NetworkEvent>>) -> Self {
+        Tagged {
+            id: id,
+            request: request,
+            reply: reply,
+        }
+    }
+
+    pub fn map<U, F>(self, func: F) -> Tagged<U>
+    where
+        F: FnOnce(R) -> U,
+    {
+        Tagged {
+            id: self.id,
+            request: func(self.request),
+            reply: self.reply,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum HttpRequest {
@@ -11,14 +45,58 @@ pub enum HttpRequest {
     MyInfo,
     ShardList,
     RoomTerrain { room_name: screeps_api::RoomName },
+    WorldSize { shard: Option<String> },
+    ServerVersion { api_url: Url },
+    GameTime { shard: Option<String> },
+    RoomStatus {
+        shard: Option<String>,
+        rooms: Vec<screeps_api::RoomName>,
+    },
+    MapStats {
+        shard: Option<String>,
+        rooms: Vec<screeps_api::RoomName>,
+    },
+    FindUsers { queries: Vec<UserQuery> },
+    ConsoleCommand { shard: Option<String>, expression: String },
+    ConsoleHistory,
+    MapViewSnapshots {
+        shard: Option<String>,
+        rooms: Vec<screeps_api::RoomName>,
+    },
+    GetMemory { shard: Option<String>, path: String },
+    SetMemory {
+        shard: Option<String>,
+        path: String,
+        value: String,
+    },
+    GetMemorySegments {
+        shard: Option<String>,
+        segments: Vec<u8>,
+    },
+    SetMemorySegment {
+        shard: Option<String>,
+        segment: u8,
+        data: String,
+    },
+    Branches,
+    GetCode { branch: String },
+    UploadCode {
+        branch: String,
+        modules: Arc<CodeModules>,
+    },
+    SetActiveBranch { branch: String, target: ActiveBranch },
     ChangeSettings { settings: Arc<ConnectionSettings> },
     Exit,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum WebsocketRequest {
-    SetMapSubscribes { rooms: SelectedRooms },
-    SetFocusRoom { room: Option<screeps_api::RoomName> },
+    SetMapSubscribes { rooms: Vec<screeps_api::RoomName> },
+    SetDetailRooms { rooms: Vec<screeps_api::RoomName> },
+    AcquireChannel { channel: String },
+    ReleaseChannel { channel: String },
+    WatchMemory { path: String },
+    UnwatchMemory { path: String },
     ChangeSettings { settings: Arc<ConnectionSettings> },
     Exit,
 }
@@ -39,10 +117,75 @@ impl From<Request> for GenericRequest {
             Request::RoomTerrain { room_name } => GenericRequest::Http(HttpRequest::RoomTerrain {
                 room_name: room_name,
             }),
+            Request::WorldSize { shard } => GenericRequest::Http(HttpRequest::WorldSize { shard: shard }),
+            Request::ServerVersion { api_url } => GenericRequest::Http(HttpRequest::ServerVersion { api_url: api_url }),
+            Request::GameTime { shard } => GenericRequest::Http(HttpRequest::GameTime { shard: shard }),
+            Request::RoomStatus { shard, rooms } => GenericRequest::Http(HttpRequest::RoomStatus {
+                shard: shard,
+                rooms: rooms,
+            }),
+            Request::MapStats { shard, rooms } => GenericRequest::Http(HttpRequest::MapStats {
+                shard: shard,
+                rooms: rooms,
+            }),
+            Request::FindUsers { queries } => GenericRequest::Http(HttpRequest::FindUsers { queries: queries }),
+            Request::ConsoleCommand { shard, expression } => GenericRequest::Http(HttpRequest::ConsoleCommand {
+                shard: shard,
+                expression: expression,
+            }),
+            Request::ConsoleHistory => GenericRequest::Http(HttpRequest::ConsoleHistory),
+            Request::MapViewSnapshots { shard, rooms } => GenericRequest::Http(HttpRequest::MapViewSnapshots {
+                shard: shard,
+                rooms: rooms,
+            }),
+            Request::GetMemory { shard, path } => GenericRequest::Http(HttpRequest::GetMemory {
+                shard: shard,
+                path: path,
+            }),
+            Request::SetMemory { shard, path, value } => GenericRequest::Http(HttpRequest::SetMemory {
+                shard: shard,
+                path: path,
+                value: value,
+            }),
+            Request::GetMemorySegments { shard, segments } => {
+                GenericRequest::Http(HttpRequest::GetMemorySegments {
+                    shard: shard,
+                    segments: segments,
+                })
+            }
+            Request::SetMemorySegment { shard, segment, data } => {
+                GenericRequest::Http(HttpRequest::SetMemorySegment {
+                    shard: shard,
+                    segment: segment,
+                    data: data,
+                })
+            }
+            Request::Branches => GenericRequest::Http(HttpRequest::Branches),
+            Request::GetCode { branch } => GenericRequest::Http(HttpRequest::GetCode { branch: branch }),
+            Request::UploadCode { branch, modules } => GenericRequest::Http(HttpRequest::UploadCode {
+                branch: branch,
+                modules: modules,
+            }),
+            Request::SetActiveBranch { branch, target } => GenericRequest::Http(HttpRequest::SetActiveBranch {
+                branch: branch,
+                target: target,
+            }),
             Request::SetMapSubscribes { rooms } => {
                 GenericRequest::Websocket(WebsocketRequest::SetMapSubscribes { rooms: rooms })
             }
-            Request::SetFocusRoom { room } => GenericRequest::Websocket(WebsocketRequest::SetFocusRoom { room: room }),
+            Request::SetDetailRooms { rooms } => {
+                GenericRequest::Websocket(WebsocketRequest::SetDetailRooms { rooms: rooms })
+            }
+            Request::AcquireChannel { channel } => {
+                GenericRequest::Websocket(WebsocketRequest::AcquireChannel { channel: channel })
+            }
+            Request::ReleaseChannel { channel } => {
+                GenericRequest::Websocket(WebsocketRequest::ReleaseChannel { channel: channel })
+            }
+            Request::WatchMemory { path } => GenericRequest::Websocket(WebsocketRequest::WatchMemory { path: path }),
+            Request::UnwatchMemory { path } => {
+                GenericRequest::Websocket(WebsocketRequest::UnwatchMemory { path: path })
+            }
             Request::ChangeSettings { settings } => GenericRequest::Both(
                 HttpRequest::ChangeSettings {
                     settings: settings.clone(),
@@ -63,6 +206,55 @@ impl Into<Request> for HttpRequest {
             HttpRequest::RoomTerrain { room_name } => Request::RoomTerrain {
                 room_name: room_name,
             },
+            HttpRequest::WorldSize { shard } => Request::WorldSize { shard: shard },
+            HttpRequest::ServerVersion { api_url } => Request::ServerVersion { api_url: api_url },
+            HttpRequest::GameTime { shard } => Request::GameTime { shard: shard },
+            HttpRequest::RoomStatus { shard, rooms } => Request::RoomStatus {
+                shard: shard,
+                rooms: rooms,
+            },
+            HttpRequest::MapStats { shard, rooms } => Request::MapStats {
+                shard: shard,
+                rooms: rooms,
+            },
+            HttpRequest::FindUsers { queries } => Request::FindUsers { queries: queries },
+            HttpRequest::ConsoleCommand { shard, expression } => Request::ConsoleCommand {
+                shard: shard,
+                expression: expression,
+            },
+            HttpRequest::ConsoleHistory => Request::ConsoleHistory,
+            HttpRequest::MapViewSnapshots { shard, rooms } => Request::MapViewSnapshots {
+                shard: shard,
+                rooms: rooms,
+            },
+            HttpRequest::GetMemory { shard, path } => Request::GetMemory {
+                shard: shard,
+                path: path,
+            },
+            HttpRequest::SetMemory { shard, path, value } => Request::SetMemory {
+                shard: shard,
+                path: path,
+                value: value,
+            },
+            HttpRequest::GetMemorySegments { shard, segments } => Request::GetMemorySegments {
+                shard: shard,
+                segments: segments,
+            },
+            HttpRequest::SetMemorySegment { shard, segment, data } => Request::SetMemorySegment {
+                shard: shard,
+                segment: segment,
+                data: data,
+            },
+            HttpRequest::Branches => Request::Branches,
+            HttpRequest::GetCode { branch } => Request::GetCode { branch: branch },
+            HttpRequest::UploadCode { branch, modules } => Request::UploadCode {
+                branch: branch,
+                modules: modules,
+            },
+            HttpRequest::SetActiveBranch { branch, target } => Request::SetActiveBranch {
+                branch: branch,
+                target: target,
+            },
             HttpRequest::ChangeSettings { settings } => Request::ChangeSettings { settings: settings },
             HttpRequest::Exit => Request::Exit,
         }
@@ -73,7 +265,11 @@ impl Into<Request> for WebsocketRequest {
     fn into(self) -> Request {
         match self {
             WebsocketRequest::SetMapSubscribes { rooms } => Request::SetMapSubscribes { rooms: rooms },
-            WebsocketRequest::SetFocusRoom { room } => Request::SetFocusRoom { room: room },
+            WebsocketRequest::SetDetailRooms { rooms } => Request::SetDetailRooms { rooms: rooms },
+            WebsocketRequest::AcquireChannel { channel } => Request::AcquireChannel { channel: channel },
+            WebsocketRequest::ReleaseChannel { channel } => Request::ReleaseChannel { channel: channel },
+            WebsocketRequest::WatchMemory { path } => Request::WatchMemory { path: path },
+            WebsocketRequest::UnwatchMemory { path } => Request::UnwatchMemory { path: path },
             WebsocketRequest::ChangeSettings { settings } => Request::ChangeSettings { settings: settings },
             WebsocketRequest::Exit => Request::Exit,
         }
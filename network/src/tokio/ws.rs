@@ -1,6 +1,6 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-use std::cell::{Cell, RefCell};
+use std::cell::RefCell;
 use std::sync::Arc;
 
 use std::sync::mpsc::Sender as StdSender;
@@ -8,18 +8,21 @@ use futures::sync::mpsc as futures_mpsc;
 use futures::sync::mpsc::UnboundedSender as FuturesSender;
 use futures::sync::mpsc::UnboundedReceiver as FuturesReceiver;
 
-use futures::{future, stream, Future, Sink, Stream};
+use futures::{future, Future, Sink, Stream};
 use tokio_core::reactor::Handle;
 
 use screeps_api::{self, NoToken, RoomName, TokenStorage};
 use screeps_api::websocket::Channel;
 
-use {hyper, websocket};
+use {hyper, time, websocket};
 
-use event::NetworkEvent;
+use compression;
+use diskcache;
+use event::{NetworkEvent, ServerInfo, SUPPORTED_PROTOCOL};
 use {ConnectionSettings, Notify};
 
-use super::types::WebsocketRequest;
+use super::types::{Tagged, WebsocketRequest};
+use super::subscriptions::SubscriptionManager;
 use super::utils;
 
 mod types {
@@ -34,6 +37,11 @@ mod types {
 }
 
 use self::types::{WebsocketMergedStream, WebsocketSink};
+
+/// Seconds to wait before trying to find the logged in user's ID again, after failing to.
+const USER_ID_RETRY_SECONDS: i64 = 30;
+/// Seconds between storing map views of the same room in the disk cache.
+const MAP_VIEW_STORE_SECONDS: i64 = 60;
 use self::read::ReaderData;
 
 pub struct Executor<N, C, H, T> {
@@ -42,6 +50,7 @@ pub struct Executor<N, C, H, T> {
     notify: N,
     http_client: screeps_api::Api<C, H, T>,
     settings: Arc<ConnectionSettings>,
+    disk_cache: diskcache::Cache,
     /// Receive messages from the Reader thread to send.
     raw_send_receiver: Option<FuturesReceiver<(u16, websocket::OwnedMessage)>>,
     raw_send_sender: FuturesSender<(u16, websocket::OwnedMessage)>,
@@ -49,9 +58,17 @@ pub struct Executor<N, C, H, T> {
     /// can be ignored/dropped.
     connection_id: u16,
     client: Option<WebsocketSink>,
-    // What we're currently subscribed to:
-    subscribed_map_view: Rc<RefCell<HashSet<RoomName>>>,
-    subscribed_room_view: Rc<Cell<Option<RoomName>>>,
+    /// All channels held, and which the server knows about.
+    subscriptions: SubscriptionManager,
+    // Rooms channels are held for:
+    subscribed_map_view: HashSet<RoomName>,
+    subscribed_room_view: HashSet<RoomName>,
+    /// Watched memory paths, and how many times each has been watched.
+    watched_memory: Rc<RefCell<HashMap<String, usize>>>,
+    /// ID of the logged in user, fetched once to subscribe to user channels.
+    user_id: Option<String>,
+    /// When to next try fetching the user ID, if the last try failed.
+    user_id_retry_at: Option<time::Timespec>,
 }
 
 impl<N, C, H, T> Executor<N, C, H, T> {
@@ -61,6 +78,7 @@ impl<N, C, H, T> Executor<N, C, H, T> {
         http_client: screeps_api::Api<C, H, T>,
         settings: Arc<ConnectionSettings>,
         notify: N,
+        disk_cache: diskcache::Cache,
     ) -> Self {
         let (raw_sender, raw_receiver) = futures_mpsc::unbounded();
 
@@ -70,12 +88,17 @@ impl<N, C, H, T> Executor<N, C, H, T> {
             notify: notify,
             http_client: http_client,
             settings: settings,
+            disk_cache: disk_cache,
             raw_send_receiver: Some(raw_receiver),
             raw_send_sender: raw_sender,
             connection_id: 0,
             client: None,
+            subscriptions: SubscriptionManager::default(),
             subscribed_map_view: Default::default(),
             subscribed_room_view: Default::default(),
+            watched_memory: Default::default(),
+            user_id: None,
+            user_id_retry_at: None,
         }
     }
 }
@@ -111,9 +134,13 @@ where
     T: TokenStorage + 'static,
     N: Notify + 'static,
 {
-    pub fn run(mut self, ws_recv: FuturesReceiver<WebsocketRequest>) -> impl Future<Item = (), Error = ()> + 'static {
+    pub fn run(
+        mut self,
+        ws_recv: FuturesReceiver<Tagged<WebsocketRequest>>,
+    ) -> impl Future<Item = (), Error = ()> + 'static {
         ws_recv
-            .map(|m| WebsocketRequestOrRaw::Structured(m))
+            // websocket requests have no direct result, so any reply channel is dropped here.
+            .map(|m| WebsocketRequestOrRaw::Structured(m.request))
             .select(
                 self.raw_send_receiver
                     .take()
@@ -134,60 +161,86 @@ where
     fn execute(mut self, request: WebsocketRequest) -> impl Future<Item = Self, Error = WsExit> + 'static {
         match request {
             WebsocketRequest::SetMapSubscribes { rooms } => {
-                let room_set = self.subscribed_map_view.clone();
-
-                // subscribe to rooms we aren't subscribed to already.
-                Box::new(
-                    stream::iter_ok(
-                        rooms
-                            .into_iter()
-                            .filter(move |room_name| !room_set.borrow().contains(&room_name)),
-                    ).fold(self, |executor, room_name| {
-                        let shard = executor.settings.shard.clone();
-                        executor.subscribe(Channel::room_map_view(room_name, shard))
-                    })
-                        .and_then(move |executor| {
-                            // and unsubscribe from rooms we no longer need data for.
-                            let unneeded_rooms = executor
-                                .subscribed_map_view
-                                .borrow()
-                                .iter()
-                                .cloned()
-                                .filter(|room_name| !rooms.contains(room_name))
-                                .collect::<Vec<RoomName>>();
-
-                            stream::iter_ok(unneeded_rooms.into_iter()).fold(executor, |executor, room_name| {
-                                let shard = executor.settings.shard.clone();
-                                executor.unsubscribe(Channel::room_map_view(room_name, shard))
-                            })
-                        }),
-                ) as Box<Future<Item = Self, Error = WsExit>>
+                let shard = self.settings.shard.clone();
+                update_held_rooms(
+                    &mut self.subscriptions,
+                    &mut self.subscribed_map_view,
+                    rooms.into_iter().collect(),
+                    |room_name| Channel::room_map_view(room_name, shard.clone()).to_string(),
+                );
+
+                self.sync_subscriptions()
             }
-            WebsocketRequest::SetFocusRoom { room } => match (self.subscribed_room_view.get(), room) {
-                (None, None) => Box::new(future::ok(self)) as Box<Future<Item = Self, Error = WsExit>>,
-                (Some(ref r1), Some(ref r2)) if r1 == r2 => Box::new(future::ok(self)),
-                (None, Some(to_subscribe)) => {
-                    let shard = self.settings.shard.clone();
-                    Box::new(self.subscribe(Channel::room_detail(to_subscribe, shard)))
-                }
-                (Some(to_unsubscribe), None) => {
-                    let shard = self.settings.shard.clone();
-                    Box::new(self.unsubscribe(Channel::room_detail(to_unsubscribe, shard)))
+            WebsocketRequest::SetDetailRooms { rooms } => {
+                let shard = self.settings.shard.clone();
+                update_held_rooms(
+                    &mut self.subscriptions,
+                    &mut self.subscribed_room_view,
+                    rooms.into_iter().collect(),
+                    |room_name| Channel::room_detail(room_name, shard.clone()).to_string(),
+                );
+
+                self.sync_subscriptions()
+            }
+            WebsocketRequest::AcquireChannel { channel } => {
+                self.subscriptions.acquire(channel);
+
+                self.sync_subscriptions()
+            }
+            WebsocketRequest::ReleaseChannel { channel } => {
+                self.subscriptions.release(&channel);
+
+                self.sync_subscriptions()
+            }
+            WebsocketRequest::WatchMemory { path } => {
+                let first_watch = {
+                    let mut watched = self.watched_memory.borrow_mut();
+                    let count = watched.entry(path.clone()).or_insert(0);
+                    *count += 1;
+                    *count == 1
+                };
+
+                // without a user ID, the channel is acquired once connected, in `subscribe_user_channels`.
+                if first_watch {
+                    if let Some(channel) = self.memory_channel(self.settings.shard.as_ref().map(|s| &**s), &path) {
+                        debug!("watching memory path {}", path);
+                        self.subscriptions.acquire(channel);
+                    }
                 }
-                (Some(to_unsubscribe), Some(to_subscribe)) => {
-                    let shard = self.settings.shard.clone();
-                    Box::new(
-                        self.unsubscribe(Channel::room_detail(to_unsubscribe, shard))
-                            .and_then(move |executor| {
-                                let shard = executor.settings.shard.clone();
-                                executor.subscribe(Channel::room_detail(to_subscribe, shard))
-                            }),
-                    )
+
+                self.sync_subscriptions()
+            }
+            WebsocketRequest::UnwatchMemory { path } => {
+                let last_watch = {
+                    let mut watched = self.watched_memory.borrow_mut();
+                    let remove = match watched.get_mut(&path) {
+                        Some(count) => {
+                            *count -= 1;
+                            *count == 0
+                        }
+                        None => {
+                            warn!("unwatched memory path {} which was not being watched", path);
+                            false
+                        }
+                    };
+                    if remove {
+                        watched.remove(&path);
+                    }
+                    remove
+                };
+
+                if last_watch {
+                    if let Some(channel) = self.memory_channel(self.settings.shard.as_ref().map(|s| &**s), &path) {
+                        debug!("no longer watching memory path {}", path);
+                        self.subscriptions.release(&channel);
+                    }
                 }
-            },
+
+                self.sync_subscriptions()
+            }
             WebsocketRequest::ChangeSettings { settings } => {
                 debug!("websocket connection received new settings");
-                let (unsubscribe_from_shard, restart) = {
+                let (old_shard, restart) = {
                     let current = &mut self.settings;
                     match (
                         settings.api_url == current.api_url,
@@ -201,14 +254,12 @@ where
                             (None, false)
                         }
                         (true, true, _, false) => {
-                            // unsubscribe from everything for the old shard.
                             let old_shard = current.shard.clone();
                             *current = settings;
 
                             (Some(old_shard), false)
                         }
                         (_, false, ..) | (false, ..) => {
-                            // unsubscribe from everything for the old shard.
                             let old_shard = current.shard.clone();
                             *current = settings;
 
@@ -218,66 +269,82 @@ where
                     }
                 };
 
-                // and unsubscribe from rooms we no longer need data for.
-                let unsubscribe_map_views = move |exec: Self, unneeded_rooms: Vec<RoomName>, old_shard| {
-                    stream::iter_ok(unneeded_rooms.into_iter()).fold(
-                        (exec, old_shard),
-                        |(executor, old_shard), room_name| {
-                            let shard = executor.settings.shard.clone();
-                            executor
-                                .unsubscribe(Channel::room_map_view(room_name, old_shard))
-                                .map(|exec| (exec, shard))
-                        },
-                    )
-                };
-                let unsubscribe_detail = move |exec: Self, room_name, old_shard| {
-                    exec.unsubscribe(Channel::room_detail(room_name, old_shard))
+                let old_shard = match old_shard {
+                    Some(old_shard) => old_shard,
+                    None => return Box::new(future::ok(self)) as Box<Future<Item = Self, Error = WsExit>>,
                 };
+                let new_shard = self.settings.shard.clone();
+
+                // room channels include the shard, so they need to be moved to the new one.
+                for &room_name in &self.subscribed_map_view {
+                    self.subscriptions
+                        .release(&Channel::room_map_view(room_name, old_shard.clone()).to_string());
+                    self.subscriptions
+                        .acquire(Channel::room_map_view(room_name, new_shard.clone()).to_string());
+                }
+                for &room_name in &self.subscribed_room_view {
+                    self.subscriptions
+                        .release(&Channel::room_detail(room_name, old_shard.clone()).to_string());
+                    self.subscriptions
+                        .acquire(Channel::room_detail(room_name, new_shard.clone()).to_string());
+                }
 
-                let prerestart_future = match unsubscribe_from_shard {
-                    Some(old_shard) => {
-                        let unsubscribed_rooms = self.subscribed_map_view
-                            .borrow()
-                            .iter()
-                            .cloned()
-                            .collect::<Vec<RoomName>>();
-                        let subscribed_room = self.subscribed_room_view.get();
-
-                        match (unsubscribed_rooms.len(), subscribed_room) {
-                            (0, Some(to_unsubscribe)) => {
-                                (Box::new(unsubscribe_detail(self, to_unsubscribe, old_shard))
-                                    as Box<Future<Item = Self, Error = _> + 'static>)
-                            }
-                            (_, Some(to_unsubscribe)) => Box::new(
-                                unsubscribe_map_views(self, unsubscribed_rooms, old_shard)
-                                    .map(move |(exec, old_shard)| (exec, old_shard, to_unsubscribe))
-                                    .and_then(move |(exec, old_shard, room)| unsubscribe_detail(exec, room, old_shard)),
-                            ),
-                            (0, None) => Box::new(future::ok(self)),
-                            (_, None) => Box::new(
-                                unsubscribe_map_views(self, unsubscribed_rooms, old_shard).map(|(exec, _)| exec),
-                            ),
-                        }
+                if restart {
+                    // the new settings may be for another user, whose channels are acquired once connected.
+                    for channel in self.user_channels(old_shard.as_ref().map(|s| &**s)) {
+                        self.subscriptions.release(&channel);
                     }
-                    None => Box::new(future::ok(self)),
-                };
+                    self.user_id = None;
+                    self.user_id_retry_at = None;
 
-                if restart {
-                    debug!("scheduling websocket connection restart due to new settings.");
-                    Box::new(prerestart_future.map(|mut executor| {
-                        debug!("restarting websocket connection.");
-                        executor.client = None; // TODO: how do we force exit the receiver thread for this client..?
-                        executor.connection_id += 1;
-                        executor
-                    })) as Box<Future<Item = _, Error = _> + 'static>
+                    debug!("restarting websocket connection.");
+                    self.client = None; // TODO: how do we force exit the receiver thread for this client..?
+                    self.connection_id += 1;
                 } else {
-                    prerestart_future
+                    // memory watches include the shard too.
+                    let paths = self.watched_memory.borrow().keys().cloned().collect::<Vec<_>>();
+                    for path in paths {
+                        let old_channel = self.memory_channel(old_shard.as_ref().map(|s| &**s), &path);
+                        let new_channel = self.memory_channel(new_shard.as_ref().map(|s| &**s), &path);
+                        if let (Some(old_channel), Some(new_channel)) = (old_channel, new_channel) {
+                            self.subscriptions.release(&old_channel);
+                            self.subscriptions.acquire(new_channel);
+                        }
+                    }
                 }
+
+                self.sync_subscriptions()
             }
             WebsocketRequest::Exit => Box::new(future::err(WsExit)),
         }
     }
 
+    /// Sends any changes to held channels to the server, connecting first if anything is held and there's no
+    /// connection.
+    fn sync_subscriptions(mut self) -> Box<Future<Item = Self, Error = WsExit>> {
+        if self.client.is_none() {
+            if self.subscriptions.is_empty() {
+                return Box::new(future::ok(self));
+            }
+            // connecting subscribes to everything held.
+            return Box::new(self.connect().or_else(|executor| future::ok(executor)));
+        }
+
+        let retry_user_id = match self.user_id_retry_at {
+            Some(retry_at) => self.user_id.is_none() && time::get_time() >= retry_at,
+            None => false,
+        };
+        if retry_user_id {
+            // this subscribes to everything held as well.
+            return Box::new(self.subscribe_user_channels().or_else(|executor| future::ok(executor)));
+        }
+
+        match self.subscriptions.flush() {
+            Some(message) => Box::new(self.send(message).or_else(|executor| future::ok(executor))),
+            None => Box::new(future::ok(self)),
+        }
+    }
+
     fn send_raw(mut self, id: u16, message: websocket::OwnedMessage) -> Box<Future<Item = Self, Error = WsExit>> {
         // ignore messages from past closed connections.
         if id == self.connection_id {
@@ -334,44 +401,49 @@ where
     fn send(mut self, message: websocket::OwnedMessage) -> impl Future<Item = Self, Error = Self> + 'static {
         match self.client.take() {
             Some(sink) => Box::new(self.send_into(sink, message)) as Box<Future<Item = _, Error = _>>,
-            None => {
-                let login_failed = |executor: Self, err| {
-                    executor.relay_http_error(err);
+            None => Box::new(self.connect().and_then(|executor| executor.send(message))),
+        }
+    }
 
-                    future::err(executor)
-                };
+    /// Opens and authenticates a new connection, then subscribes to all held channels.
+    fn connect(self) -> Box<Future<Item = Self, Error = Self>> {
+        let login_failed = |executor: Self, err| {
+            executor.relay_http_error(err);
 
-                let get_token = |executor: Self| match executor.http_client.tokens.take_token() {
-                    Some(t) => Ok(future::ok((executor, t))),
-                    None => Err((executor, NoToken)),
-                };
+            future::err(executor)
+        };
 
-                // OK, first let's get a token to authenticate with:
-                Box::new(
-                    utils::execute_or_login_and_execute(self, get_token, login_failed).and_then(|(executor, token)| {
-                        // Now actually start the websocket connection
+        let get_token = |executor: Self| match executor.http_client.tokens.take_token() {
+            Some(t) => Ok(future::ok((executor, t))),
+            None => Err((executor, NoToken)),
+        };
 
-                        let url = screeps_api::websocket::default_url();
+        // OK, first let's get a token to authenticate with:
+        Box::new(
+            utils::execute_or_login_and_execute(self, get_token, login_failed).and_then(|(executor, token)| {
+                // Now actually start the websocket connection
 
-                        let connection_future =
-                            websocket::ClientBuilder::from_url(&url).async_connect_secure(None, &executor.handle);
+                let url = screeps_api::websocket::default_url();
 
-                        connection_future.then(|result| match result {
-                            Ok((connection, _)) => Box::new(
-                                executor
-                                    .login_protocol(connection, token)
-                                    .and_then(|executor| executor.send(message)),
-                            ) as Box<Future<Item = _, Error = _>>,
-                            Err(e) => {
-                                executor.relay_error(e);
+                let connection_future =
+                    websocket::ClientBuilder::from_url(&url).async_connect_secure(None, &executor.handle);
 
-                                Box::new(future::err(executor)) as Box<Future<Item = _, Error = _>>
-                            }
-                        })
-                    }),
-                ) as Box<Future<Item = _, Error = _>>
-            }
-        }
+                connection_future.then(|result| match result {
+                    Ok((connection, _)) => Box::new(executor.login_protocol(connection, token).and_then(
+                        |mut executor| {
+                            // subscriptions don't carry over between connections.
+                            executor.subscriptions.disconnected();
+                            executor.subscribe_user_channels()
+                        },
+                    )) as Box<Future<Item = _, Error = _>>,
+                    Err(e) => {
+                        executor.relay_error(e);
+
+                        Box::new(future::err(executor)) as Box<Future<Item = _, Error = _>>
+                    }
+                })
+            }),
+        )
     }
 
     fn login_protocol(
@@ -433,8 +505,9 @@ where
                             Ok((Some(message), connection)) => {
                                 use screeps_api::websocket::parsing;
 
-                                let text = match message {
-                                    websocket::OwnedMessage::Text(text) => Ok(text),
+                                let parsed = match message {
+                                    websocket::OwnedMessage::Text(text) => parse_text_frame(&text),
+                                    websocket::OwnedMessage::Binary(data) => parse_binary_frame(&data),
                                     websocket::OwnedMessage::Ping(data) => {
                                         return Box::new(finish_ping(executor, connection, data))
                                             as Box<Future<Item = _, Error = _>>
@@ -445,10 +518,6 @@ where
                                                 debug!("pong received: {:?}", data);
                                                 Ok(())
                                             }
-                                            websocket::OwnedMessage::Binary(data) => {
-                                                warn!("ignoring binary data received on websocket: {:?}", data);
-                                                Ok(())
-                                            }
                                             websocket::OwnedMessage::Close(reason) => {
                                                 warn!(
                                                     "websocket closed while waiting for 'auth ok' response: {:?}",
@@ -472,16 +541,7 @@ where
                                     }
                                 };
 
-                                let text = match text {
-                                    Ok(v) => v,
-                                    Err(e) => {
-                                        executor.relay_event(NetworkEvent::WebsocketParseError { error: e });
-
-                                        return Box::new(future::err(executor)) as Box<Future<Item = _, Error = _>>;
-                                    }
-                                };
-
-                                let parsed = match parsing::SockjsMessage::parse(&text) {
+                                let parsed = match parsed {
                                     Ok(v) => v,
                                     Err(e) => {
                                         executor.relay_event(NetworkEvent::WebsocketParseError { error: e });
@@ -503,6 +563,17 @@ where
 
                                             Box::new(future::err(executor)) as Box<Future<Item = _, Error = _>>
                                         }
+                                        msg @ parsing::ScreepsMessage::ServerProtocol { .. }
+                                        | msg @ parsing::ScreepsMessage::ServerPackage { .. }
+                                        | msg @ parsing::ScreepsMessage::ServerTime { .. } => {
+                                            if let Some(info) = server_info(&msg) {
+                                                executor.relay_event(NetworkEvent::ServerInfo { info: info });
+                                            }
+
+                                            // Recursion here!
+                                            Box::new(test_response(executor, connection))
+                                                as Box<Future<Item = _, Error = _>>
+                                        }
                                         other => {
                                             warn!(
                                                 "received unexpected websocket message while \
@@ -531,11 +602,12 @@ where
                                                     return Box::new(future::err(executor))
                                                         as Box<Future<Item = _, Error = _>>;
                                                 }
-                                                // TODO: duplicated below in actual receiver
                                                 msg @ parsing::ScreepsMessage::ServerProtocol { .. }
                                                 | msg @ parsing::ScreepsMessage::ServerPackage { .. }
                                                 | msg @ parsing::ScreepsMessage::ServerTime { .. } => {
-                                                    debug!("received protocol message: {:?}", msg);
+                                                    if let Some(info) = server_info(&msg) {
+                                                        executor.relay_event(NetworkEvent::ServerInfo { info: info });
+                                                    }
                                                 }
                                                 other => {
                                                     warn!(
@@ -600,6 +672,9 @@ where
                         executor.notify.clone(),
                         executor.raw_send_sender.clone(),
                         executor.connection_id,
+                        executor.watched_memory.clone(),
+                        executor.disk_cache.clone(),
+                        executor.http_client.url.as_str().to_owned(),
                     ).start(stream);
 
                     executor.client = Some(sink);
@@ -609,66 +684,176 @@ where
             )
     }
 
-    fn subscribe(self, channel: Channel<'static>) -> impl Future<Item = Self, Error = WsExit> + 'static {
-        let message = websocket::OwnedMessage::Text(screeps_api::websocket::subscribe(&channel));
-        self.send(message)
-            .and_then(move |executor| {
-                match channel {
-                    Channel::RoomMapView { room_name, .. } => {
-                        executor.subscribed_map_view.borrow_mut().insert(room_name);
-                    }
-                    Channel::RoomDetail { room_name, .. } => {
-                        executor.subscribed_room_view.set(Some(room_name));
-                    }
-                    other => {
-                        warn!(
-                            "websocket executor not prepared to handle registering channel {}",
-                            other
-                        );
-                    }
-                };
-                Ok(executor)
-            })
-            .then(move |result| match result {
-                Ok(executor) => future::ok(executor),
-                Err(executor) => future::ok(executor),
-            })
+    /// Gets the channel for watching a memory path, if the logged in user is known.
+    fn memory_channel(&self, shard: Option<&str>, path: &str) -> Option<String> {
+        self.user_id.as_ref().map(|user_id| match shard {
+            Some(shard) => format!("user:{}/memory/{}/{}", user_id, shard, path),
+            None => format!("user:{}/memory/{}", user_id, path),
+        })
     }
 
-    fn unsubscribe(self, channel: Channel<'static>) -> impl Future<Item = Self, Error = WsExit> + 'static {
-        let message = websocket::OwnedMessage::Text(screeps_api::websocket::unsubscribe(&channel));
-        self.send(message)
-            .and_then(move |executor| {
-                match channel {
-                    Channel::RoomMapView {
-                        room_name,
-                        shard_name: _shard_name, // we just assume that there's only one shard for now.
-                    } => {
-                        executor.subscribed_map_view.borrow_mut().remove(&room_name);
-                    }
-                    Channel::RoomDetail {
-                        room_name,
-                        shard_name: _shard_name, // we just assume that there's only one shard for now.
-                    } => if Some(room_name) == executor.subscribed_room_view.get() {
-                        executor.subscribed_room_view.set(None);
-                    },
-                    other => {
-                        warn!(
-                            "websocket executor not prepared to handle registering channel {}",
-                            other
-                        );
-                    }
+    /// Gets all channels for the logged in user, such as console output and watched memory on a shard.
+    fn user_channels(&self, shard: Option<&str>) -> Vec<String> {
+        let user_id = match self.user_id {
+            Some(ref user_id) => user_id.clone(),
+            None => return Vec::new(),
+        };
+
+        let mut channels = vec![
+            Channel::user_console(user_id.clone()).to_string(),
+            Channel::user_cpu(user_id).to_string(),
+        ];
+        channels.extend(
+            self.watched_memory
+                .borrow()
+                .keys()
+                .filter_map(|path| self.memory_channel(shard, path)),
+        );
+        channels
+    }
+
+    /// Acquires channels for the logged in user the first time they are known, then subscribes to everything
+    /// held.
+    ///
+    /// This is called after each new connection is authenticated. If the user can't be found, everything else is
+    /// still subscribed to, and finding the user is tried again later by `sync_subscriptions`.
+    fn subscribe_user_channels(self) -> Box<Future<Item = Self, Error = Self>> {
+        fn failed<N, C, H, T>(
+            mut executor: Executor<N, C, H, T>,
+            error: screeps_api::Error,
+        ) -> future::FutureResult<(Executor<N, C, H, T>, Option<String>), Executor<N, C, H, T>>
+        where
+            C: hyper::client::Connect + 'static,
+            H: screeps_api::HyperClient<C> + 'static,
+            T: TokenStorage + 'static,
+            N: Notify + 'static,
+        {
+            warn!(
+                "failed to find the logged in user, retrying in {} seconds: {}",
+                USER_ID_RETRY_SECONDS, error
+            );
+            executor.relay_http_error(error);
+            executor.user_id_retry_at = Some(time::get_time() + time::Duration::seconds(USER_ID_RETRY_SECONDS));
+
+            future::ok((executor, None))
+        }
+
+        let user_id_future = match self.user_id.clone() {
+            Some(user_id) => Box::new(future::ok((self, Some(user_id)))) as Box<Future<Item = _, Error = _>>,
+            None => {
+                let execute = |executor: Self| match executor.http_client.my_info() {
+                    Ok(future) => Ok(future.then(move |result| match result {
+                        Ok(info) => future::ok((executor, Some(info.user_id))),
+                        Err(e) => failed(executor, e),
+                    })),
+                    Err(e) => Err((executor, e)),
                 };
-                Ok(executor)
-            })
-            .then(move |result| match result {
-                Ok(executor) => future::ok(executor),
-                Err(executor) => future::ok(executor),
-            })
+
+                utils::execute_or_login_and_execute(self, execute, failed)
+            }
+        };
+
+        Box::new(user_id_future.and_then(|(mut executor, user_id)| {
+            if let (None, Some(user_id)) = (executor.user_id.clone(), user_id) {
+                executor.user_id = Some(user_id);
+                executor.user_id_retry_at = None;
+
+                let channels = executor.user_channels(executor.settings.shard.as_ref().map(|s| &**s));
+                for channel in channels {
+                    debug!("acquiring user channel {}", channel);
+                    executor.subscriptions.acquire(channel);
+                }
+            }
+
+            match executor.subscriptions.flush() {
+                Some(message) => Box::new(executor.send(message)) as Box<Future<Item = _, Error = _>>,
+                None => Box::new(future::ok(executor)),
+            }
+        }))
+    }
+}
+
+/// Changes the rooms held in `held` to `rooms`, releasing channels for rooms no longer needed and acquiring
+/// channels for new rooms.
+fn update_held_rooms<F>(
+    subscriptions: &mut SubscriptionManager,
+    held: &mut HashSet<RoomName>,
+    rooms: HashSet<RoomName>,
+    channel: F,
+) where
+    F: Fn(RoomName) -> String,
+{
+    for &room_name in held.difference(&rooms) {
+        subscriptions.release(&channel(room_name));
     }
+    for &room_name in rooms.difference(held) {
+        subscriptions.acquire(channel(room_name));
+    }
+    *held = rooms;
+}
+
+/// Parses a text websocket frame, decompressing any compressed messages inside it first.
+fn parse_text_frame(
+    text: &str,
+) -> Result<screeps_api::websocket::SockjsMessage, screeps_api::websocket::parsing::ParseError> {
+    use screeps_api::websocket::parsing::ParseError;
+
+    let text = compression::decompress_sockjs_frame(text)
+        .map_err(|e| ParseError::Other(format!("failed to decompress websocket message: {}", e)))?;
+
+    screeps_api::websocket::SockjsMessage::parse(&text)
+}
+
+/// Parses a binary websocket frame, which contains a compressed text frame.
+fn parse_binary_frame(
+    data: &[u8],
+) -> Result<screeps_api::websocket::SockjsMessage, screeps_api::websocket::parsing::ParseError> {
+    use screeps_api::websocket::parsing::ParseError;
+
+    let text = compression::decompress_binary_frame(data)
+        .map_err(|e| ParseError::Other(format!("failed to decompress binary websocket message: {}", e)))?;
+
+    screeps_api::websocket::SockjsMessage::parse(&text)
+}
+
+/// Reads the server information in a `ServerProtocol`, `ServerPackage` or `ServerTime` message.
+///
+/// Protocol versions other than the supported one are warned about.
+fn server_info(message: &screeps_api::websocket::ScreepsMessage) -> Option<ServerInfo> {
+    use screeps_api::websocket::ScreepsMessage;
+
+    let info = match *message {
+        ScreepsMessage::ServerProtocol { protocol } => ServerInfo {
+            protocol: Some(protocol),
+            ..ServerInfo::default()
+        },
+        ScreepsMessage::ServerPackage { package } => ServerInfo {
+            package: Some(package),
+            ..ServerInfo::default()
+        },
+        ScreepsMessage::ServerTime { time } => ServerInfo {
+            time: Some(time),
+            ..ServerInfo::default()
+        },
+        _ => return None,
+    };
+
+    if info.protocol_mismatch() {
+        warn!(
+            "server uses protocol version {}, but only version {} is supported. \
+             Some features may not work.",
+            info.protocol.unwrap_or_default(),
+            SUPPORTED_PROTOCOL
+        );
+    }
+
+    Some(info)
 }
 
 mod read {
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::cell::RefCell;
     use std::sync::mpsc::Sender as StdSender;
 
     use futures::{future, Future, Stream};
@@ -676,11 +861,17 @@ mod read {
     use websocket::{OwnedMessage, WebSocketError};
     use futures::sync::mpsc::UnboundedSender;
 
-    use screeps_api::{self, TokenStorage};
-    use screeps_api::websocket::{ChannelUpdate, ScreepsMessage, SockjsMessage};
+    use serde_json::{self, Value};
+    use time;
+
+    use screeps_api::{self, RoomName, TokenStorage};
+    use screeps_api::websocket::{ChannelUpdate, RoomMapViewUpdate, ScreepsMessage, SockjsMessage,
+                                 UserConsoleUpdate};
 
+    use diskcache;
     use event::NetworkEvent;
     use Notify;
+    use super::MAP_VIEW_STORE_SECONDS;
     use super::types::WebsocketStream;
 
     pub struct ReaderData<N, T> {
@@ -690,6 +881,12 @@ mod read {
         notify: N,
         raw_send_sender: UnboundedSender<(u16, OwnedMessage)>,
         connection_id: u16,
+        watched_memory: Rc<RefCell<HashMap<String, usize>>>,
+        disk_cache: diskcache::Cache,
+        /// API url of the server, which disk cache entries are stored under.
+        server: String,
+        /// When the map view of each room was last stored in the disk cache.
+        stored_map_views: RefCell<HashMap<RoomName, time::Timespec>>,
     }
 
     /// marker error return to mean exiting the thread now.
@@ -704,6 +901,9 @@ mod read {
             notify: N,
             send: UnboundedSender<(u16, OwnedMessage)>,
             connection_id: u16,
+            watched_memory: Rc<RefCell<HashMap<String, usize>>>,
+            disk_cache: diskcache::Cache,
+            server: String,
         ) -> Self {
             ReaderData {
                 handle: handle,
@@ -712,6 +912,54 @@ mod read {
                 notify: notify,
                 raw_send_sender: send,
                 connection_id: connection_id,
+                watched_memory: watched_memory,
+                disk_cache: disk_cache,
+                server: server,
+                stored_map_views: RefCell::new(HashMap::new()),
+            }
+        }
+
+        /// Stores a room's map view in the disk cache, unless it was stored recently.
+        fn store_map_view(&self, shard: Option<&str>, room_name: RoomName, update: &RoomMapViewUpdate) {
+            let now = time::get_time();
+            {
+                let mut stored = self.stored_map_views.borrow_mut();
+                if let Some(&last) = stored.get(&room_name) {
+                    if now - last < time::Duration::seconds(MAP_VIEW_STORE_SECONDS) {
+                        return;
+                    }
+                }
+                stored.insert(room_name, now);
+            }
+
+            self.handle.spawn(
+                self.disk_cache
+                    .set_map_view(&self.server, shard, room_name, update)
+                    .then(|result| {
+                        if let Err(e) = result {
+                            warn!("error occurred storing to map view cache: {:?}", e);
+                        }
+                        Ok(())
+                    }),
+            );
+        }
+
+        /// Finds the watched memory path a channel is for.
+        ///
+        /// Channels are `user:<id>/memory/<path>`, or `user:<id>/memory/<shard>/<path>` on servers with shards.
+        fn watched_memory_path(&self, channel: &str) -> Option<String> {
+            if !channel.starts_with("user:") {
+                return None;
+            }
+            let rest = channel.splitn(2, "/memory/").nth(1)?;
+            let watched = self.watched_memory.borrow();
+
+            if watched.contains_key(rest) {
+                Some(rest.to_owned())
+            } else {
+                rest.splitn(2, '/')
+                    .nth(1)
+                    .and_then(|path| if watched.contains_key(path) { Some(path.to_owned()) } else { None })
             }
         }
 
@@ -764,7 +1012,15 @@ mod read {
 
         fn event_websocket_message(&self, message: OwnedMessage) -> Result<(), ExitNow> {
             match message {
-                OwnedMessage::Text(text) => match SockjsMessage::parse(&text) {
+                OwnedMessage::Text(text) => match super::parse_text_frame(&text) {
+                    Ok(message) => {
+                        self.event_sockjs_message(message)?;
+                    }
+                    Err(e) => {
+                        self.send(NetworkEvent::WebsocketParseError { error: e })?;
+                    }
+                },
+                OwnedMessage::Binary(data) => match super::parse_binary_frame(&data) {
                     Ok(message) => {
                         self.event_sockjs_message(message)?;
                     }
@@ -772,9 +1028,6 @@ mod read {
                         self.send(NetworkEvent::WebsocketParseError { error: e })?;
                     }
                 },
-                OwnedMessage::Binary(data) => {
-                    warn!("ignoring binary data received on websocket: {:?}", data);
-                }
                 OwnedMessage::Close(reason) => {
                     warn!("websocket closed: {:?}", reason);
                 }
@@ -806,11 +1059,12 @@ mod read {
         }
         fn event_screeps_message(&self, message: ScreepsMessage) -> Result<(), ExitNow> {
             match message {
-                // TODO: duplicated above in login protocol
                 msg @ ScreepsMessage::ServerProtocol { .. }
                 | msg @ ScreepsMessage::ServerPackage { .. }
                 | msg @ ScreepsMessage::ServerTime { .. } => {
-                    debug!("received protocol message: {:?}", msg);
+                    if let Some(info) = super::server_info(&msg) {
+                        self.send(NetworkEvent::ServerInfo { info: info })?;
+                    }
                 }
                 ScreepsMessage::AuthFailed => {
                     warn!(
@@ -841,8 +1095,11 @@ mod read {
             // TODO: what to do here if shard name does not equal saved shard? discard?
             match update {
                 ChannelUpdate::RoomMapView {
-                    room_name, update, ..
+                    room_name,
+                    shard_name,
+                    update,
                 } => {
+                    self.store_map_view(shard_name.as_ref().map(|s| &**s), room_name, &update);
                     let event = NetworkEvent::MapView {
                         room_name: room_name,
                         result: update,
@@ -851,15 +1108,64 @@ mod read {
                     self.send(event)?;
                 }
                 ChannelUpdate::RoomDetail {
-                    room_name, update, ..
+                    room_name,
+                    shard_name,
+                    update,
                 } => {
                     let event = NetworkEvent::RoomView {
                         room_name: room_name,
+                        shard: shard_name,
                         result: update,
                     };
                     debug!("received room view update for {}!", room_name);
                     self.send(event)?;
                 }
+                ChannelUpdate::UserConsole { update, .. } => {
+                    let event = match update {
+                        UserConsoleUpdate::Messages {
+                            log_messages,
+                            result_messages,
+                            shard,
+                        } => NetworkEvent::ConsoleOutput {
+                            shard: shard,
+                            messages: log_messages,
+                            results: result_messages,
+                            error: None,
+                        },
+                        UserConsoleUpdate::Error { message, shard } => NetworkEvent::ConsoleOutput {
+                            shard: shard,
+                            messages: Vec::new(),
+                            results: Vec::new(),
+                            error: Some(message),
+                        },
+                    };
+                    self.send(event)?;
+                }
+                ChannelUpdate::UserCpu { update, .. } => {
+                    self.send(NetworkEvent::UserCpu {
+                        cpu: update.cpu,
+                        memory: update.memory,
+                    })?;
+                }
+                ChannelUpdate::Other { channel, update } => match self.watched_memory_path(&channel) {
+                    Some(path) => {
+                        // memory values are sent as JSON text, or "undefined" for paths which don't exist.
+                        let value = match update {
+                            Value::String(text) => match serde_json::from_str(&text) {
+                                Ok(value) => value,
+                                Err(_) => Value::String(text),
+                            },
+                            other => other,
+                        };
+                        self.send(NetworkEvent::MemoryWatch {
+                            path: path,
+                            value: value,
+                        })?;
+                    }
+                    None => {
+                        warn!("received update for unexpected channel {}: {:#?}", channel, update);
+                    }
+                },
                 other => {
                     warn!("received unexpected channel update: {:#?}", other);
                 }
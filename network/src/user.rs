@@ -0,0 +1,68 @@
+//! Profiles of other players, as found by id or username.
+use screeps_api;
+
+/// A way to look up a user.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UserQuery {
+    Id(String),
+    Username(String),
+}
+
+/// A player's badge, drawn from one of the built in patterns.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Badge {
+    /// Pattern number, from 1 to 24, or 0 for a custom pattern which can't be drawn.
+    pub pattern: u32,
+    /// Primary, secondary and tertiary colors, as `#rrggbb` strings.
+    pub colors: [String; 3],
+    /// Pattern parameter, from -100 to 100.
+    pub param: i32,
+    /// Whether the pattern is mirrored.
+    pub flip: bool,
+}
+
+/// Public information about a player.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub id: String,
+    pub username: String,
+    /// Total control points earned, which decide the global control level.
+    pub gcl_points: u64,
+    pub badge: Option<Badge>,
+}
+
+impl UserProfile {
+    /// Global control level reached.
+    pub fn gcl(&self) -> u64 {
+        screeps_api::gcl_calc(self.gcl_points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UserProfile;
+
+    fn with_points(gcl_points: u64) -> UserProfile {
+        UserProfile {
+            id: "5a1b".to_owned(),
+            username: "daboross".to_owned(),
+            gcl_points: gcl_points,
+            badge: None,
+        }
+    }
+
+    #[test]
+    fn gcl_starts_at_one() {
+        assert_eq!(with_points(0).gcl(), 1);
+        assert_eq!(with_points(999_999).gcl(), 1);
+    }
+
+    #[test]
+    fn gcl_follows_control_points() {
+        // reaching level n + 1 takes 1,000,000 * n ^ 2.4 points in total.
+        assert_eq!(with_points(1_000_000).gcl(), 2);
+        assert_eq!(with_points(5_278_031).gcl(), 2);
+        assert_eq!(with_points(5_278_032).gcl(), 3);
+        assert_eq!(with_points(13_966_611).gcl(), 4);
+    }
+}
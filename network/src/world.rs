@@ -0,0 +1,128 @@
+//! Shape of the game world and the status of rooms in it.
+use screeps_api::RoomName;
+use time::Timespec;
+
+use request::RoomRect;
+
+/// Number of rooms across each axis of a shard.
+///
+/// The world is centered on the corner between W0N0, E0N0, W0S0 and E0S0, so a world 20 rooms wide spans from
+/// W9 to E9.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WorldSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WorldSize {
+    /// Gets every room in the world.
+    pub fn rooms(&self) -> RoomRect {
+        let half_width = (self.width / 2) as i32;
+        let half_height = (self.height / 2) as i32;
+
+        RoomRect::new(
+            RoomName {
+                x_coord: -half_width,
+                y_coord: -half_height,
+            }..RoomName {
+                x_coord: half_width,
+                y_coord: half_height,
+            },
+        )
+    }
+
+    /// Whether a room is inside the world. Rooms outside never exist, and have no terrain.
+    pub fn contains(&self, room: &RoomName) -> bool {
+        self.rooms().contains(room)
+    }
+}
+
+/// Whether a room can be entered, and whether it belongs to a protected area.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RoomStatus {
+    /// An ordinary room, open to everyone.
+    Normal,
+    /// A room which is not yet open, or is outside the borders of the world.
+    Closed,
+    /// A room in a novice area, which only new players can enter, until the given time.
+    Novice { until: Timespec },
+    /// A room in a respawn area, which players who have just respawned can enter, until the given time.
+    Respawn { until: Timespec },
+}
+
+/// A user mentioned in map stats.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MapUser {
+    pub id: String,
+    /// Username, or None if the server didn't include it.
+    pub username: Option<String>,
+}
+
+/// Who controls a room.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoomControl {
+    /// The controller is claimed, and at the given level.
+    Owned { user: MapUser, level: u32 },
+    /// The controller is reserved.
+    Reserved { user: MapUser },
+}
+
+impl RoomControl {
+    pub fn user(&self) -> &MapUser {
+        match *self {
+            RoomControl::Owned { ref user, .. } | RoomControl::Reserved { ref user } => user,
+        }
+    }
+}
+
+/// A message left on a room's controller.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RoomSign {
+    pub user: MapUser,
+    pub text: String,
+    /// Game time the sign was made.
+    pub game_time: u32,
+}
+
+/// Ownership of a single room, as given by the map stats endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RoomStats {
+    /// Owner or reserver of the controller, if any. Rooms without controllers are never controlled.
+    pub control: Option<RoomControl>,
+    pub sign: Option<RoomSign>,
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps_api::RoomName;
+
+    use super::WorldSize;
+
+    fn room(name: &str) -> RoomName {
+        RoomName::new(name).unwrap()
+    }
+
+    #[test]
+    fn world_is_centered() {
+        let size = WorldSize {
+            width: 20,
+            height: 20,
+        };
+        for name in &["W9N9", "E9N9", "W9S9", "E9S9", "W0N0", "E0S0"] {
+            assert!(size.contains(&room(name)), "expected world to contain {}", name);
+        }
+        for name in &["W10N0", "E10N0", "W0N10", "E0S10"] {
+            assert!(!size.contains(&room(name)), "expected world not to contain {}", name);
+        }
+    }
+
+    #[test]
+    fn world_rooms_match_size() {
+        let size = WorldSize {
+            width: 202,
+            height: 102,
+        };
+        let rooms = size.rooms();
+        assert_eq!((rooms.width(), rooms.height()), (202, 102));
+    }
+}
@@ -0,0 +1,441 @@
+#[macro_use]
+extern crate clap;
+extern crate fern;
+extern crate futures;
+#[macro_use]
+extern crate log;
+extern crate screeps_api;
+extern crate screeps_rs_network;
+extern crate screeps_rs_ui;
+#[macro_use]
+extern crate serde_json;
+extern crate time;
+
+use std::{env, io, process};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use futures::Future;
+
+use screeps_api::RoomName;
+use screeps_api::endpoints::room_terrain::TerrainType;
+use screeps_rs_network::{ActiveBranch, BlockingClient, CodeModule, ConnectionSettings, MapCacheData, RoomRect,
+                         Url};
+use screeps_rs_network::{blocking, code};
+use screeps_rs_network::diskcache;
+use screeps_rs_ui::rendering::{export, PlayerColors};
+
+fn main() {
+    let matches = App::new("screeps-rs-cli")
+        .version(crate_version!())
+        .author(&*crate_authors!().replace(":", "\n"))
+        .about("Headless command line client for the Screeps JavaScript MMO")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("enables verbose logging"),
+        )
+        .arg(
+            Arg::with_name("server")
+                .short("s")
+                .long("server")
+                .value_name("URL")
+                .takes_value(true)
+                .help("server to connect to, defaults to the official server"),
+        )
+        .arg(
+            Arg::with_name("username")
+                .short("u")
+                .long("username")
+                .value_name("USERNAME")
+                .takes_value(true)
+                .help("username to login with"),
+        )
+        .arg(
+            Arg::with_name("password")
+                .short("p")
+                .long("password")
+                .value_name("PASSWORD")
+                .takes_value(true)
+                .help("password to login with, read from SCREEPS_PASSWORD if not given"),
+        )
+        .arg(
+            Arg::with_name("shard")
+                .long("shard")
+                .value_name("SHARD")
+                .takes_value(true)
+                .help("shard to make requests on"),
+        )
+        .subcommand(SubCommand::with_name("login").about("Tests logging in"))
+        .subcommand(SubCommand::with_name("whoami").about("Shows information on the logged in user"))
+        .subcommand(SubCommand::with_name("shards").about("Lists the server's shards"))
+        .subcommand(
+            SubCommand::with_name("terrain")
+                .about("Prints a room's terrain")
+                .arg(Arg::with_name("room").required(true).help("room name, such as W1N1"))
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("print terrain as JSON rather than ASCII"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch-map")
+                .about("Streams map view updates as JSON lines")
+                .arg(
+                    Arg::with_name("region")
+                        .required(true)
+                        .help("inclusive region of rooms, such as W10N10:W5N5"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-map")
+                .about("Renders terrain and map views of a region to a PNG file")
+                .arg(
+                    Arg::with_name("region")
+                        .required(true)
+                        .help("inclusive region of rooms, such as W10N10:W5N5"),
+                )
+                .arg(Arg::with_name("output").required(true).help("PNG file to write"))
+                .arg(
+                    Arg::with_name("scale")
+                        .long("scale")
+                        .value_name("PIXELS")
+                        .default_value("4")
+                        .help("size of a single room tile in pixels"),
+                )
+                .arg(
+                    Arg::with_name("wait")
+                        .long("wait")
+                        .value_name("SECONDS")
+                        .default_value("5")
+                        .help("time to spend collecting map view updates, or 0 to only render terrain"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("code")
+                .about("Manages deployed code branches")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("branches").about("Lists branches, marking active ones"))
+                .subcommand(
+                    SubCommand::with_name("download")
+                        .about("Writes a branch's modules to .js and .wasm files in a directory")
+                        .arg(Arg::with_name("branch").required(true).help("branch to download"))
+                        .arg(Arg::with_name("directory").required(true).help("directory to write to")),
+                )
+                .subcommand(
+                    SubCommand::with_name("upload")
+                        .about("Replaces a branch's modules with the .js and .wasm files in a directory")
+                        .arg(Arg::with_name("branch").required(true).help("branch to upload to"))
+                        .arg(Arg::with_name("directory").required(true).help("directory to read from")),
+                )
+                .subcommand(
+                    SubCommand::with_name("activate")
+                        .about("Makes a branch the one running in the world")
+                        .arg(Arg::with_name("branch").required(true).help("branch to activate"))
+                        .arg(
+                            Arg::with_name("sim")
+                                .long("sim")
+                                .help("activate the branch in the simulation room instead"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cache")
+                .about("Manages the disk cache shared with screeps-rs-client")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("stats").about("Shows cache usage"))
+                .subcommand(SubCommand::with_name("purge").about("Removes everything from the cache")),
+        )
+        .get_matches();
+
+    init_logger(matches.is_present("verbose"));
+
+    if let Err(e) = run(&matches) {
+        let _ = writeln!(io::stderr(), "error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn init_logger(verbose: bool) {
+    fern::Dispatch::new()
+        .level(if verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Warn
+        })
+        .level_for("rustls", log::LevelFilter::Warn)
+        .level_for("hyper", log::LevelFilter::Warn)
+        .format(|out, msg, record| out.finish(format_args!("[{}] {}: {}", record.level(), record.target(), msg)))
+        // stdout is reserved for command output.
+        .chain(io::stderr())
+        .apply()
+        .unwrap_or_else(|_| {
+            let _ = writeln!(io::stderr(), "logging initialization failed: a global logger was already set!");
+        });
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    if let ("cache", Some(sub)) = matches.subcommand() {
+        return cache(sub);
+    }
+
+    let mut client = BlockingClient::new(settings(matches)?);
+
+    match matches.subcommand() {
+        ("login", _) => {
+            client.login().map_err(|e| e.to_string())?;
+            println!("logged in successfully");
+        }
+        ("whoami", _) => {
+            let info = client.my_info().map_err(|e| e.to_string())?;
+            println!("{} ({}) - GCL {}", info.username, info.user_id, screeps_api::gcl_calc(info.gcl_points));
+        }
+        ("shards", _) => match client.shard_list().map_err(|e| e.to_string())? {
+            Some(shards) => for shard_info in shards {
+                println!("{}", shard_info.as_ref());
+            },
+            None => println!("<no shards>"),
+        },
+        ("terrain", Some(sub)) => {
+            let room_name = parse_room(sub.value_of("room").expect("expected required argument to exist"))?;
+            let terrain = client.room_terrain(room_name).map_err(|e| e.to_string())?;
+
+            let rows = terrain
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&terrain_type| match terrain_type {
+                            TerrainType::Plains => '.',
+                            TerrainType::Swamp => '~',
+                            TerrainType::SwampyWall | TerrainType::Wall => '#',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+
+            if sub.is_present("json") {
+                println!("{}", json!({ "room": room_name.to_string(), "terrain": rows }));
+            } else {
+                for row in rows {
+                    println!("{}", row);
+                }
+            }
+        }
+        ("watch-map", Some(sub)) => {
+            let rooms = parse_region(sub.value_of("region").expect("expected required argument to exist"))?;
+
+            let stdout = io::stdout();
+            for result in client.subscribe_map(rooms) {
+                match result {
+                    Ok((room_name, update)) => {
+                        let line = json!({
+                            "room": room_name.to_string(),
+                            "roads": update.roads,
+                            "walls": update.walls,
+                            "power": update.power_or_power_bank,
+                            "portals": update.portals,
+                            "sources": update.sources,
+                            "minerals": update.minerals,
+                            "controllers": update.controllers,
+                            "keeper_lairs": update.keeper_lairs,
+                            "users": update.users_objects,
+                        });
+                        let mut out = stdout.lock();
+                        writeln!(out, "{}", line)
+                            .and_then(|()| out.flush())
+                            .map_err(|e| e.to_string())?;
+                    }
+                    // rooms without activity won't send updates, so keep waiting.
+                    Err(blocking::Error::TimedOut) => (),
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+        }
+        ("export-map", Some(sub)) => {
+            let rooms = parse_region(sub.value_of("region").expect("expected required argument to exist"))?;
+            let output = sub.value_of("output").expect("expected required argument to exist");
+            let scale = value_t!(sub, "scale", u32).map_err(|e| e.to_string())?;
+            let wait = value_t!(sub, "wait", u64).map_err(|e| e.to_string())?;
+
+            let mut data = MapCacheData::default();
+
+            for room_name in rooms {
+                match client.room_terrain(room_name) {
+                    Ok(terrain) => {
+                        data.terrain.insert(room_name, (time::get_time(), Some(terrain)));
+                    }
+                    Err(blocking::Error::Api(e)) => {
+                        warn!("couldn't fetch terrain for {}, leaving it blank: {}", room_name, e);
+                        data.terrain.insert(room_name, (time::get_time(), None));
+                    }
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+
+            if wait > 0 {
+                let room_count = rooms.len();
+                let deadline = Instant::now() + Duration::from_secs(wait);
+                // poll in short intervals so that the deadline is respected.
+                client.set_timeout(Duration::from_secs(1));
+
+                let mut updates = client.subscribe_map(rooms);
+                while data.map_views.len() < room_count && Instant::now() < deadline {
+                    match updates.next().expect("expected map update iterator to never end") {
+                        Ok((room_name, update)) => {
+                            data.map_views.insert(room_name, (time::get_time(), update));
+                        }
+                        Err(blocking::Error::TimedOut) => (),
+                        Err(e) => return Err(e.to_string()),
+                    }
+                }
+            }
+
+            export::rasterize(rooms, &data, &PlayerColors::new(), scale)
+                .map_err(|e| format!("failed to export {}: {}", output, e))?
+                .write_png(output)
+                .map_err(|e| format!("failed to write {}: {}", output, e))?;
+            println!("wrote {}", output);
+        }
+        ("code", Some(sub)) => return manage_code(&mut client, sub),
+        (other, _) => return Err(format!("unknown subcommand: {}", other)),
+    }
+
+    Ok(())
+}
+
+fn manage_code(client: &mut BlockingClient, matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        ("branches", _) => for branch in client.branches().map_err(|e| e.to_string())? {
+            let mut active = Vec::new();
+            if branch.active_world {
+                active.push("world");
+            }
+            if branch.active_sim {
+                active.push("sim");
+            }
+            if active.is_empty() {
+                println!("{}", branch.name);
+            } else {
+                println!("{} ({})", branch.name, active.join(", "));
+            }
+        },
+        ("download", Some(sub)) => {
+            let branch = sub.value_of("branch").expect("expected required argument to exist");
+            let directory = sub.value_of("directory").expect("expected required argument to exist");
+
+            let modules = client.code(branch).map_err(|e| e.to_string())?;
+            code::write_directory(directory, &modules).map_err(|e| format!("failed to write {}: {}", directory, e))?;
+            println!("wrote {} modules to {}", modules.len(), directory);
+        }
+        ("upload", Some(sub)) => {
+            let branch = sub.value_of("branch").expect("expected required argument to exist");
+            let directory = sub.value_of("directory").expect("expected required argument to exist");
+
+            let modules = code::load_directory(directory).map_err(|e| format!("failed to read {}: {}", directory, e))?;
+            if modules.is_empty() {
+                return Err(format!("no .js or .wasm files found in {}", directory));
+            }
+            for (name, module) in &modules {
+                match *module {
+                    CodeModule::Text(ref text) => println!("{}: {} bytes", name, text.len()),
+                    CodeModule::Binary(ref data) => println!("{}: {} bytes (binary)", name, data.len()),
+                }
+            }
+
+            let count = modules.len();
+            client.upload_code(branch, modules).map_err(|e| e.to_string())?;
+            println!("uploaded {} modules to {}", count, branch);
+        }
+        ("activate", Some(sub)) => {
+            let branch = sub.value_of("branch").expect("expected required argument to exist");
+            let target = if sub.is_present("sim") {
+                ActiveBranch::Sim
+            } else {
+                ActiveBranch::World
+            };
+
+            client.set_active_branch(branch, target).map_err(|e| e.to_string())?;
+            println!("activated {}", branch);
+        }
+        (other, _) => return Err(format!("unknown code subcommand: {}", other)),
+    }
+
+    Ok(())
+}
+
+fn cache(matches: &ArgMatches) -> Result<(), String> {
+    let cache = diskcache::Cache::load().map_err(|e| e.to_string())?;
+
+    match matches.subcommand() {
+        ("stats", _) => {
+            let stats = cache.stats().wait().map_err(|e| format!("{:?}", e))?;
+            for (kind, count) in &stats.entries {
+                println!("{}: {} entries", kind, count);
+            }
+            if stats.unknown_entries > 0 {
+                println!("unreadable: {} entries", stats.unknown_entries);
+            }
+            println!("total size: {} bytes", stats.total_bytes);
+        }
+        ("purge", _) => {
+            let removed = cache.purge().wait().map_err(|e| format!("{:?}", e))?;
+            println!("removed {} entries", removed);
+        }
+        (other, _) => return Err(format!("unknown cache subcommand: {}", other)),
+    }
+
+    Ok(())
+}
+
+fn settings(matches: &ArgMatches) -> Result<ConnectionSettings, String> {
+    let api_url = match matches.value_of("server") {
+        None | Some("") => screeps_api::DEFAULT_OFFICIAL_API_URL
+            .parse()
+            .expect("expected default URL to parse"),
+        Some(server) => if server.starts_with("http") {
+            server.parse()
+        } else {
+            format!("http://{}", server).parse()
+        }.map(|url: Url| {
+            url.join("api/")
+                .expect("expected hardcoded URL segment to parse")
+        })
+            .map_err(|e| format!("server URL invalid: {}", e))?,
+    };
+
+    let username = matches.value_of("username").unwrap_or("").to_owned();
+    let password = match matches.value_of("password") {
+        Some(password) => password.to_owned(),
+        None => env::var("SCREEPS_PASSWORD").unwrap_or_default(),
+    };
+
+    Ok(ConnectionSettings::with_url(
+        api_url,
+        username,
+        password,
+        matches.value_of("shard").map(ToOwned::to_owned),
+    ))
+}
+
+fn parse_room(name: &str) -> Result<RoomName, String> {
+    RoomName::new(name).map_err(|e| format!("invalid room name '{}': {}", name, e))
+}
+
+/// Parses an inclusive region of the form `W10N10:W5N5`.
+fn parse_region(region: &str) -> Result<RoomRect, String> {
+    let mut split = region.splitn(2, ':');
+    let start = parse_room(split.next().unwrap_or(""))?;
+    let end = match split.next() {
+        Some(end) => parse_room(end)?,
+        None => start,
+    };
+
+    let rooms = RoomRect::new(start..end);
+
+    Ok(RoomRect::new(rooms.start..(rooms.end + (1, 1))))
+}
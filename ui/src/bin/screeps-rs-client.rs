@@ -39,13 +39,32 @@ fn main() {
                 .takes_value(true)
                 .multiple(true),
         )
+        .arg(
+            Arg::with_name("max-map-rooms")
+                .long("max-map-rooms")
+                .value_name("ROOMS")
+                .help("Most rooms to receive live map view updates for at once")
+                .long_help(
+                    "Sets the most rooms to receive live map view updates for at once. Rooms past this show their \
+                     last known map view, and are refreshed a few at a time.\n\
+                     Lower this to use less bandwidth when zoomed out.",
+                )
+                .takes_value(true),
+        )
         .get_matches();
 
+    let max_map_rooms = if matches.is_present("max-map-rooms") {
+        Some(value_t!(matches, "max-map-rooms", usize).unwrap_or_else(|e| e.exit()))
+    } else {
+        None
+    };
+
     screeps_rs_ui::main(
         matches.is_present("verbose"),
         matches
             .values_of("debug-modules")
             .into_iter()
             .flat_map(|iter| iter),
+        max_map_rooms,
     );
 }
@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+
+use conrod::{self, color, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget};
+use conrod::widget::*;
+
+use screeps_rs_network::ConsoleLevel;
+use widgets::text_box::{self, TextBox};
+use ui_state::{ConsoleState, Event as UiEvent, MenuState, PanelStates};
+use network_integration::NetworkCache;
+
+use super::{Ids, HEADER_HEIGHT};
+
+const CONSOLE_HEIGHT: conrod::Scalar = 250.0;
+const CONSOLE_PADDING: conrod::Scalar = 5.0;
+const CONSOLE_LINE_HEIGHT: conrod::Scalar = 18.0;
+const FILTER_BUTTON_WIDTH: conrod::Scalar = 80.0;
+const FILTER_BUTTON_HEIGHT: conrod::Scalar = 20.0;
+const INPUT_HEIGHT: conrod::Scalar = 24.0;
+
+pub struct ConsoleIds {
+    pub panel_toggle: Id,
+    pub panel_canvas: Id,
+    lines: Id,
+    input: Id,
+    filter_all: Id,
+    filter_shards: id::List,
+}
+
+impl ConsoleIds {
+    pub fn new(gen: &mut id::Generator) -> Self {
+        ConsoleIds {
+            panel_toggle: gen.next(),
+            panel_canvas: gen.next(),
+            lines: gen.next(),
+            input: gen.next(),
+            filter_all: gen.next(),
+            filter_shards: id::List::new(),
+        }
+    }
+}
+
+fn level_color(level: ConsoleLevel) -> conrod::Color {
+    match level {
+        ConsoleLevel::Debug => color::GREY,
+        ConsoleLevel::Info => color::WHITE,
+        ConsoleLevel::Warning => color::YELLOW,
+        ConsoleLevel::Error => color::LIGHT_RED,
+        ConsoleLevel::Result => color::LIGHT_BLUE,
+    }
+}
+
+pub fn console_panel_available(
+    ui: &mut conrod::UiCell,
+    ids: &mut Ids,
+    panels: &PanelStates,
+    state: &ConsoleState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let toggle_clicks = Button::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .w_h(100.0, HEADER_HEIGHT)
+        // label
+        .label("Console")
+        .small_font(&ui)
+        .left_justify_label()
+        .label_color(color::WHITE)
+        // place
+        .parent(ids.root.header)
+        .right_from(ids.left_panel.panel_toggle, 0.0)
+        .set(ids.console.panel_toggle, ui)
+        // now TimesClicked(u16)
+        .0;
+
+    match panels.console {
+        MenuState::Open => {
+            console_panel_open(ui, ids, state, net_cache, update);
+
+            if toggle_clicks % 2 == 1 {
+                update.push_back(UiEvent::ConsoleClosed);
+            }
+        }
+        MenuState::Closed => if toggle_clicks % 2 == 1 {
+            update.push_back(UiEvent::ConsoleOpened);
+        },
+    }
+}
+
+fn console_panel_open(
+    ui: &mut conrod::UiCell,
+    ids: &mut Ids,
+    state: &ConsoleState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let width = ui.window_dim()[0];
+
+    Canvas::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .w_h(width, CONSOLE_HEIGHT)
+        // place
+        .floating(true)
+        .bottom_left_of(ids.root.root)
+        .set(ids.console.panel_canvas, ui);
+
+    // shard filter
+    let shards = match net_cache.shard_list() {
+        Some(Some(shards)) => shards
+            .iter()
+            .map(|info| info.as_ref().to_owned())
+            .collect::<Vec<String>>(),
+        _ => Vec::new(),
+    };
+
+    let filter_button = |label: &str, selected: bool| {
+        Button::new()
+            .color(if selected {
+                color::CHARCOAL
+            } else {
+                color::DARK_CHARCOAL
+            })
+            .border(0.0)
+            .w_h(FILTER_BUTTON_WIDTH, FILTER_BUTTON_HEIGHT)
+            .label(label)
+            .label_font_size(12)
+            .label_color(color::WHITE)
+    };
+
+    if filter_button("all", state.shard_filter.is_none())
+        .top_left_with_margin_on(ids.console.panel_canvas, CONSOLE_PADDING)
+        .set(ids.console.filter_all, ui)
+        .was_clicked()
+    {
+        update.push_back(UiEvent::ConsoleShardFilter(None));
+    }
+
+    if ids.console.filter_shards.len() < shards.len() {
+        ids.console
+            .filter_shards
+            .resize(shards.len(), &mut ui.widget_id_generator());
+    }
+
+    let mut last_button = ids.console.filter_all;
+    for (shard, &id) in shards.iter().zip(ids.console.filter_shards.iter()) {
+        if filter_button(shard, state.shard_filter.as_ref() == Some(shard))
+            .right_from(last_button, CONSOLE_PADDING)
+            .set(id, ui)
+            .was_clicked()
+        {
+            update.push_back(UiEvent::ConsoleShardFilter(Some(shard.clone())));
+        }
+        last_button = id;
+    }
+
+    // command input
+    let input_events = TextBox::new(&state.input)
+        // style
+        .w_h(width - CONSOLE_PADDING * 2.0, INPUT_HEIGHT)
+        .font_size(ui.theme.font_size_small)
+        .left_justify()
+        .pad_text(5.0)
+        // position
+        .mid_bottom_with_margin_on(ids.console.panel_canvas, CONSOLE_PADDING)
+        .set(ids.console.input, ui);
+
+    // starts loading stored history, so that it's available by the time it's needed.
+    net_cache.console_history();
+
+    let mut input = state.input.clone();
+    let mut history_position = state.history_position;
+    for event in input_events {
+        match event {
+            text_box::Event::Update(text) => {
+                input = text.clone();
+                history_position = None;
+                update.push_back(UiEvent::ConsoleInput(text));
+            }
+            text_box::Event::Enter => {
+                if !input.trim().is_empty() {
+                    net_cache.send_console_command(state.shard_filter.clone(), input.clone());
+                    update.push_back(UiEvent::ConsoleSubmitted);
+                }
+            }
+            text_box::Event::Up | text_box::Event::Down => {
+                let history = net_cache.console_history();
+                let new_position = match (event, history_position) {
+                    (_, _) if history.is_empty() => continue,
+                    (text_box::Event::Up, None) => Some(history.len() - 1),
+                    (text_box::Event::Up, Some(position)) => Some(position.saturating_sub(1)),
+                    (_, Some(position)) if position + 1 < history.len() => Some(position + 1),
+                    (_, _) => None,
+                };
+
+                if new_position != history_position {
+                    history_position = new_position;
+                    input = new_position
+                        .and_then(|position| history.get(position).cloned())
+                        .unwrap_or_default();
+                    update.push_back(UiEvent::ConsoleHistorySelected {
+                        position: history_position,
+                        text: input.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // output, newest first from the bottom up
+    let lines = net_cache
+        .console()
+        .iter()
+        .rev()
+        .filter(|line| match (state.shard_filter.as_ref(), line.shard.as_ref()) {
+            (Some(filter), Some(shard)) => filter == shard,
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+
+    let (mut items, scrollbar) = List::flow_up(lines.len())
+        .item_size(CONSOLE_LINE_HEIGHT)
+        .scrollbar_on_top()
+        .w_h(
+            width - CONSOLE_PADDING * 2.0,
+            CONSOLE_HEIGHT - FILTER_BUTTON_HEIGHT - INPUT_HEIGHT - CONSOLE_PADDING * 4.0,
+        )
+        .up_from(ids.console.input, CONSOLE_PADDING)
+        .set(ids.console.lines, ui);
+
+    while let Some(item) = items.next(ui) {
+        let line = lines[item.i];
+        let text = Text::new(&line.text)
+            .font_size(ui.theme.font_size_small)
+            .no_line_wrap()
+            .left_justify()
+            .color(level_color(line.level));
+        item.set(text, ui);
+    }
+
+    if let Some(scrollbar) = scrollbar {
+        scrollbar.set(ui);
+    }
+}
@@ -5,12 +5,12 @@ use conrod::widget::*;
 
 use time;
 
-use screeps_rs_network::{self, ConnectionSettings};
+use screeps_rs_network::{self, ConnectionSettings, Url, UrlParseError};
 use widgets::text_box::TextBox;
 use ui_state::{Event as UiEvent, LoginScreenState};
 
 use app::AppCell;
-use layout::{frame, HEADER_HEIGHT};
+use layout::{frame, server_description, HEADER_HEIGHT};
 const LOGIN_WIDTH: conrod::Scalar = 300.0;
 const LOGIN_HEIGHT: conrod::Scalar = 200.0;
 
@@ -18,6 +18,9 @@ const LOGIN_PADDING: conrod::Scalar = 10.0;
 
 const LOGIN_LOWER_SECTION_HEIGHT: conrod::Scalar = (LOGIN_HEIGHT - HEADER_HEIGHT) / 3.0;
 
+/// Milliseconds after the server field stops changing before looking up the server's version.
+const SERVER_VERSION_DELAY_MS: i64 = 750;
+
 #[derive(Copy, Clone)]
 struct TextboxIds {
     canvas: Id,
@@ -36,6 +39,7 @@ pub struct LoginIds {
     submit_canvas: Id,
     exit_button: Id,
     submit_button: Id,
+    server_info: Id,
 }
 
 impl TextboxIds {
@@ -60,6 +64,7 @@ impl LoginIds {
             submit_canvas: gen.next(),
             exit_button: gen.next(),
             submit_button: gen.next(),
+            server_info: gen.next(),
         }
     }
 }
@@ -144,6 +149,7 @@ pub fn create_ui(app: &mut AppCell, state: &LoginScreenState, update: &mut VecDe
                     enter_pressed = true;
                     break;
                 }
+                TextBoxEvent::Up | TextBoxEvent::Down => (),
             }
         }
         enter_pressed
@@ -160,6 +166,29 @@ pub fn create_ui(app: &mut AppCell, state: &LoginScreenState, update: &mut VecDe
             .set(ids.label, ui);
     }
 
+    // the version is fetched over HTTP, since the websocket which also sends it isn't open until logging in.
+    let typing = state
+        .server_edited
+        .map_or(false, |edited| time::get_time() - edited < time::Duration::milliseconds(SERVER_VERSION_DELAY_MS));
+    let version = match server_url(&state.server) {
+        Ok(ref url) if !typing => app.net_cache
+            .server_version(url)
+            .and_then(|info| server_description(Some(url), info)),
+        _ => None,
+    };
+    if let Some((text, mismatch)) = version {
+        Text::new(&text)
+            // style
+            .font_size(ui.theme.font_size_small)
+            .color(if mismatch { color::LIGHT_RED } else { color::WHITE })
+            .center_justify()
+            .no_line_wrap()
+            // position
+            .down_from(ids.login.root, LOGIN_PADDING)
+            .align_middle_x_of(ids.login.root)
+            .set(ids.login.server_info, ui);
+    }
+
     textbox_label("server", ids.login.server, ui);
     textbox_label("username", ids.login.username, ui);
     textbox_label("password", ids.login.password, ui);
@@ -256,27 +285,11 @@ pub fn create_ui(app: &mut AppCell, state: &LoginScreenState, update: &mut VecDe
     } else if (submit_pressed || password_enter_pressed || username_enter_pressed || server_enter_pressed
         || shard_enter_pressed) && state.username.len() > 0 && state.password.len() > 0
     {
-        use screeps_rs_network::Url;
-        let server = if state.server.len() == 0 {
-            ::screeps_api::DEFAULT_OFFICIAL_API_URL
-                .parse()
-                .expect("expected default URL to parse")
-        } else {
-            let result = if state.server.starts_with("http") || state.server.starts_with("https") {
-                state.server.parse()
-            } else {
-                format!("http://{}", state.server).parse()
-            }.map(|url: Url| {
-                url.join("api/")
-                    .expect("expected hardcoded URL segment to parse")
-            });
-
-            match result {
-                Ok(url) => url,
-                Err(e) => {
-                    warn!("server URL invalid: {}", e);
-                    return;
-                }
+        let server = match server_url(&state.server) {
+            Ok(url) => url,
+            Err(e) => {
+                warn!("server URL invalid: {}", e);
+                return;
             }
         };
         // TODO: UI option for shard.
@@ -303,3 +316,20 @@ pub fn create_ui(app: &mut AppCell, state: &LoginScreenState, update: &mut VecDe
         update.push_front(UiEvent::LoginSubmitted(time::now_utc()));
     }
 }
+
+/// Gets the API url of the server typed into the login screen, or the official server if nothing is typed.
+fn server_url(server: &str) -> Result<Url, UrlParseError> {
+    if server.is_empty() {
+        return Ok(::screeps_api::DEFAULT_OFFICIAL_API_URL
+            .parse()
+            .expect("expected default URL to parse"));
+    }
+
+    let url = if server.starts_with("http") || server.starts_with("https") {
+        server.parse::<Url>()?
+    } else {
+        format!("http://{}", server).parse::<Url>()?
+    };
+
+    Ok(url.join("api/").expect("expected hardcoded URL segment to parse"))
+}
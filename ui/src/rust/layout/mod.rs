@@ -1,6 +1,9 @@
 mod login_screen;
 mod room_view;
 mod left_panel;
+mod console;
+mod tools;
+mod watch;
 
 use std::collections::VecDeque;
 
@@ -8,6 +11,8 @@ use conrod::{self, color, Borderable, Colorable, Widget};
 use conrod::widget::*;
 use conrod::widget::id;
 
+use screeps_rs_network::{ServerInfo, Url, SUPPORTED_PROTOCOL};
+
 use app::AppCell;
 use rendering::AdditionalRender;
 use ui_state::{ScreenState, State};
@@ -33,6 +38,31 @@ pub fn create_ui(app: &mut AppCell, state: &mut State) {
     state.transform(update.drain(..));
 }
 
+/// Describes the server being connected to and its versions, along with whether its protocol is unsupported.
+///
+/// Returns None until the server has sent any information.
+fn server_description(server: Option<&Url>, info: &ServerInfo) -> Option<(String, bool)> {
+    if info.protocol.is_none() && info.package.is_none() {
+        return None;
+    }
+
+    let mut text = match server.and_then(Url::host_str) {
+        Some(host) => host.to_owned(),
+        None => "server".to_owned(),
+    };
+    if let Some(package) = info.package {
+        text.push_str(&format!(" v{}", package));
+    }
+    if let Some(protocol) = info.protocol {
+        text.push_str(&format!(" (protocol {})", protocol));
+    }
+    if info.protocol_mismatch() {
+        text.push_str(&format!(" - unsupported, expected protocol {}", SUPPORTED_PROTOCOL));
+    }
+
+    Some((text, info.protocol_mismatch()))
+}
+
 fn frame(ui: &mut conrod::UiCell, ids: &Ids, body_id: Id, body: Canvas) {
     let header = Canvas::new()
         .color(color::DARK_CHARCOAL)
@@ -64,6 +94,9 @@ impl RootIds {
 pub struct Ids {
     root: RootIds,
     left_panel: left_panel::LeftPanelIds,
+    console: console::ConsoleIds,
+    tools: tools::ToolsIds,
+    watch: watch::WatchIds,
     login: login_screen::LoginIds,
     room_view: room_view::RoomViewIds,
 }
@@ -73,6 +106,9 @@ impl Ids {
         Ids {
             root: RootIds::new(gen),
             left_panel: left_panel::LeftPanelIds::new(gen),
+            console: console::ConsoleIds::new(gen),
+            tools: tools::ToolsIds::new(gen),
+            watch: watch::WatchIds::new(gen),
             login: login_screen::LoginIds::new(gen),
             room_view: room_view::RoomViewIds::new(gen),
         }
@@ -1,34 +1,84 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::{fs, thread};
 
-use conrod::{color, Borderable, Colorable, Positionable, Rect, Sizeable, Widget};
+use conrod::{color, Borderable, Colorable, Labelable, Positionable, Rect, Sizeable, Widget};
+use conrod::image::Id as ImageId;
 use conrod::widget::*;
+use glium;
 
-use screeps_api;
+use screeps_api::{self, RoomName};
 
-use screeps_rs_network::SelectedRooms;
+use screeps_rs_network::{ActiveBranch, MapCacheData, MapUser, RoomControl, RoomRect};
 use ui_state::{self, Event as UiEvent, MapClickEvent, MapPanEvent, MapScreenState, MapZoomEvent, ScrollState};
-use rendering::MapViewOffset;
+use rendering::{MapViewOffset, PlayerColors, RenderCache};
+use rendering::export;
+use rendering::player_colors::{relation_color, user_color};
+use relations::Relation;
 
 use app::AppCell;
-use super::{frame, AdditionalRender};
+use network_integration::NetworkCache;
+use super::{frame, server_description, AdditionalRender, HEADER_HEIGHT};
 use super::left_panel::left_panel_available;
+use super::console::console_panel_available;
+use super::tools::tools_panel_available;
+use super::watch::watch_panel_available;
 use self::room_view_widget::ScrollableRoomView;
 use map_view_utils::zoom_multiplier_from_factor;
+use widgets::sparkline::Sparkline;
+
+/// Size of a single room tile in exported images, in pixels.
+const EXPORT_PIXELS_PER_TILE: u32 = 4;
+/// Margin around the column of widgets in the left panel.
+const PANEL_COLUMN_MARGIN: f64 = 10.0;
+const BRANCH_ROW_HEIGHT: f64 = 24.0;
+/// Most players without a relation listed in the legend.
+const LEGEND_MAX_PLAYERS: usize = 8;
+const LEGEND_SWATCH_SIZE: f64 = 12.0;
+/// Size of badges shown next to usernames in the left panel.
+const PANEL_BADGE_SIZE: f64 = 16.0;
 
 pub struct RoomViewIds {
     username_gcl_header: Id,
+    username_badge: Id,
+    server_header: Id,
     display: Id,
     scroll_widget: Id,
     shard_dropdown: Id,
+    export_button: Id,
+    cpu_graph: Id,
+    cpu_label: Id,
+    tick_label: Id,
+    selected_room_info: Id,
+    selected_room_badge: Id,
+    legend_swatches: id::List,
+    legend_labels: id::List,
+    legend_badges: id::List,
+    branches_label: Id,
+    branch_world_buttons: id::List,
+    branch_sim_buttons: id::List,
 }
 
 impl RoomViewIds {
     pub fn new(gen: &mut id::Generator) -> Self {
         RoomViewIds {
             username_gcl_header: gen.next(),
+            username_badge: gen.next(),
+            server_header: gen.next(),
             display: gen.next(),
             scroll_widget: gen.next(),
             shard_dropdown: gen.next(),
+            export_button: gen.next(),
+            cpu_graph: gen.next(),
+            cpu_label: gen.next(),
+            tick_label: gen.next(),
+            selected_room_info: gen.next(),
+            selected_room_badge: gen.next(),
+            legend_swatches: id::List::new(),
+            legend_labels: id::List::new(),
+            legend_badges: id::List::new(),
+            branches_label: gen.next(),
+            branch_world_buttons: id::List::new(),
+            branch_sim_buttons: id::List::new(),
         }
     }
 }
@@ -38,6 +88,8 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
         ref mut ui,
         ref mut net_cache,
         ref mut ids,
+        ref mut image_cache,
+        display,
         ..
     } = *app;
 
@@ -46,6 +98,17 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
     frame(ui, ids, ids.root.body, body);
 
     left_panel_available(ui, ids, &state.panels, update);
+    console_panel_available(ui, ids, &state.panels, &state.console, net_cache, update);
+    tools_panel_available(
+        ui,
+        ids,
+        &state.panels,
+        state.shard.as_ref().map(|s| &**s),
+        &state.tools,
+        net_cache,
+        update,
+    );
+    watch_panel_available(ui, ids, &state.panels, &state.watch, net_cache, update);
 
     // scrolling
     let scroll_result = ScrollableRoomView::new()
@@ -60,7 +123,26 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
         .graphics_for(ids.room_view.scroll_widget)
         .set(ids.room_view.display, ui);
 
+    let mut export_clicked = false;
+    // bottom of the column of widgets in the left panel: the shard list, branches, legend and selected room are
+    // each placed below the last, so they can't overlap however many rows each has.
+    let mut column_bottom = None;
+
     if state.panels.left == ui_state::MenuState::Open {
+        export_clicked = Button::new()
+            // style
+            .color(color::DARK_CHARCOAL)
+            .border(0.0)
+            .w_h(120.0, 30.0)
+            // label
+            .label("Export PNG")
+            .small_font(&ui)
+            .label_color(color::WHITE)
+            // place
+            .mid_bottom_with_margin_on(ids.left_panel.open_panel_canvas, 10.0)
+            .set(ids.room_view.export_button, ui)
+            .was_clicked();
+
         let shard_list = net_cache.shard_list();
         match shard_list {
             Some(Some(shards)) => {
@@ -73,22 +155,93 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
                     .font_size(ui.theme.font_size_medium)
                     .right_justify()
                     .no_line_wrap()
-                    .top_left_of(ids.left_panel.open_panel_canvas)
+                    .top_left_with_margin_on(ids.left_panel.open_panel_canvas, PANEL_COLUMN_MARGIN)
                     .set(ids.room_view.shard_dropdown, ui);
+                column_bottom = Some(ids.room_view.shard_dropdown);
             }
             Some(None) => {
                 Text::new("<no shards>")
                     .font_size(ui.theme.font_size_medium)
                     .right_justify()
                     .no_line_wrap()
-                    .top_left_of(ids.left_panel.open_panel_canvas)
+                    .top_left_with_margin_on(ids.left_panel.open_panel_canvas, PANEL_COLUMN_MARGIN)
                     .set(ids.room_view.shard_dropdown, ui);
+                column_bottom = Some(ids.room_view.shard_dropdown);
             }
             None => {}
         }
+
+        // branch picker: clicking a branch runs it in the world, and "sim" runs it in the simulation room.
+        let branches = net_cache.branches().map(|branches| branches.to_vec());
+        if let Some(branches) = branches {
+            let label = Text::new("Branches")
+                .font_size(ui.theme.font_size_medium)
+                .no_line_wrap();
+            below_in_column(label, column_bottom, ids.left_panel.open_panel_canvas, 10.0)
+                .set(ids.room_view.branches_label, ui);
+
+            if ids.room_view.branch_world_buttons.len() < branches.len() {
+                let mut gen = ui.widget_id_generator();
+                ids.room_view
+                    .branch_world_buttons
+                    .resize(branches.len(), &mut gen);
+                ids.room_view
+                    .branch_sim_buttons
+                    .resize(branches.len(), &mut gen);
+            }
+
+            let branch_button = |label: &str, active: bool| {
+                Button::new()
+                    .color(if active {
+                        color::CHARCOAL
+                    } else {
+                        color::DARK_CHARCOAL
+                    })
+                    .border(0.0)
+                    .label(label)
+                    .label_font_size(12)
+                    .label_color(if active { color::LIGHT_GREEN } else { color::WHITE })
+            };
+
+            let mut last_row = ids.room_view.branches_label;
+            for (idx, branch) in branches.iter().enumerate() {
+                let world_id = ids.room_view.branch_world_buttons[idx];
+                let sim_id = ids.room_view.branch_sim_buttons[idx];
+
+                if branch_button(&branch.name, branch.active_world)
+                    .w_h(200.0, BRANCH_ROW_HEIGHT)
+                    .down_from(last_row, 5.0)
+                    .align_left_of(ids.room_view.branches_label)
+                    .set(world_id, ui)
+                    .was_clicked() && !branch.active_world
+                {
+                    info!("activating branch {} in the world", branch.name);
+                    net_cache.set_active_branch(&branch.name, ActiveBranch::World);
+                }
+
+                if branch_button("sim", branch.active_sim)
+                    .w_h(50.0, BRANCH_ROW_HEIGHT)
+                    .right_from(world_id, 5.0)
+                    .set(sim_id, ui)
+                    .was_clicked() && !branch.active_sim
+                {
+                    info!("activating branch {} in the simulation", branch.name);
+                    net_cache.set_active_branch(&branch.name, ActiveBranch::Sim);
+                }
+
+                last_row = world_id;
+            }
+            column_bottom = Some(last_row);
+        }
     }
 
-    if let Some(info) = net_cache.my_info() {
+    let own_id = net_cache.my_info().map(|info| info.user_id.clone());
+    let own_badge = match own_id {
+        Some(ref id) => user_badge(id, net_cache, image_cache, display),
+        None => None,
+    };
+
+    let has_user_header = if let Some(info) = net_cache.my_info() {
         Text::new(&format!("{} - GCL {}", info.username, screeps_api::gcl_calc(info.gcl_points)))
                 // style
                 .font_size(ui.theme.font_size_small)
@@ -97,6 +250,79 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
                 // position
                 .mid_right_with_margin_on(ids.root.header, 10.0)
                 .set(ids.room_view.username_gcl_header, ui);
+        true
+    } else {
+        false
+    };
+
+    let has_user_badge = match own_badge {
+        Some(badge) if has_user_header => {
+            Image::new(badge)
+                .w_h(HEADER_HEIGHT - 10.0, HEADER_HEIGHT - 10.0)
+                .left_from(ids.room_view.username_gcl_header, 5.0)
+                .set(ids.room_view.username_badge, ui);
+            true
+        }
+        _ => false,
+    };
+
+    if let Some((text, mismatch)) = server_description(net_cache.server(), net_cache.server_info()) {
+        let server_text = Text::new(&text)
+            // style
+            .font_size(ui.theme.font_size_small)
+            .color(if mismatch { color::LIGHT_RED } else { color::WHITE })
+            .right_justify()
+            .no_line_wrap();
+        let server_text = if has_user_badge {
+            server_text.left_from(ids.room_view.username_badge, 20.0)
+        } else if has_user_header {
+            server_text.left_from(ids.room_view.username_gcl_header, 20.0)
+        } else {
+            server_text.mid_right_with_margin_on(ids.root.header, 10.0)
+        };
+        server_text.set(ids.room_view.server_header, ui);
+    }
+
+    if let Some(last) = net_cache.usage().last().cloned() {
+        let cpu_values = net_cache
+            .usage()
+            .iter()
+            .map(|sample| sample.cpu as f64)
+            .collect::<Vec<_>>();
+
+        Sparkline::new(&cpu_values)
+            // style
+            .color(color::LIGHT_GREEN)
+            .w_h(200.0, HEADER_HEIGHT - 10.0)
+            // position
+            .middle_of(ids.root.header)
+            .set(ids.room_view.cpu_graph, ui);
+
+        Text::new(&format!("CPU {}ms - memory {}KB", last.cpu, last.memory / 1024))
+            // style
+            .font_size(ui.theme.font_size_small)
+            .no_line_wrap()
+            // position
+            .left_from(ids.room_view.cpu_graph, 10.0)
+            .set(ids.room_view.cpu_label, ui);
+    }
+
+    if let Some(clock) = net_cache.tick_clock().shard(state.shard.as_ref().map(|s| &**s)) {
+        let text = match clock.tick_seconds {
+            Some(seconds) => format!("tick {} ({:.1}s/tick)", clock.game_time, seconds),
+            None => format!("tick {}", clock.game_time),
+        };
+
+        let tick_text = Text::new(&text)
+            // style
+            .font_size(ui.theme.font_size_small)
+            .no_line_wrap();
+        let tick_text = if net_cache.usage().is_empty() {
+            tick_text.middle_of(ids.root.header)
+        } else {
+            tick_text.right_from(ids.room_view.cpu_graph, 10.0)
+        };
+        tick_text.set(ids.room_view.tick_label, ui);
     }
 
     let view_rect = ui.rect_of(ids.room_view.display)
@@ -108,7 +334,6 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
         scroll_x: saved_room_scroll_x,
         scroll_y: saved_room_scroll_y,
         zoom_factor,
-        selected_room,
         ..
     } = state.map_scroll;
 
@@ -142,13 +367,121 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
     );
 
     // fetch rooms just outside the boundary as well so we can have smoother scrolling
-    let rooms_to_fetch = SelectedRooms::new((initial_room - (1, 1))..(initial_room + (count_x + 1, count_y + 1)));
+    let rooms_to_fetch = RoomRect::new((initial_room - (1, 1))..(initial_room + (count_x + 1, count_y + 1)));
 
-    let room_data = net_cache.view_rooms(rooms_to_fetch, selected_room).clone();
-
-    let rooms_to_view = SelectedRooms::new(initial_room..(initial_room + (count_x, count_y)));
     let offset = MapViewOffset::new(extra_scroll_x, extra_scroll_y, room_size);
 
+    let room_data = net_cache
+        .view_rooms(rooms_to_fetch, &state.map_scroll.detail_rooms(), offset.is_overview())
+        .clone();
+
+    let rooms_to_view = RoomRect::new(initial_room..(initial_room + (count_x, count_y)));
+
+    let players = visible_players(rooms_to_view, &room_data.borrow());
+    let mut player_relations = HashMap::new();
+    for user_id in &players {
+        let username = net_cache.user(user_id).map(|profile| profile.username.clone());
+        let relation = state.relations.relation(
+            user_id,
+            username.as_ref().map(|s| &**s),
+            own_id.as_ref().map(|s| &**s),
+        );
+        if let Some(relation) = relation {
+            player_relations.insert(user_id.clone(), relation);
+        }
+    }
+    image_cache.player_colors.set_relations(player_relations);
+
+    // generate owners' badges for the map view to draw on their controllers.
+    for user_id in room_owners(rooms_to_view, &room_data.borrow()) {
+        user_badge(&user_id, net_cache, image_cache, display);
+    }
+
+    if state.panels.left == ui_state::MenuState::Open {
+        // legend, listing each relation and then other players in view.
+        let mut legend = Relation::ALL
+            .iter()
+            .map(|&relation| {
+                let badge = if relation == Relation::Own { own_badge } else { None };
+                (relation_color(relation), relation.description().to_owned(), badge)
+            })
+            .collect::<Vec<_>>();
+        let unrelated = players
+            .iter()
+            .filter(|user_id| image_cache.player_colors.relation(user_id).is_none())
+            .take(LEGEND_MAX_PLAYERS)
+            .cloned()
+            .collect::<Vec<_>>();
+        for user_id in unrelated {
+            let name = net_cache
+                .user(&user_id)
+                .map(|profile| profile.username.clone())
+                .unwrap_or_else(|| user_id.clone());
+            let badge = user_badge(&user_id, net_cache, image_cache, display);
+            legend.push((user_color(&user_id), name, badge));
+        }
+
+        if ids.room_view.legend_swatches.len() < legend.len() {
+            let mut gen = ui.widget_id_generator();
+            ids.room_view
+                .legend_swatches
+                .resize(legend.len(), &mut gen);
+            ids.room_view.legend_labels.resize(legend.len(), &mut gen);
+            ids.room_view.legend_badges.resize(legend.len(), &mut gen);
+        }
+
+        for (idx, &(color, ref label, badge)) in legend.iter().enumerate() {
+            let swatch_id = ids.room_view.legend_swatches[idx];
+
+            let swatch = Rectangle::fill_with([LEGEND_SWATCH_SIZE, LEGEND_SWATCH_SIZE], color);
+            below_in_column(
+                swatch,
+                column_bottom,
+                ids.left_panel.open_panel_canvas,
+                if idx == 0 { 10.0 } else { 6.0 },
+            ).set(swatch_id, ui);
+
+            Text::new(label)
+                .font_size(ui.theme.font_size_small)
+                .no_line_wrap()
+                .right_from(swatch_id, 5.0)
+                .set(ids.room_view.legend_labels[idx], ui);
+
+            if let Some(badge) = badge {
+                Image::new(badge)
+                    .w_h(PANEL_BADGE_SIZE, PANEL_BADGE_SIZE)
+                    .right_from(ids.room_view.legend_labels[idx], 5.0)
+                    .set(ids.room_view.legend_badges[idx], ui);
+            }
+
+            column_bottom = Some(swatch_id);
+        }
+
+        if let Some(room_name) = state.map_scroll.selected_room {
+            let owner_badge = room_owner(room_name, &room_data.borrow())
+                .and_then(|user_id| user_badge(&user_id, net_cache, image_cache, display));
+            if let Some(badge) = owner_badge {
+                let image = Image::new(badge).w_h(PANEL_BADGE_SIZE * 2.0, PANEL_BADGE_SIZE * 2.0);
+                below_in_column(image, column_bottom, ids.left_panel.open_panel_canvas, 10.0)
+                    .set(ids.room_view.selected_room_badge, ui);
+                column_bottom = Some(ids.room_view.selected_room_badge);
+            }
+
+            let text = describe_room(room_name, &room_data.borrow(), net_cache);
+            let info = Text::new(&text)
+                .font_size(ui.theme.font_size_small)
+                .w(200.0)
+                .wrap_by_word();
+            below_in_column(info, column_bottom, ids.left_panel.open_panel_canvas, 5.0)
+                .set(ids.room_view.selected_room_info, ui);
+        }
+    }
+
+    if export_clicked {
+        let data = export::copy_rooms(rooms_to_view, &room_data.borrow());
+        export_in_background(rooms_to_view, data, image_cache.player_colors.clone());
+    }
+
     *app.additional_rendering = Some(AdditionalRender::map_view(
         ids.root.body,
         rooms_to_view,
@@ -157,6 +490,128 @@ pub fn create_ui(app: &mut AppCell, state: &MapScreenState, mut update: &mut Vec
     ));
 }
 
+/// Places a widget in the left panel's column, below `above` or at the top of the panel if nothing is there yet.
+fn below_in_column<W: Positionable>(widget: W, above: Option<Id>, panel: Id, gap: f64) -> W {
+    match above {
+        Some(above) => widget.down_from(above, gap).align_left_of(above),
+        None => widget.top_left_with_margin_on(panel, PANEL_COLUMN_MARGIN),
+    }
+}
+
+/// Writes an image of the given rooms to the exports directory, without blocking the UI.
+fn export_in_background(rooms: RoomRect, data: MapCacheData, colors: PlayerColors) {
+    thread::spawn(move || {
+        let dir = export::exports_dir();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            warn!("failed to create export directory ({}): {}", dir.display(), e);
+            return;
+        }
+        let path = dir.join(format!("screeps-map-{}-{}.png", rooms.start, rooms.end));
+
+        match export::rasterize(rooms, &data, &colors, EXPORT_PIXELS_PER_TILE) {
+            Ok(image) => match image.write_png(&path) {
+                Ok(()) => info!("exported map view to {}", path.display()),
+                Err(e) => warn!("failed to export map view to {}: {}", path.display(), e),
+            },
+            Err(e) => warn!("failed to export map view: {}", e),
+        }
+    });
+}
+
+/// Finds every player with objects in the given rooms' map views, sorted by user id.
+fn visible_players(rooms: RoomRect, data: &MapCacheData) -> Vec<String> {
+    let mut players = Vec::new();
+    for room_name in rooms {
+        if let Some(&(_, ref map_view)) = data.map_views.get(&room_name) {
+            players.extend(map_view.users_objects.iter().map(|&(ref id, _)| id.clone()));
+        }
+    }
+    players.sort();
+    players.dedup();
+    players
+}
+
+/// Finds the owner of a room's controller, if it's owned.
+fn room_owner(room_name: RoomName, data: &MapCacheData) -> Option<String> {
+    match data.room_stats.get(&room_name) {
+        Some(&(_, ref stats)) => match stats.control {
+            Some(RoomControl::Owned { ref user, .. }) => Some(user.id.clone()),
+            _ => None,
+        },
+        None => None,
+    }
+}
+
+/// Finds the owners of every owned room among the given rooms, without duplicates.
+fn room_owners(rooms: RoomRect, data: &MapCacheData) -> Vec<String> {
+    let mut owners = rooms
+        .into_iter()
+        .filter_map(|room_name| room_owner(room_name, data))
+        .collect::<Vec<_>>();
+    owners.sort();
+    owners.dedup();
+    owners
+}
+
+/// Finds the image of a user's badge, looking up their profile and generating the image as needed.
+fn user_badge(
+    user_id: &str,
+    net_cache: &mut NetworkCache,
+    image_cache: &mut RenderCache,
+    display: &glium::Display,
+) -> Option<ImageId> {
+    let badge = net_cache.user(user_id).and_then(|profile| profile.badge.clone())?;
+    let image_id = image_cache.get_or_generate_badge(display, &badge)?;
+    image_cache.set_user_badge(user_id, image_id);
+    Some(image_id)
+}
+
+/// Describes who owns a room and which players have anything in it, with usernames looked up as needed.
+fn describe_room(room_name: RoomName, data: &MapCacheData, net_cache: &mut NetworkCache) -> String {
+    use std::fmt::Write;
+
+    let mut text = room_name.to_string();
+    {
+        let mut name_of = |user: &MapUser| match user.username {
+            Some(ref username) => username.clone(),
+            None => net_cache
+                .user(&user.id)
+                .map(|profile| profile.username.clone())
+                .unwrap_or_else(|| user.id.clone()),
+        };
+
+        if let Some(&(_, ref stats)) = data.room_stats.get(&room_name) {
+            match stats.control {
+                Some(RoomControl::Owned { ref user, level }) => {
+                    write!(text, "\nowned by {} (RCL {})", name_of(user), level)
+                }
+                Some(RoomControl::Reserved { ref user }) => write!(text, "\nreserved by {}", name_of(user)),
+                None => write!(text, "\nunowned"),
+            }.expect("writing to a String can't fail");
+            if let Some(ref sign) = stats.sign {
+                write!(text, "\nsigned by {}: {}", name_of(&sign.user), sign.text)
+                    .expect("writing to a String can't fail");
+            }
+        }
+    }
+
+    if let Some(&(_, ref map_view)) = data.map_views.get(&room_name) {
+        let names = map_view
+            .users_objects
+            .iter()
+            .map(|&(ref id, _)| match net_cache.user(id) {
+                Some(profile) => format!("{} (GCL {})", profile.username, profile.gcl()),
+                None => id.clone(),
+            })
+            .collect::<Vec<_>>();
+        if !names.is_empty() {
+            write!(text, "\nplayers: {}", names.join(", ")).expect("writing to a String can't fail");
+        }
+    }
+
+    text
+}
+
 #[derive(Copy, Clone, Debug, Default)]
 struct ScrollUpdate {
     /// The number of pixels scrolled horizontally.
@@ -171,6 +626,8 @@ struct ScrollUpdate {
     zoom_mouse_rel_y: f64,
     /// If the screen was clicked, the relative (x, y) that it was clicked at.
     clicked: Option<(f64, f64)>,
+    /// Whether shift was held during the click.
+    clicked_with_shift: bool,
 }
 
 impl ScrollUpdate {
@@ -178,7 +635,10 @@ impl ScrollUpdate {
         if let Some(pos_tuple) = self.clicked {
             update.push_front(UiEvent::MapClick {
                 view_rect: view_rect,
-                event: MapClickEvent { clicked: pos_tuple },
+                event: MapClickEvent {
+                    clicked: pos_tuple,
+                    pin: self.clicked_with_shift,
+                },
             });
         }
         if self.zoom_change != 0.0 {
@@ -244,6 +704,7 @@ mod room_view_widget {
         fn update(self, args: widget::UpdateArgs<Self>) -> Option<ScrollUpdate> {
             use conrod::event::Widget as Event;
             use conrod::input::MouseButton;
+            use conrod::input::keyboard::ModifierKey;
 
             let widget::UpdateArgs { id, ui, state, .. } = args;
 
@@ -268,6 +729,8 @@ mod room_view_widget {
                     Event::Click(click) => if click.button == MouseButton::Left {
                         debug!("click update");
                         update.clicked = Some((click.xy[0], click.xy[1]));
+                        update.clicked_with_shift = click.modifiers.contains(ModifierKey::SHIFT);
+                        changed = true;
                     },
                     _ => {}
                 }
@@ -0,0 +1,404 @@
+use std::collections::VecDeque;
+
+use conrod::{self, color, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget};
+use conrod::widget::*;
+
+use serde_json::{self, Value};
+
+use widgets::json_tree::{self, JsonTree};
+use widgets::text_box::{self, TextBox};
+use widgets::text_edit::TextEdit;
+use ui_state::{Event as UiEvent, MenuState, PanelStates, ToolsState, ToolsTab};
+use network_integration::NetworkCache;
+
+use super::{Ids, HEADER_HEIGHT};
+
+const TOOLS_WIDTH: conrod::Scalar = 350.0;
+const TOOLS_PADDING: conrod::Scalar = 5.0;
+const TAB_BUTTON_WIDTH: conrod::Scalar = 80.0;
+const ROW_HEIGHT: conrod::Scalar = 24.0;
+const REFRESH_BUTTON_WIDTH: conrod::Scalar = 70.0;
+const SEGMENT_LIST_WIDTH: conrod::Scalar = 90.0;
+/// Number of raw memory segments available to each player.
+const SEGMENT_COUNT: u8 = 100;
+
+pub struct ToolsIds {
+    pub panel_toggle: Id,
+    pub panel_canvas: Id,
+    memory_tab: Id,
+    memory_path: Id,
+    memory_refresh: Id,
+    memory_status: Id,
+    memory_tree: Id,
+    segments_tab: Id,
+    segments_load_all: Id,
+    segments_format: Id,
+    segments_save: Id,
+    segments_list: Id,
+    segments_background: Id,
+    segments_text: Id,
+}
+
+impl ToolsIds {
+    pub fn new(gen: &mut id::Generator) -> Self {
+        ToolsIds {
+            panel_toggle: gen.next(),
+            panel_canvas: gen.next(),
+            memory_tab: gen.next(),
+            memory_path: gen.next(),
+            memory_refresh: gen.next(),
+            memory_status: gen.next(),
+            memory_tree: gen.next(),
+            segments_tab: gen.next(),
+            segments_load_all: gen.next(),
+            segments_format: gen.next(),
+            segments_save: gen.next(),
+            segments_list: gen.next(),
+            segments_background: gen.next(),
+            segments_text: gen.next(),
+        }
+    }
+}
+
+pub fn tools_panel_available(
+    ui: &mut conrod::UiCell,
+    ids: &Ids,
+    panels: &PanelStates,
+    shard: Option<&str>,
+    state: &ToolsState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let toggle_clicks = Button::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .w_h(100.0, HEADER_HEIGHT)
+        // label
+        .label("Tools")
+        .small_font(&ui)
+        .left_justify_label()
+        .label_color(color::WHITE)
+        // place
+        .parent(ids.root.header)
+        .right_from(ids.console.panel_toggle, 0.0)
+        .set(ids.tools.panel_toggle, ui)
+        // now TimesClicked(u16)
+        .0;
+
+    match panels.tools {
+        MenuState::Open => {
+            tools_panel_open(ui, ids, shard, state, net_cache, update);
+
+            if toggle_clicks % 2 == 1 {
+                update.push_back(UiEvent::ToolsClosed);
+            }
+        }
+        MenuState::Closed => if toggle_clicks % 2 == 1 {
+            update.push_back(UiEvent::ToolsOpened);
+        },
+    }
+}
+
+fn tools_panel_open(
+    ui: &mut conrod::UiCell,
+    ids: &Ids,
+    shard: Option<&str>,
+    state: &ToolsState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    Canvas::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .w_h(TOOLS_WIDTH, ui.window_dim()[1] - HEADER_HEIGHT)
+        // place
+        .floating(true)
+        .top_right_with_margins_on(ids.root.root, HEADER_HEIGHT, 0.0)
+        .set(ids.tools.panel_canvas, ui);
+
+    let tab_button = |label: &str, selected: bool| {
+        Button::new()
+            .color(if selected {
+                color::CHARCOAL
+            } else {
+                color::DARK_CHARCOAL
+            })
+            .border(0.0)
+            .w_h(TAB_BUTTON_WIDTH, ROW_HEIGHT)
+            .label(label)
+            .label_font_size(12)
+            .label_color(color::WHITE)
+    };
+
+    if tab_button("Memory", state.tab == ToolsTab::Memory)
+        .top_left_with_margin_on(ids.tools.panel_canvas, TOOLS_PADDING)
+        .set(ids.tools.memory_tab, ui)
+        .was_clicked()
+    {
+        update.push_back(UiEvent::ToolsTab(ToolsTab::Memory));
+    }
+
+    if tab_button("Segments", state.tab == ToolsTab::Segments)
+        .right_from(ids.tools.memory_tab, TOOLS_PADDING)
+        .set(ids.tools.segments_tab, ui)
+        .was_clicked()
+    {
+        update.push_back(UiEvent::ToolsTab(ToolsTab::Segments));
+    }
+
+    match state.tab {
+        ToolsTab::Memory => memory_tab(ui, ids, shard, state, net_cache, update),
+        ToolsTab::Segments => segments_tab(ui, ids, shard, state, net_cache, update),
+    }
+}
+
+fn memory_tab(
+    ui: &mut conrod::UiCell,
+    ids: &Ids,
+    shard: Option<&str>,
+    state: &ToolsState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let memory = &state.memory;
+
+    let path_events = TextBox::new(&memory.path_input)
+        // style
+        .w_h(
+            TOOLS_WIDTH - REFRESH_BUTTON_WIDTH - TOOLS_PADDING * 3.0,
+            ROW_HEIGHT,
+        )
+        .font_size(ui.theme.font_size_small)
+        .left_justify()
+        .pad_text(5.0)
+        // position
+        .down_from(ids.tools.memory_tab, TOOLS_PADDING)
+        .align_left_of(ids.tools.memory_tab)
+        .set(ids.tools.memory_path, ui);
+
+    for event in path_events {
+        match event {
+            text_box::Event::Update(text) => update.push_back(UiEvent::MemoryPathInput(text)),
+            text_box::Event::Enter => update.push_back(UiEvent::MemoryPathLoaded),
+            text_box::Event::Up | text_box::Event::Down => {}
+        }
+    }
+
+    if Button::new()
+        // style
+        .color(color::CHARCOAL)
+        .border(0.0)
+        .w_h(REFRESH_BUTTON_WIDTH, ROW_HEIGHT)
+        // label
+        .label("Refresh")
+        .label_font_size(12)
+        .label_color(color::WHITE)
+        // position
+        .right_from(ids.tools.memory_path, TOOLS_PADDING)
+        .set(ids.tools.memory_refresh, ui)
+        .was_clicked()
+    {
+        net_cache.refresh_memory(shard, &memory.loaded_path);
+    }
+
+    let status = if memory.loaded_path.is_empty() {
+        "Memory".to_owned()
+    } else {
+        format!("Memory.{}", memory.loaded_path)
+    };
+    Text::new(&status)
+        .font_size(ui.theme.font_size_small)
+        .color(color::WHITE)
+        .no_line_wrap()
+        .down_from(ids.tools.memory_path, TOOLS_PADDING)
+        .align_left_of(ids.tools.memory_path)
+        .set(ids.tools.memory_status, ui);
+
+    let tree_top = ui.rect_of(ids.tools.memory_status)
+        .map(|rect| rect.bottom())
+        .unwrap_or(0.0);
+    let canvas_bottom = ui.rect_of(ids.tools.panel_canvas)
+        .map(|rect| rect.bottom())
+        .unwrap_or(0.0);
+    let tree_height = (tree_top - canvas_bottom - TOOLS_PADDING * 2.0).max(ROW_HEIGHT);
+
+    let tree_events = match net_cache.memory(shard, &memory.loaded_path) {
+        Some(value) => JsonTree::new(value, &memory.expanded)
+            // style
+            .label_color(color::WHITE)
+            .w_h(TOOLS_WIDTH - TOOLS_PADDING * 2.0, tree_height)
+            // position
+            .down_from(ids.tools.memory_status, TOOLS_PADDING)
+            .align_left_of(ids.tools.memory_status)
+            .set(ids.tools.memory_tree, ui),
+        None => Vec::new(),
+    };
+
+    for event in tree_events {
+        match event {
+            json_tree::Event::Toggle(pointer) => update.push_back(UiEvent::MemoryTreeToggle(pointer)),
+            json_tree::Event::Edit { pointer, value } => {
+                let path = memory_path(&memory.loaded_path, &pointer);
+                info!("setting Memory.{} to {}", path, value);
+                net_cache.set_memory(shard, &path, &value);
+            }
+        }
+    }
+}
+
+fn segments_tab(
+    ui: &mut conrod::UiCell,
+    ids: &Ids,
+    shard: Option<&str>,
+    state: &ToolsState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let segments = &state.segments;
+
+    let small_button = |label: &str| {
+        Button::new()
+            .color(color::CHARCOAL)
+            .border(0.0)
+            .w_h(REFRESH_BUTTON_WIDTH, ROW_HEIGHT)
+            .label(label)
+            .label_font_size(12)
+            .label_color(color::WHITE)
+    };
+
+    if small_button("Load all")
+        .down_from(ids.tools.memory_tab, TOOLS_PADDING)
+        .align_left_of(ids.tools.memory_tab)
+        .set(ids.tools.segments_load_all, ui)
+        .was_clicked()
+    {
+        net_cache.request_memory_segments(shard, &(0..SEGMENT_COUNT).collect::<Vec<_>>());
+    }
+
+    if small_button(if segments.show_json { "JSON" } else { "Text" })
+        .right_from(ids.tools.segments_load_all, TOOLS_PADDING)
+        .set(ids.tools.segments_format, ui)
+        .was_clicked()
+    {
+        update.push_back(UiEvent::SegmentShowJson(!segments.show_json));
+    }
+
+    let list_top = ui.rect_of(ids.tools.segments_load_all)
+        .map(|rect| rect.bottom())
+        .unwrap_or(0.0);
+    let canvas_bottom = ui.rect_of(ids.tools.panel_canvas)
+        .map(|rect| rect.bottom())
+        .unwrap_or(0.0);
+    let body_height = (list_top - canvas_bottom - TOOLS_PADDING * 2.0).max(ROW_HEIGHT);
+
+    // segment list
+    let (mut items, scrollbar) = List::flow_down(SEGMENT_COUNT as usize)
+        .item_size(ROW_HEIGHT)
+        .scrollbar_on_top()
+        .w_h(SEGMENT_LIST_WIDTH, body_height)
+        .down_from(ids.tools.segments_load_all, TOOLS_PADDING)
+        .align_left_of(ids.tools.segments_load_all)
+        .set(ids.tools.segments_list, ui);
+
+    while let Some(item) = items.next(ui) {
+        let segment = item.i as u8;
+        let label = match net_cache.cached_memory_segment(shard, segment) {
+            Some(data) if !data.is_empty() => format!("{} ({}B)", segment, data.len()),
+            _ => segment.to_string(),
+        };
+        let button = Button::new()
+            .color(if segment == segments.selected {
+                color::CHARCOAL
+            } else {
+                color::DARK_CHARCOAL
+            })
+            .border(0.0)
+            .label(&label)
+            .label_font_size(12)
+            .label_color(color::WHITE)
+            .left_justify_label();
+        if item.set(button, ui).was_clicked() {
+            update.push_back(UiEvent::SegmentSelected(segment));
+        }
+    }
+
+    if let Some(scrollbar) = scrollbar {
+        scrollbar.set(ui);
+    }
+
+    // segment contents
+    let loaded = net_cache.memory_segment(shard, segments.selected).map(|data| {
+        if segments.show_json {
+            if let Ok(value) = serde_json::from_str::<Value>(data) {
+                return serde_json::to_string_pretty(&value).expect("expected serializing a JSON value to succeed");
+            }
+        }
+        data.to_owned()
+    });
+
+    let text_width = TOOLS_WIDTH - SEGMENT_LIST_WIDTH - TOOLS_PADDING * 3.0;
+
+    if small_button("Save")
+        .right_from(ids.tools.segments_format, TOOLS_PADDING)
+        .set(ids.tools.segments_save, ui)
+        .was_clicked()
+    {
+        if let Some(ref edited) = segments.edited {
+            // pretty-printed JSON is stored compactly, to save space in the segment.
+            let data = match serde_json::from_str::<Value>(edited) {
+                Ok(ref value) if segments.show_json => value.to_string(),
+                _ => edited.clone(),
+            };
+            info!("saving memory segment {}", segments.selected);
+            net_cache.set_memory_segment(shard, segments.selected, data);
+            update.push_back(UiEvent::SegmentSaved);
+        }
+    }
+
+    Rectangle::fill([text_width, body_height])
+        .color(color::CHARCOAL)
+        .right_from(ids.tools.segments_list, TOOLS_PADDING)
+        .set(ids.tools.segments_background, ui);
+
+    let text = match (segments.edited.as_ref(), loaded.as_ref()) {
+        (Some(edited), _) => &**edited,
+        (None, Some(loaded)) => &**loaded,
+        (None, None) => "",
+    };
+
+    if let Some(new_text) = TextEdit::new(text)
+        .font_size(ui.theme.font_size_small)
+        .color(color::WHITE)
+        .restrict_to_height(false)
+        .wrap_by_character()
+        .w_h(text_width - TOOLS_PADDING * 2.0, body_height - TOOLS_PADDING * 2.0)
+        .middle_of(ids.tools.segments_background)
+        .set(ids.tools.segments_text, ui)
+    {
+        update.push_back(UiEvent::SegmentEdited(new_text));
+    }
+}
+
+/// Finds the dot-separated memory path of a value shown in a tree of the memory at `base`.
+///
+/// Keys which would be misread as part of a path, such as ones containing `.`, are written in bracket notation,
+/// as in `creeps["a.b"].role`.
+fn memory_path(base: &str, pointer: &str) -> String {
+    let mut path = base.to_owned();
+    for segment in json_tree::pointer_segments(pointer) {
+        let plain = !segment.is_empty() && !segment.contains(|c: char| c == '.' || c == '[' || c == ']');
+        if plain {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&segment);
+        } else {
+            path.push_str("[\"");
+            path.push_str(&segment.replace('\\', "\\\\").replace('"', "\\\""));
+            path.push_str("\"]");
+        }
+    }
+    path
+}
@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use conrod::{self, color, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget};
+use conrod::widget::*;
+
+use widgets::text_box::{self, TextBox};
+use ui_state::{Event as UiEvent, MenuState, PanelStates, WatchDock, WatchState};
+use network_integration::NetworkCache;
+
+use super::{Ids, HEADER_HEIGHT};
+
+const WATCH_WIDTH: conrod::Scalar = 320.0;
+const WATCH_HEIGHT: conrod::Scalar = 260.0;
+const WATCH_PADDING: conrod::Scalar = 5.0;
+const ROW_HEIGHT: conrod::Scalar = 22.0;
+const BUTTON_WIDTH: conrod::Scalar = 60.0;
+/// Number of characters of a watched value shown before it's cut off.
+const MAX_VALUE_LENGTH: usize = 40;
+
+pub struct WatchIds {
+    pub panel_toggle: Id,
+    pub panel_canvas: Id,
+    input: Id,
+    add: Id,
+    dock: Id,
+    values: id::List,
+    removes: id::List,
+}
+
+impl WatchIds {
+    pub fn new(gen: &mut id::Generator) -> Self {
+        WatchIds {
+            panel_toggle: gen.next(),
+            panel_canvas: gen.next(),
+            input: gen.next(),
+            add: gen.next(),
+            dock: gen.next(),
+            values: id::List::new(),
+            removes: id::List::new(),
+        }
+    }
+}
+
+pub fn watch_panel_available(
+    ui: &mut conrod::UiCell,
+    ids: &mut Ids,
+    panels: &PanelStates,
+    state: &WatchState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let toggle_clicks = Button::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .w_h(100.0, HEADER_HEIGHT)
+        // label
+        .label("Watch")
+        .small_font(&ui)
+        .left_justify_label()
+        .label_color(color::WHITE)
+        // place
+        .parent(ids.root.header)
+        .right_from(ids.tools.panel_toggle, 0.0)
+        .set(ids.watch.panel_toggle, ui)
+        // now TimesClicked(u16)
+        .0;
+
+    match panels.watch {
+        MenuState::Open => {
+            watch_panel_open(ui, ids, state, net_cache, update);
+
+            if toggle_clicks % 2 == 1 {
+                update.push_back(UiEvent::WatchClosed);
+            }
+        }
+        MenuState::Closed => if toggle_clicks % 2 == 1 {
+            update.push_back(UiEvent::WatchOpened);
+        },
+    }
+}
+
+fn watch_panel_open(
+    ui: &mut conrod::UiCell,
+    ids: &mut Ids,
+    state: &WatchState,
+    net_cache: &mut NetworkCache,
+    update: &mut VecDeque<UiEvent>,
+) {
+    let canvas = Canvas::new()
+        // style
+        .color(color::DARK_CHARCOAL)
+        .border(1.0)
+        .border_color(color::CHARCOAL)
+        .w_h(WATCH_WIDTH, WATCH_HEIGHT)
+        // place
+        .floating(true);
+    let canvas = match state.dock {
+        WatchDock::Left => canvas.top_left_with_margins_on(ids.root.root, HEADER_HEIGHT, 0.0),
+        WatchDock::Right => canvas.top_right_with_margins_on(ids.root.root, HEADER_HEIGHT, 0.0),
+    };
+    canvas.set(ids.watch.panel_canvas, ui);
+
+    let small_button = |label: &str| {
+        Button::new()
+            .color(color::CHARCOAL)
+            .border(0.0)
+            .w_h(BUTTON_WIDTH, ROW_HEIGHT)
+            .label(label)
+            .label_font_size(12)
+            .label_color(color::WHITE)
+    };
+
+    let (dock_label, other_dock) = match state.dock {
+        WatchDock::Left => ("Right", WatchDock::Right),
+        WatchDock::Right => ("Left", WatchDock::Left),
+    };
+    if small_button(dock_label)
+        .top_right_with_margin_on(ids.watch.panel_canvas, WATCH_PADDING)
+        .set(ids.watch.dock, ui)
+        .was_clicked()
+    {
+        update.push_back(UiEvent::WatchDocked(other_dock));
+    }
+
+    let add_clicked = small_button("Watch")
+        .left_from(ids.watch.dock, WATCH_PADDING)
+        .set(ids.watch.add, ui)
+        .was_clicked();
+
+    let input_events = TextBox::new(&state.input)
+        // style
+        .w_h(WATCH_WIDTH - BUTTON_WIDTH * 2.0 - WATCH_PADDING * 4.0, ROW_HEIGHT)
+        .font_size(ui.theme.font_size_small)
+        .left_justify()
+        .pad_text(5.0)
+        // position
+        .top_left_with_margin_on(ids.watch.panel_canvas, WATCH_PADDING)
+        .set(ids.watch.input, ui);
+
+    let mut submitted = add_clicked;
+    for event in input_events {
+        match event {
+            text_box::Event::Update(text) => update.push_back(UiEvent::WatchInput(text)),
+            text_box::Event::Enter => submitted = true,
+            text_box::Event::Up | text_box::Event::Down => {}
+        }
+    }
+
+    let new_path = state.input.trim().trim_matches('.');
+    if submitted && !new_path.is_empty() && !state.paths.iter().any(|path| path == new_path) {
+        net_cache.watch_memory(new_path);
+        update.push_back(UiEvent::WatchAdded(new_path.to_owned()));
+    }
+
+    // watched values
+    if ids.watch.values.len() < state.paths.len() {
+        let mut gen = ui.widget_id_generator();
+        ids.watch.values.resize(state.paths.len(), &mut gen);
+        ids.watch.removes.resize(state.paths.len(), &mut gen);
+    }
+
+    let mut last_row = ids.watch.input;
+    for (idx, path) in state.paths.iter().enumerate() {
+        let remove_id = ids.watch.removes[idx];
+        let value_id = ids.watch.values[idx];
+
+        if Button::new()
+            .color(color::DARK_CHARCOAL)
+            .border(0.0)
+            .w_h(ROW_HEIGHT, ROW_HEIGHT)
+            .label("x")
+            .label_font_size(12)
+            .label_color(color::LIGHT_RED)
+            .down_from(last_row, WATCH_PADDING)
+            .align_left_of(ids.watch.input)
+            .set(remove_id, ui)
+            .was_clicked()
+        {
+            net_cache.unwatch_memory(path);
+            update.push_back(UiEvent::WatchRemoved(path.clone()));
+        }
+
+        let value = match net_cache.watched_memory(path) {
+            Some(&(ref value, _)) => {
+                let mut text = value.to_string();
+                if text.chars().count() > MAX_VALUE_LENGTH {
+                    text = text.chars().take(MAX_VALUE_LENGTH).collect();
+                    text.push_str("...");
+                }
+                text
+            }
+            None => "...".to_owned(),
+        };
+
+        Text::new(&format!("{}: {}", path, value))
+            .font_size(ui.theme.font_size_small)
+            .color(color::WHITE)
+            .no_line_wrap()
+            .right_from(remove_id, WATCH_PADDING)
+            .set(value_id, ui);
+
+        last_row = remove_id;
+    }
+}
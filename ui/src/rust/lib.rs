@@ -11,13 +11,16 @@ extern crate conrod_derive;
 #[macro_use]
 extern crate glium;
 extern crate glutin;
+extern crate png;
 extern crate rusttype;
 
 // Network
 extern crate screeps_api;
 extern crate screeps_rs_network;
+extern crate serde_json;
 
 // Caching
+extern crate directories;
 extern crate time;
 
 // Logging
@@ -31,6 +34,7 @@ pub mod layout;
 pub mod ui_state;
 pub mod rendering;
 pub mod network_integration;
+pub mod relations;
 pub mod window_management;
 pub mod widgets;
 mod map_view_utils;
@@ -39,14 +43,21 @@ mod glium_backend;
 pub use app::App;
 pub use network_integration::NetworkHandler;
 
-pub fn main<T, I>(verbose_logging: bool, debug_modules: I)
+/// Runs the client.
+///
+/// `max_map_rooms` limits how many rooms are subscribed to map views of at once, or is `None` for the default.
+pub fn main<T, I>(verbose_logging: bool, debug_modules: I, max_map_rooms: Option<usize>)
 where
     T: AsRef<str>,
     I: IntoIterator<Item = T>,
 {
     window_management::setup::init_logger(verbose_logging, debug_modules);
 
-    let (events_loop, app) = window_management::setup::init_window();
+    let (events_loop, mut app) = window_management::setup::init_window();
+
+    if let Some(max) = max_map_rooms {
+        app.net_cache.set_max_map_rooms(max);
+    }
 
     window_management::window_loop::main_window_loop(events_loop, app);
 }
@@ -0,0 +1,123 @@
+//! Configured relations with other players, which decide the colors their objects are drawn in.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+
+use {directories, serde_json};
+
+/// Name of the relations file in the configuration directory.
+const RELATIONS_FILE_NAME: &str = "relations.json";
+
+/// How a player is related to the logged in user.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Relation {
+    /// The logged in user, or one of their other accounts.
+    Own,
+    Ally,
+    Enemy,
+    Neutral,
+}
+
+impl Relation {
+    /// Every relation, in the order shown in the legend.
+    pub const ALL: [Relation; 4] = [Relation::Own, Relation::Ally, Relation::Enemy, Relation::Neutral];
+
+    /// Key listing players with this relation in the relations file.
+    fn key(&self) -> &'static str {
+        match *self {
+            Relation::Own => "self",
+            Relation::Ally => "allies",
+            Relation::Enemy => "enemies",
+            Relation::Neutral => "neutral",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            Relation::Own => "you",
+            Relation::Ally => "allies",
+            Relation::Enemy => "enemies",
+            Relation::Neutral => "neutral",
+        }
+    }
+}
+
+/// Players listed in the relations file, by username or user id.
+///
+/// The file is a JSON object with lists under `self`, `allies`, `enemies` and `neutral`, each of which is optional:
+///
+/// ```json
+/// { "allies": ["friend"], "enemies": ["5a0b6a6fc1e2f35ec2b3e5f9"] }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Relations {
+    /// Relations by user id or lowercase username.
+    players: HashMap<String, Relation>,
+}
+
+impl Relations {
+    /// Loads relations from the configuration directory, or returns no relations if there's no relations file or
+    /// it can't be read.
+    pub fn load() -> Self {
+        let path = Relations::path();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Relations::default(),
+            Err(e) => {
+                warn!("failed to open relations file ({}): {}", path.display(), e);
+                return Relations::default();
+            }
+        };
+
+        match serde_json::from_reader(file) {
+            Ok(json) => {
+                let relations = Relations::from_json(&json);
+                info!("loaded {} relations from {}", relations.players.len(), path.display());
+                relations
+            }
+            Err(e) => {
+                warn!("failed to parse relations file ({}): {}", path.display(), e);
+                Relations::default()
+            }
+        }
+    }
+
+    /// Location of the relations file.
+    pub fn path() -> PathBuf {
+        let dirs = directories::ProjectDirs::from("net.daboross", "OpenScreeps", "screeps-rs");
+        dirs.config_dir().join(RELATIONS_FILE_NAME)
+    }
+
+    fn from_json(json: &serde_json::Value) -> Self {
+        let mut players = HashMap::new();
+        for &relation in &Relation::ALL {
+            let names = match json.get(relation.key()).and_then(serde_json::Value::as_array) {
+                Some(names) => names,
+                None => continue,
+            };
+            for name in names {
+                match name.as_str() {
+                    Some(name) => {
+                        players.insert(name.to_lowercase(), relation);
+                    }
+                    None => warn!("ignoring non-string entry in relations list {}: {}", relation.key(), name),
+                }
+            }
+        }
+        Relations { players: players }
+    }
+
+    /// Finds a player's relation, if any.
+    ///
+    /// The logged in user, `own_id`, is always `Own`. Other players are found by id, or by username if it's known.
+    pub fn relation(&self, id: &str, username: Option<&str>, own_id: Option<&str>) -> Option<Relation> {
+        if own_id == Some(id) {
+            return Some(Relation::Own);
+        }
+        self.players
+            .get(id)
+            .or_else(|| username.and_then(|username| self.players.get(&username.to_lowercase())))
+            .cloned()
+    }
+}
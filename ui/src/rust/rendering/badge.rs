@@ -0,0 +1,193 @@
+//! Procedurally drawn player badges.
+//!
+//! The game draws badges from one of 24 built in patterns, each shaped by a parameter and painted in three colors.
+//! The shapes here loosely follow the game's patterns, so that each player's badge is recognizable, but aren't exact
+//! copies of them.
+use std::f64::consts::PI;
+
+use conrod::{self, Color};
+use glium::texture::Texture2dDataSource;
+
+use screeps_rs_network::Badge;
+
+use super::constants::*;
+
+/// Number of built in badge patterns.
+const PATTERN_COUNT: u32 = 24;
+
+/// Whether a badge uses a built in pattern. Custom badges are uploaded as images, and can't be drawn.
+pub fn can_draw(badge: &Badge) -> bool {
+    badge.pattern >= 1 && badge.pattern <= PATTERN_COUNT
+}
+
+/// Draws a badge as a circle on a transparent square `BADGE_TEXTURE_SIZE` pixels across.
+pub fn make_badge_texture(badge: &Badge) -> impl Texture2dDataSource<'static> {
+    let colors = [
+        parse_color(&badge.colors[0]),
+        parse_color(&badge.colors[1]),
+        parse_color(&badge.colors[2]),
+    ];
+    let param = (badge.param as f64 / 100.0).max(-1.0).min(1.0);
+    let size = BADGE_TEXTURE_SIZE as f64;
+
+    (0..BADGE_TEXTURE_SIZE)
+        .map(|row| {
+            (0..BADGE_TEXTURE_SIZE)
+                .map(|column| {
+                    // -1 to 1 across the badge, with y going up.
+                    let x = (column as f64 + 0.5) / size * 2.0 - 1.0;
+                    let y = 1.0 - (row as f64 + 0.5) / size * 2.0;
+                    if x * x + y * y > 1.0 {
+                        return (0.0, 0.0, 0.0, 0.0);
+                    }
+
+                    let x = if badge.flip { -x } else { x };
+                    let rgb = colors[pattern_layer(badge.pattern, x, y, param)].to_rgb();
+                    (rgb.0, rgb.1, rgb.2, rgb.3)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Parses a `#rrggbb` color, falling back to gray for anything else.
+fn parse_color(text: &str) -> Color {
+    let channel = |idx: usize| {
+        text.get(idx..idx + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+    };
+
+    match (text.starts_with('#') && text.len() == 7, channel(1), channel(3), channel(5)) {
+        (true, Some(r), Some(g), Some(b)) => conrod::color::rgb_bytes(r, g, b),
+        _ => {
+            debug!("unknown badge color {:?}", text);
+            conrod::color::GRAY
+        }
+    }
+}
+
+/// Finds which of the badge's colors covers a point: 0 for the primary color, which fills the background, 1 for
+/// the secondary color and 2 for the tertiary color.
+///
+/// `x` and `y` range from -1 to 1 across the badge, and `param` from -1 to 1.
+fn pattern_layer(pattern: u32, x: f64, y: f64, param: f64) -> usize {
+    let r = (x * x + y * y).sqrt();
+    // 0 to 1 around the badge, starting to the right.
+    let angle = (y.atan2(x) / (2.0 * PI) + 1.0) % 1.0;
+    let layer = |secondary: bool| if secondary { 1 } else { 0 };
+
+    match pattern {
+        // vertical band
+        1 => layer(x.abs() < 0.3 + 0.2 * param),
+        // horizontal split
+        2 => layer(y > param * 0.5),
+        // diagonal split
+        3 => layer(x + y > param),
+        // vertical stripes on each side
+        4 => if x < -0.35 + 0.2 * param {
+            1
+        } else if x > 0.35 - 0.2 * param {
+            2
+        } else {
+            0
+        },
+        // horizontal stripes above and below
+        5 => if y > 0.35 - 0.2 * param {
+            1
+        } else if y < -0.35 + 0.2 * param {
+            2
+        } else {
+            0
+        },
+        // centered circle
+        6 => layer(r < 0.45 + 0.2 * param),
+        // ring
+        7 => layer(r > 0.4 && r < 0.6 + 0.15 * param),
+        // cross
+        8 => layer(x.abs() < 0.2 + 0.1 * param || y.abs() < 0.2 + 0.1 * param),
+        // diagonal cross
+        9 => layer((x - y).abs() < 0.25 + 0.1 * param || (x + y).abs() < 0.25 + 0.1 * param),
+        // quarters
+        10 => layer((x > 0.0) == (y > 0.0)),
+        // concentric rings
+        11 => (r * (3.0 + 1.5 * param)) as usize % 3,
+        // chevron
+        12 => layer(y > x.abs() * (1.0 + param) - 0.3),
+        // rays
+        13 => layer((angle * (8.0 + 4.0 * param).round()) as usize % 2 == 1),
+        // spiral
+        14 => layer((angle + r * (1.5 + param)) % 1.0 < 0.5),
+        // three vertical bands
+        15 => if x < -0.33 + 0.3 * param {
+            0
+        } else if x < 0.33 + 0.3 * param {
+            1
+        } else {
+            2
+        },
+        // three horizontal bands
+        16 => if y > 0.33 + 0.3 * param {
+            0
+        } else if y > -0.33 + 0.3 * param {
+            1
+        } else {
+            2
+        },
+        // checkerboard
+        17 => {
+            let cells = 3.0 + (1.5 * param).round();
+            let column = ((x + 1.0) / 2.0 * cells) as usize;
+            let row = ((y + 1.0) / 2.0 * cells) as usize;
+            layer((column + row) % 2 == 1)
+        }
+        // waves
+        18 => {
+            let wave = (x * PI * (1.5 + param)).sin() * 0.4;
+            if y > wave {
+                1
+            } else if y < wave - 0.5 {
+                2
+            } else {
+                0
+            }
+        }
+        // diamond
+        19 => {
+            let distance = x.abs() + y.abs();
+            if distance < 0.3 + 0.1 * param {
+                2
+            } else if distance < 0.6 + 0.2 * param {
+                1
+            } else {
+                0
+            }
+        }
+        // crescent
+        20 => {
+            let inner_x = x - 0.3 - 0.1 * param;
+            layer(r < 0.7 && (inner_x * inner_x + y * y).sqrt() > 0.6)
+        }
+        // triangle
+        21 => layer(y > -0.5 && y < 0.6 && x.abs() < (0.6 - y) * (0.6 + 0.2 * param)),
+        // two circles
+        22 => {
+            let radius = 0.3 + 0.1 * param;
+            if ((x + 0.35) * (x + 0.35) + y * y).sqrt() < radius {
+                1
+            } else if ((x - 0.35) * (x - 0.35) + y * y).sqrt() < radius {
+                2
+            } else {
+                0
+            }
+        }
+        // diagonal stripes
+        23 => layer((((x + y + 2.0) * (2.0 + param)) as usize) % 2 == 1),
+        // arch over a horizon
+        24 => if y < -0.3 + 0.3 * param {
+            2
+        } else {
+            layer(r > 0.45 && r < 0.7 && y > 0.0)
+        },
+        _ => 0,
+    }
+}
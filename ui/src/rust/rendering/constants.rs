@@ -10,4 +10,44 @@ pub const SOURCE_COLOR: Color = Color::Rgba(1.0, 0.949, 0.274, 1.0);
 pub const MINERAL_COLOR: Color = Color::Rgba(0.2157, 0.0745, 0.5137, 1.0);
 pub const CONTROLLER_COLOR: Color = Color::Rgba(0.80392, 0.80392, 0.80392, 1.0);
 pub const KEEPER_COLOR: Color = Color::Rgba(0.3647, 0.2980, 0.1804, 1.0);
-pub const USER_COLOR: Color = Color::Rgba(0.1372, 0.3804, 0.2667, 1.0);
+pub const CONSTRUCTED_WALL_COLOR: Color = Color::Rgba(0.2, 0.2, 0.2, 1.0);
+pub const SPAWN_COLOR: Color = Color::Rgba(0.85, 0.85, 0.85, 1.0);
+pub const EXTENSION_COLOR: Color = Color::Rgba(0.7, 0.7, 0.7, 1.0);
+pub const EXTRACTOR_COLOR: Color = Color::Rgba(0.5, 0.5, 0.5, 1.0);
+pub const TOWER_COLOR: Color = Color::Rgba(0.6, 0.6, 0.75, 1.0);
+pub const STORAGE_COLOR: Color = Color::Rgba(0.55, 0.45, 0.25, 1.0);
+pub const TERMINAL_COLOR: Color = Color::Rgba(0.45, 0.55, 0.6, 1.0);
+pub const LINK_COLOR: Color = Color::Rgba(0.9, 0.9, 0.5, 1.0);
+pub const LAB_COLOR: Color = Color::Rgba(0.4, 0.4, 0.4, 1.0);
+pub const CONTAINER_COLOR: Color = Color::Rgba(0.4, 0.35, 0.25, 1.0);
+pub const OBSERVER_COLOR: Color = Color::Rgba(0.5, 0.7, 0.9, 1.0);
+pub const POWER_SPAWN_COLOR: Color = Color::Rgba(0.8, 0.2, 0.2, 1.0);
+pub const NUKER_COLOR: Color = Color::Rgba(0.3, 0.8, 0.3, 1.0);
+pub const CONSTRUCTION_SITE_COLOR: Color = Color::Rgba(0.8, 0.8, 0.8, 0.6);
+pub const RESOURCE_COLOR: Color = Color::Rgba(1.0, 0.9, 0.3, 1.0);
+pub const TOMBSTONE_COLOR: Color = Color::Rgba(0.3, 0.3, 0.3, 1.0);
+pub const CONTROLLER_LEVEL_COLOR: Color = Color::Rgba(1.0, 1.0, 1.0, 1.0);
+/// Opacity of ramparts, which are drawn in their owner's color over whatever they protect.
+pub const RAMPART_ALPHA: f32 = 0.6;
+pub const CLOSED_ROOM_COLOR: Color = Color::Rgba(0.0, 0.0, 0.0, 0.6);
+pub const NOVICE_ROOM_COLOR: Color = Color::Rgba(0.2, 0.6, 0.2, 0.15);
+pub const RESPAWN_ROOM_COLOR: Color = Color::Rgba(0.7, 0.6, 0.1, 0.15);
+pub const OWNED_ROOM_COLOR: Color = Color::Rgba(0.8, 0.2, 0.2, 0.45);
+pub const RESERVED_ROOM_COLOR: Color = Color::Rgba(0.8, 0.2, 0.2, 0.2);
+pub const OWN_COLOR: Color = Color::Rgba(0.1372, 0.3804, 0.2667, 1.0);
+pub const ALLY_COLOR: Color = Color::Rgba(0.2, 0.4, 0.9, 1.0);
+pub const ENEMY_COLOR: Color = Color::Rgba(0.9, 0.15, 0.15, 1.0);
+pub const NEUTRAL_COLOR: Color = Color::Rgba(0.6, 0.6, 0.6, 1.0);
+/// Saturation and lightness of the colors picked for players without a relation.
+pub const USER_COLOR_SATURATION: f32 = 0.55;
+pub const USER_COLOR_LIGHTNESS: f32 = 0.5;
+
+/// Size of a room on screen, in pixels, below which the map only shows room owners.
+pub const OVERVIEW_ROOM_SIZE: f64 = 40.0;
+
+/// Width and height of generated badge images, in pixels.
+pub const BADGE_TEXTURE_SIZE: u32 = 64;
+/// Size of badges drawn on controllers, in room tiles.
+pub const CONTROLLER_BADGE_TILES: f64 = 5.0;
+/// Size of badges drawn in the middle of owned rooms when zoomed out, in room tiles.
+pub const OVERVIEW_BADGE_TILES: f64 = 25.0;
@@ -0,0 +1,344 @@
+//! Rasterizes map data into plain RGBA images, without needing a window or GPU.
+use std::{fmt, fs};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use conrod::Color;
+use png::{self, HasParameters};
+
+use screeps_api::endpoints::room_terrain::{TerrainGrid, TerrainType};
+use screeps_api::websocket::RoomMapViewUpdate;
+
+use screeps_rs_network::{MapCacheData, RoomRect};
+
+use directories;
+
+use super::constants::*;
+use super::player_colors::PlayerColors;
+
+/// Width and height of a room, in tiles.
+const ROOM_TILES: u32 = 50;
+/// Largest image `rasterize` will create, in bytes.
+const MAX_IMAGE_BYTES: usize = 1 << 30;
+/// Name of the directory exports from the client are written to, in the data directory.
+const EXPORTS_DIR_NAME: &str = "exports";
+
+/// Reasons map data can't be rasterized.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportError {
+    /// Tiles were asked to be zero pixels across.
+    ZeroScale,
+    /// The image would be larger than `MAX_IMAGE_BYTES`.
+    TooLarge,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::ZeroScale => write!(f, "scale must be at least one pixel per tile"),
+            ExportError::TooLarge => write!(
+                f,
+                "image would be larger than {} MiB, try a smaller region or scale",
+                MAX_IMAGE_BYTES >> 20
+            ),
+        }
+    }
+}
+
+/// An RGBA image with 8 bits per channel, stored row by row starting at the top left.
+#[derive(Clone, Debug)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Creates a new fully transparent image, or returns `None` if it wouldn't fit in memory.
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        Some(RgbaImage {
+            width: width,
+            height: height,
+            data: vec![0; byte_len(width, height)?],
+        })
+    }
+
+    /// Fills a rectangle of pixels with the given color, clipping anything outside of the image.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+        let end_x = x.saturating_add(width).min(self.width);
+        let end_y = y.saturating_add(height).min(self.height);
+
+        for pixel_y in y..end_y {
+            for pixel_x in x..end_x {
+                let index = (pixel_y as usize * self.width as usize + pixel_x as usize) * 4;
+                self.data[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Writes this image to a PNG file.
+    pub fn write_png<P: AsRef<Path>>(&self, path: P) -> Result<(), png::EncodingError> {
+        let file = fs::File::create(path)?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+
+        Ok(())
+    }
+}
+
+/// Size of an image's data in bytes, or `None` if it doesn't fit in a `usize`.
+fn byte_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul(4)
+}
+
+fn to_rgba8(color: Color) -> [u8; 4] {
+    let rgba = color.to_rgb();
+    let channel = |c: f32| (c.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    [channel(rgba.0), channel(rgba.1), channel(rgba.2), channel(rgba.3)]
+}
+
+fn terrain_color(terrain_type: TerrainType) -> Color {
+    match terrain_type {
+        TerrainType::Plains => PLAINS_COLOR,
+        TerrainType::Swamp => SWAMP_COLOR,
+        TerrainType::SwampyWall | TerrainType::Wall => WALL_COLOR,
+    }
+}
+
+/// Directory exports from the client are written to.
+pub fn exports_dir() -> PathBuf {
+    let dirs = directories::ProjectDirs::from("net.daboross", "OpenScreeps", "screeps-rs");
+    dirs.data_dir().join(EXPORTS_DIR_NAME)
+}
+
+/// Copies the terrain and map views of the given rooms, so that they can be rasterized on another thread.
+pub fn copy_rooms(rooms: RoomRect, data: &MapCacheData) -> MapCacheData {
+    let mut copy = MapCacheData::default();
+    for room_name in rooms {
+        if let Some(terrain) = data.terrain.get(&room_name) {
+            copy.terrain.insert(room_name, terrain.clone());
+        }
+        if let Some(map_view) = data.map_views.get(&room_name) {
+            copy.map_views.insert(room_name, map_view.clone());
+        }
+    }
+    copy
+}
+
+/// Rasterizes all known terrain and map views for the given rooms.
+///
+/// Each room tile is drawn as a `scale` by `scale` square. Rooms without known terrain are left transparent.
+///
+/// Fails without drawing anything if `scale` is zero, or if the image would be too large.
+pub fn rasterize(
+    rooms: RoomRect,
+    data: &MapCacheData,
+    colors: &PlayerColors,
+    scale: u32,
+) -> Result<RgbaImage, ExportError> {
+    if scale == 0 {
+        return Err(ExportError::ZeroScale);
+    }
+    let horizontal_room_count = rooms.width() as u32;
+    let vertical_room_count = rooms.height() as u32;
+
+    let room_pixels = ROOM_TILES.checked_mul(scale).ok_or(ExportError::TooLarge)?;
+    let width = horizontal_room_count
+        .checked_mul(room_pixels)
+        .ok_or(ExportError::TooLarge)?;
+    let height = vertical_room_count
+        .checked_mul(room_pixels)
+        .ok_or(ExportError::TooLarge)?;
+    match byte_len(width, height) {
+        Some(bytes) if bytes <= MAX_IMAGE_BYTES => (),
+        _ => return Err(ExportError::TooLarge),
+    }
+
+    let mut image = RgbaImage::new(width, height).ok_or(ExportError::TooLarge)?;
+
+    for relative_room_x in 0..horizontal_room_count {
+        for relative_room_y in 0..vertical_room_count {
+            let room_name = rooms.start + (relative_room_x as i32, relative_room_y as i32);
+            let origin = (relative_room_x * room_pixels, relative_room_y * room_pixels);
+
+            if let Some(&(_, Some(ref terrain))) = data.terrain.get(&room_name) {
+                rasterize_terrain(&mut image, origin, scale, terrain);
+            }
+            if let Some(&(_, ref map_view)) = data.map_views.get(&room_name) {
+                rasterize_map_view(&mut image, origin, scale, colors, map_view);
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+fn rasterize_terrain(image: &mut RgbaImage, origin: (u32, u32), scale: u32, terrain: &TerrainGrid) {
+    for (y, row) in terrain.iter().enumerate() {
+        for (x, &terrain_type) in row.iter().enumerate() {
+            image.fill_rect(
+                origin.0 + x as u32 * scale,
+                origin.1 + y as u32 * scale,
+                scale,
+                scale,
+                to_rgba8(terrain_color(terrain_type)),
+            );
+        }
+    }
+}
+
+fn rasterize_map_view(
+    image: &mut RgbaImage,
+    origin: (u32, u32),
+    scale: u32,
+    colors: &PlayerColors,
+    map_view: &RoomMapViewUpdate,
+) {
+    let mut draw_all = |positions: &[(u32, u32)], color: Color| {
+        let color = to_rgba8(color);
+        for &(x, y) in positions {
+            // positions come from the server, so they may be outside of the room.
+            if x >= ROOM_TILES || y >= ROOM_TILES {
+                continue;
+            }
+            image.fill_rect(origin.0 + x * scale, origin.1 + y * scale, scale, scale, color);
+        }
+    };
+
+    draw_all(&map_view.roads, ROAD_COLOR);
+    draw_all(&map_view.power_or_power_bank, POWER_COLOR);
+    draw_all(&map_view.walls, WALL_COLOR);
+    draw_all(&map_view.portals, PORTAL_COLOR);
+    draw_all(&map_view.sources, SOURCE_COLOR);
+    draw_all(&map_view.minerals, MINERAL_COLOR);
+    draw_all(&map_view.controllers, CONTROLLER_COLOR);
+    draw_all(&map_view.keeper_lairs, KEEPER_COLOR);
+
+    for &(ref user_id, ref positions) in &map_view.users_objects {
+        draw_all(positions, colors.color(user_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps_api::RoomName;
+    use screeps_api::endpoints::room_terrain::TerrainType;
+    use screeps_api::websocket::RoomMapViewUpdate;
+    use screeps_rs_network::{MapCacheData, RoomRect};
+    use serde_json;
+    use time;
+
+    use super::super::constants::*;
+    use super::super::player_colors::PlayerColors;
+    use super::{rasterize, to_rgba8, ExportError, RgbaImage};
+
+    const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+    /// Two rooms side by side, starting at W1N1.
+    fn two_rooms() -> RoomRect {
+        let start = RoomName::new("W1N1").unwrap();
+        RoomRect::new(start..start + (2, 1))
+    }
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * image.width as usize + x as usize) * 4;
+        [
+            image.data[index],
+            image.data[index + 1],
+            image.data[index + 2],
+            image.data[index + 3],
+        ]
+    }
+
+    /// Creates a map view with only roads, given as a JSON list of positions.
+    fn roads(positions: &str) -> RoomMapViewUpdate {
+        let json = format!(
+            r#"{{"w": [], "r": {}, "pb": [], "p": [], "s": [], "m": [], "c": [], "k": []}}"#,
+            positions
+        );
+        serde_json::from_str(&json).expect("expected test map view to parse")
+    }
+
+    #[test]
+    fn zero_scale_is_an_error() {
+        let result = rasterize(two_rooms(), &MapCacheData::default(), &PlayerColors::new(), 0);
+        assert_eq!(result.err(), Some(ExportError::ZeroScale));
+    }
+
+    #[test]
+    fn large_images_are_an_error() {
+        let start = RoomName::new("W1N1").unwrap();
+        let rooms = RoomRect::new(start..start + (100, 100));
+        // 100 rooms of 500 pixels each way is 10 GB.
+        let result = rasterize(rooms, &MapCacheData::default(), &PlayerColors::new(), 10);
+        assert_eq!(result.err(), Some(ExportError::TooLarge));
+    }
+
+    #[test]
+    fn overflowing_sizes_are_an_error() {
+        let result = rasterize(two_rooms(), &MapCacheData::default(), &PlayerColors::new(), u32::max_value());
+        assert_eq!(result.err(), Some(ExportError::TooLarge));
+    }
+
+    #[test]
+    fn image_covers_every_room() {
+        let image = rasterize(two_rooms(), &MapCacheData::default(), &PlayerColors::new(), 2).unwrap();
+        assert_eq!((image.width, image.height), (200, 100));
+        assert_eq!(image.data.len(), 200 * 100 * 4);
+        assert!(image.data.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn terrain_is_drawn_at_each_rooms_offset() {
+        let rooms = two_rooms();
+        let mut terrain = [[TerrainType::Plains; 50]; 50];
+        terrain[4][3] = TerrainType::Swamp;
+        terrain[49][49] = TerrainType::Wall;
+        let mut data = MapCacheData::default();
+        // only the second room has terrain.
+        data.terrain.insert(rooms.start + (1, 0), (time::get_time(), Some(terrain)));
+
+        let image = rasterize(rooms, &data, &PlayerColors::new(), 2).unwrap();
+
+        // the first room is left transparent.
+        assert_eq!(pixel(&image, 0, 0), TRANSPARENT);
+        assert_eq!(pixel(&image, 99, 99), TRANSPARENT);
+        // tiles in the second room start 100 pixels in, and are 2 pixels across.
+        assert_eq!(pixel(&image, 100, 0), to_rgba8(PLAINS_COLOR));
+        assert_eq!(pixel(&image, 106, 8), to_rgba8(SWAMP_COLOR));
+        assert_eq!(pixel(&image, 107, 9), to_rgba8(SWAMP_COLOR));
+        assert_eq!(pixel(&image, 108, 8), to_rgba8(PLAINS_COLOR));
+        assert_eq!(pixel(&image, 198, 98), to_rgba8(WALL_COLOR));
+        assert_eq!(pixel(&image, 199, 99), to_rgba8(WALL_COLOR));
+    }
+
+    #[test]
+    fn map_views_are_drawn_over_terrain() {
+        let rooms = two_rooms();
+        let mut data = MapCacheData::default();
+        data.terrain.insert(rooms.start, (time::get_time(), Some([[TerrainType::Plains; 50]; 50])));
+        data.map_views.insert(rooms.start, (time::get_time(), roads("[[10, 20]]")));
+
+        let image = rasterize(rooms, &data, &PlayerColors::new(), 1).unwrap();
+
+        assert_eq!(pixel(&image, 10, 20), to_rgba8(ROAD_COLOR));
+        assert_eq!(pixel(&image, 11, 20), to_rgba8(PLAINS_COLOR));
+    }
+
+    #[test]
+    fn map_view_positions_outside_of_the_room_are_skipped() {
+        let rooms = two_rooms();
+        let mut data = MapCacheData::default();
+        // (60, 10) and (10, 60) would land in the room to the right of and below this one.
+        data.map_views.insert(rooms.start, (time::get_time(), roads("[[60, 10], [10, 60], [50, 0]]")));
+
+        let image = rasterize(rooms, &data, &PlayerColors::new(), 1).unwrap();
+
+        assert!(image.data.iter().all(|&byte| byte == 0));
+    }
+}
@@ -10,10 +10,11 @@ use screeps_api::endpoints::room_terrain::{TerrainGrid, TerrainType};
 use screeps_api::websocket::RoomMapViewUpdate;
 use screeps_api::websocket::types::room::objects::KnownRoomObject;
 
-use screeps_rs_network::{MapCacheData, SelectedRooms};
+use screeps_rs_network::{MapCacheData, RoomControl, RoomRect, RoomStatus};
 
 use super::constants::*;
 use super::types::{IterAdapter, MapViewOffset};
+use super::player_colors::PlayerColors;
 use super::render_cache::RenderCache;
 
 #[derive(Copy, Clone)]
@@ -30,7 +31,7 @@ pub fn render<'a>(
     view_rect: Rect,
     scizzor: Rect,
     image_cache: &'a RenderCache,
-    (selected, data, offset): (SelectedRooms, Ref<'a, MapCacheData>, MapViewOffset),
+    (selected, data, offset): (RoomRect, Ref<'a, MapCacheData>, MapViewOffset),
 ) -> impl Iterator<Item = Primitive<'static>> + 'a {
     let render_data = RenderData {
         id,
@@ -45,8 +46,8 @@ pub fn render<'a>(
 
     let gen = move || {
         let start_room_name = selected.start;
-        let horizontal_room_count = selected.end.x_coord - selected.start.x_coord;
-        let vertical_room_count = selected.end.y_coord - selected.start.y_coord;
+        let horizontal_room_count = selected.width();
+        let vertical_room_count = selected.height();
 
         // terrain
         for relative_room_x in 0..horizontal_room_count {
@@ -98,14 +99,102 @@ pub fn render<'a>(
             }
         }
 
-        // room view
-        let opt_viewed: Option<RoomName> = data.detail_view.as_ref().map(|&(name, _)| name);
-        if let Some(viewed_room) = opt_viewed {
+        // owners, when zoomed out too far for map views
+        if offset.is_overview() {
+            for relative_room_x in 0..horizontal_room_count {
+                for relative_room_y in 0..vertical_room_count {
+                    let current_name = start_room_name + (relative_room_x, relative_room_y);
+
+                    let color = match data.room_stats.get(&current_name) {
+                        Some(&(_, ref stats)) => stats.control.as_ref().map(room_control_color),
+                        None => None,
+                    };
+
+                    if let Some(color) = color {
+                        yield_from!(render_room_overlay(
+                            render_data,
+                            relative_room_x,
+                            relative_room_y,
+                            color,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // owners' badges, on their controllers or in the middle of the room when zoomed out
+        for relative_room_x in 0..horizontal_room_count {
+            for relative_room_y in 0..vertical_room_count {
+                let current_name = start_room_name + (relative_room_x, relative_room_y);
+
+                let badge = match data.room_stats.get(&current_name) {
+                    Some(&(_, ref stats)) => match stats.control {
+                        Some(RoomControl::Owned { ref user, .. }) => render_data.image_cache.get_user_badge(&user.id),
+                        _ => None,
+                    },
+                    None => None,
+                };
+                let badge = match badge {
+                    Some(badge) => badge,
+                    None => continue,
+                };
+
+                let controller = if offset.is_overview() {
+                    None
+                } else {
+                    data.map_views
+                        .get(&current_name)
+                        .and_then(|&(_, ref map_view)| map_view.controllers.first().cloned())
+                };
+                let (center, size) = match controller {
+                    Some((x, y)) => ((x as f64 + 0.5, y as f64 + 0.5), CONTROLLER_BADGE_TILES),
+                    None if offset.is_overview() => ((25.0, 25.0), OVERVIEW_BADGE_TILES),
+                    None => continue,
+                };
+
+                yield_from!(render_badge(
+                    render_data,
+                    relative_room_x,
+                    relative_room_y,
+                    center,
+                    size,
+                    badge,
+                ));
+            }
+        }
+
+        // closed rooms, protected areas and the edge of the world
+        for relative_room_x in 0..horizontal_room_count {
+            for relative_room_y in 0..vertical_room_count {
+                let current_name = start_room_name + (relative_room_x, relative_room_y);
+
+                let outside_world = data.world_size
+                    .map_or(false, |size| !size.contains(&current_name));
+                let color = if outside_world {
+                    Some(CLOSED_ROOM_COLOR)
+                } else {
+                    data.room_status
+                        .get(&current_name)
+                        .and_then(|&(_, status)| room_status_color(status))
+                };
+
+                if let Some(color) = color {
+                    yield_from!(render_room_overlay(
+                        render_data,
+                        relative_room_x,
+                        relative_room_y,
+                        color,
+                    ));
+                }
+            }
+        }
+
+        // room views
+        let viewed_rooms: Vec<RoomName> = data.detail_view.keys().cloned().collect();
+        for viewed_room in viewed_rooms {
             let (x_diff, y_diff) = viewed_room - start_room_name;
             if x_diff >= 0 && x_diff <= horizontal_room_count && y_diff >= 0 && y_diff <= vertical_room_count {
-                let room_objects = Ref::map(Ref::clone(&data), |data| {
-                    &data.detail_view.as_ref().unwrap().1
-                });
+                let room_objects = Ref::map(Ref::clone(&data), |data| &data.detail_view[&viewed_room]);
 
                 yield_from!(render_room(render_data, x_diff, y_diff, room_objects));
             }
@@ -123,6 +212,22 @@ fn terrain_color(terrain_type: TerrainType) -> conrod::Color {
     }
 }
 
+fn room_status_color(status: RoomStatus) -> Option<conrod::Color> {
+    match status {
+        RoomStatus::Normal => None,
+        RoomStatus::Closed => Some(CLOSED_ROOM_COLOR),
+        RoomStatus::Novice { .. } => Some(NOVICE_ROOM_COLOR),
+        RoomStatus::Respawn { .. } => Some(RESPAWN_ROOM_COLOR),
+    }
+}
+
+fn room_control_color(control: &RoomControl) -> conrod::Color {
+    match *control {
+        RoomControl::Owned { .. } => OWNED_ROOM_COLOR,
+        RoomControl::Reserved { .. } => RESERVED_ROOM_COLOR,
+    }
+}
+
 use glium::texture::Texture2dDataSource;
 
 pub fn make_terrain_texture(terrain: &TerrainGrid) -> impl Texture2dDataSource<'static> {
@@ -200,6 +305,57 @@ fn render_room_image<'a>(
     }
 }
 
+fn render_room_overlay<'a>(
+    data: RenderData<'a>,
+    current_relative_room_x: i32,
+    current_relative_room_y: i32,
+    color: conrod::Color,
+) -> impl Generator<Yield = Primitive<'static>, Return = ()> + 'a {
+    move || {
+        let x_pos = data.start_room_screen_pos.0 + data.offset.room_size * (current_relative_room_x as f64);
+        let y_pos = data.start_room_screen_pos.1 + data.offset.room_size * (current_relative_room_y as f64);
+        let end_x = x_pos + data.offset.room_size;
+        let end_y = y_pos + data.offset.room_size;
+
+        yield Primitive {
+            id: data.id,
+            kind: PrimitiveKind::Rectangle { color: color },
+            scizzor: data.scizzor,
+            rect: Rect::from_corners([x_pos, y_pos], [end_x, end_y]),
+        }
+    }
+}
+
+/// Draws a badge centered on the given position, in tiles from the top left of the room.
+fn render_badge<'a>(
+    data: RenderData<'a>,
+    current_relative_room_x: i32,
+    current_relative_room_y: i32,
+    (center_x, center_y): (f64, f64),
+    size_tiles: f64,
+    image_id: ImageId,
+) -> impl Generator<Yield = Primitive<'static>, Return = ()> + 'a {
+    move || {
+        let tile_size = data.offset.room_size / 50.0;
+        let x_pos = data.start_room_screen_pos.0 + data.offset.room_size * (current_relative_room_x as f64)
+            + tile_size * center_x;
+        let y_pos = data.start_room_screen_pos.1 + data.offset.room_size * (current_relative_room_y as f64 + 1.0)
+            - tile_size * center_y;
+        let size = tile_size * size_tiles;
+
+        yield Primitive {
+            id: data.id,
+            kind: PrimitiveKind::Image {
+                image_id,
+                color: None,
+                source_rect: None,
+            },
+            scizzor: data.scizzor,
+            rect: Rect::from_xy_dim([x_pos, y_pos], [size, size]),
+        }
+    }
+}
+
 fn render_map_view_of<'a>(
     data: RenderData<'a>,
     current_relative_room_x: i32,
@@ -287,35 +443,182 @@ fn render_map_view_of<'a>(
 
         let num_users = map_view.users_objects.len();
         for idx in 0..num_users {
+            let color = data.image_cache.player_colors.color(&map_view.users_objects[idx].0);
             let num_user_objects = map_view.users_objects[idx].1.len();
             for jdx in 0..num_user_objects {
                 let (x, y) = map_view.users_objects[idx].1[jdx];
-                yield draw_square_at!(x, y, USER_COLOR);
+                yield draw_square_at!(x, y, color);
             }
         }
     }
 }
 
+/// Shapes room objects are drawn as. Primitives must live for `'static`, so each shape is built from rectangles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Shape {
+    Square,
+    /// The outline of a square, leaving whatever is under it visible.
+    Outline,
+    /// A rough circle, from a wide and a tall rectangle overlapping.
+    Round,
+    /// A rough diamond, from three stacked bars.
+    Diamond,
+    /// A thin horizontal bar.
+    Bar,
+}
+
+/// How a single room object is drawn.
+#[derive(Copy, Clone, Debug)]
+struct ObjectStyle {
+    shape: Shape,
+    /// Width of the shape, in tiles.
+    size: f64,
+    color: conrod::Color,
+    /// Objects in higher layers are drawn over lower ones.
+    layer: u8,
+}
+
+impl ObjectStyle {
+    fn new(shape: Shape, size: f64, color: conrod::Color, layer: u8) -> Self {
+        ObjectStyle {
+            shape: shape,
+            size: size,
+            color: color,
+            layer: layer,
+        }
+    }
+}
+
+/// Finds where and how to draw a room object, or returns None for objects this doesn't know how to draw.
+fn object_style(object: &KnownRoomObject, colors: &PlayerColors) -> Option<((u32, u32), ObjectStyle)> {
+    use self::Shape::*;
+
+    let (position, style) = match *object {
+        KnownRoomObject::Road(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.5, ROAD_COLOR, 0)),
+        KnownRoomObject::ConstructionSite(ref o) => {
+            ((o.x, o.y), ObjectStyle::new(Outline, 0.6, CONSTRUCTION_SITE_COLOR, 1))
+        }
+        KnownRoomObject::Resource(ref o) => ((o.x, o.y), ObjectStyle::new(Bar, 0.4, RESOURCE_COLOR, 1)),
+        KnownRoomObject::Tombstone(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 0.4, TOMBSTONE_COLOR, 1)),
+        KnownRoomObject::Source(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.8, SOURCE_COLOR, 2)),
+        KnownRoomObject::Mineral(ref o) => ((o.x, o.y), ObjectStyle::new(Diamond, 0.8, MINERAL_COLOR, 2)),
+        KnownRoomObject::Controller(ref o) => ((o.x, o.y), ObjectStyle::new(Diamond, 1.0, CONTROLLER_COLOR, 2)),
+        KnownRoomObject::KeeperLair(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 0.9, KEEPER_COLOR, 2)),
+        KnownRoomObject::Portal(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 1.0, PORTAL_COLOR, 2)),
+        KnownRoomObject::PowerBank(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 0.9, POWER_COLOR, 2)),
+        KnownRoomObject::Wall(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 1.0, CONSTRUCTED_WALL_COLOR, 2)),
+        KnownRoomObject::Spawn(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 1.0, SPAWN_COLOR, 3)),
+        KnownRoomObject::Extension(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.5, EXTENSION_COLOR, 3)),
+        KnownRoomObject::Extractor(ref o) => ((o.x, o.y), ObjectStyle::new(Outline, 0.9, EXTRACTOR_COLOR, 3)),
+        KnownRoomObject::Tower(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.8, TOWER_COLOR, 3)),
+        KnownRoomObject::Storage(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 0.9, STORAGE_COLOR, 3)),
+        KnownRoomObject::Terminal(ref o) => ((o.x, o.y), ObjectStyle::new(Diamond, 0.9, TERMINAL_COLOR, 3)),
+        KnownRoomObject::Link(ref o) => ((o.x, o.y), ObjectStyle::new(Diamond, 0.5, LINK_COLOR, 3)),
+        KnownRoomObject::Lab(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.7, LAB_COLOR, 3)),
+        KnownRoomObject::Container(ref o) => ((o.x, o.y), ObjectStyle::new(Square, 0.5, CONTAINER_COLOR, 3)),
+        KnownRoomObject::Observer(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.4, OBSERVER_COLOR, 3)),
+        KnownRoomObject::PowerSpawn(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 1.0, POWER_SPAWN_COLOR, 3)),
+        KnownRoomObject::Nuker(ref o) => ((o.x, o.y), ObjectStyle::new(Diamond, 1.0, NUKER_COLOR, 3)),
+        KnownRoomObject::Rampart(ref o) => {
+            let color = colors.color(&o.user).with_alpha(RAMPART_ALPHA);
+            ((o.x, o.y), ObjectStyle::new(Outline, 1.0, color, 4))
+        }
+        KnownRoomObject::Creep(ref o) => ((o.x, o.y), ObjectStyle::new(Round, 0.7, colors.color(&o.user), 5)),
+        _ => return None,
+    };
+
+    Some((position, style))
+}
+
+/// Splits a shape centered on `(x, y)` into the rectangles which draw it.
+fn shape_rects(shape: Shape, (x, y): (f64, f64), size: f64) -> Vec<Rect> {
+    let half = size / 2.0;
+    match shape {
+        Shape::Square => vec![Rect::from_xy_dim([x, y], [size, size])],
+        Shape::Outline => {
+            let line = size / 8.0;
+            vec![
+                Rect::from_xy_dim([x, y + half - line / 2.0], [size, line]),
+                Rect::from_xy_dim([x, y - half + line / 2.0], [size, line]),
+                Rect::from_xy_dim([x - half + line / 2.0, y], [line, size - 2.0 * line]),
+                Rect::from_xy_dim([x + half - line / 2.0, y], [line, size - 2.0 * line]),
+            ]
+        }
+        Shape::Round => vec![
+            Rect::from_xy_dim([x, y], [size, size * 0.6]),
+            Rect::from_xy_dim([x, y], [size * 0.6, size]),
+        ],
+        Shape::Diamond => vec![
+            Rect::from_xy_dim([x, y + size / 3.0], [size / 3.0, size / 3.0]),
+            Rect::from_xy_dim([x, y], [size, size / 3.0]),
+            Rect::from_xy_dim([x, y - size / 3.0], [size / 3.0, size / 3.0]),
+        ],
+        Shape::Bar => vec![Rect::from_xy_dim([x, y], [size, size / 3.0])],
+    }
+}
+
 fn render_room<'a>(
     data: RenderData<'a>,
     current_relative_room_x: i32,
     current_relative_room_y: i32,
-    _room_objects: Ref<'a, HashMap<String, KnownRoomObject>>,
+    room_objects: Ref<'a, HashMap<String, KnownRoomObject>>,
 ) -> impl Generator<Yield = Primitive<'static>, Return = ()> + 'a {
     move || {
-        let x_pos = data.start_room_screen_pos.0 + data.offset.room_size * (current_relative_room_x as f64);
-        let y_pos = data.start_room_screen_pos.1 + data.offset.room_size * (current_relative_room_y as f64);
-
-        yield Primitive {
-            id: data.id,
-            kind: PrimitiveKind::Rectangle {
-                color: KEEPER_COLOR,
-            },
-            scizzor: data.scizzor,
-            rect: Rect::from_corners(
-                [x_pos, y_pos - data.offset.room_size],
-                [x_pos + data.offset.room_size, y_pos],
-            ),
+        let room_screen_size = data.offset.room_size;
+        let tile_size = room_screen_size / 50.0;
+        let room_x_pos = data.start_room_screen_pos.0 + room_screen_size * (current_relative_room_x as f64);
+        let room_y_pos = data.start_room_screen_pos.1 + room_screen_size * (current_relative_room_y as f64 + 1.0);
+
+        // center of a tile on screen, in the same place render_map_view_of puts it.
+        let tile_center = move |x: u32, y: u32| {
+            (
+                room_x_pos + tile_size * (x as f64 + 0.5),
+                room_y_pos - tile_size * (y as f64 + 0.5),
+            )
         };
+
+        // work out everything to draw up front, so that the objects aren't borrowed while yielding.
+        let mut objects = room_objects
+            .values()
+            .filter_map(|object| object_style(object, &data.image_cache.player_colors))
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|&(_, style)| style.layer);
+
+        let controller_level = room_objects
+            .values()
+            .filter_map(|object| match *object {
+                KnownRoomObject::Controller(ref o) => Some(((o.x, o.y), o.level as u32)),
+                _ => None,
+            })
+            .next();
+
+        for ((x, y), style) in objects {
+            for rect in shape_rects(style.shape, tile_center(x, y), style.size * tile_size) {
+                yield Primitive {
+                    id: data.id,
+                    kind: PrimitiveKind::Rectangle { color: style.color },
+                    scizzor: data.scizzor,
+                    rect: rect,
+                };
+            }
+        }
+
+        // controller level, as a row of pips under the controller.
+        if let Some(((x, y), level)) = controller_level {
+            let (center_x, center_y) = tile_center(x, y);
+            let pip_size = tile_size / 4.0;
+            let start_x = center_x - pip_size * level as f64;
+            for pip in 0..level {
+                let pip_x = start_x + pip_size * (2 * pip) as f64 + pip_size;
+                yield Primitive {
+                    id: data.id,
+                    kind: PrimitiveKind::Rectangle {
+                        color: CONTROLLER_LEVEL_COLOR,
+                    },
+                    scizzor: data.scizzor,
+                    rect: Rect::from_xy_dim([pip_x, center_y - tile_size * 0.75], [pip_size, pip_size]),
+                };
+            }
+        }
     }
 }
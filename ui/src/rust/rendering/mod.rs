@@ -5,25 +5,29 @@ use glium;
 use conrod::{self, widget, Rect};
 use conrod::render::{Primitive, PrimitiveWalker};
 
-use screeps_rs_network::{MapCache, MapCacheData, SelectedRooms};
+use screeps_rs_network::{MapCache, MapCacheData, RoomRect};
 
 #[macro_use]
 mod macros;
+pub mod badge;
 pub mod constants;
+pub mod export;
 mod map_view;
+pub mod player_colors;
 mod types;
 pub mod render_cache;
 
 pub use self::types::MapViewOffset;
+pub use self::player_colors::PlayerColors;
 pub use self::render_cache::RenderCache;
 
 #[derive(Clone, Debug)]
 pub enum AdditionalRenderType {
-    MapView((SelectedRooms, MapCache, MapViewOffset)),
+    MapView((RoomRect, MapCache, MapViewOffset)),
 }
 
 enum BorrowedRenderType<'a> {
-    MapView((SelectedRooms, Ref<'a, MapCacheData>, MapViewOffset)),
+    MapView((RoomRect, Ref<'a, MapCacheData>, MapViewOffset)),
 }
 
 impl<'a> Clone for BorrowedRenderType<'a> {
@@ -90,7 +94,7 @@ pub trait RenderPipelineFinish {
 
 impl AdditionalRender {
     #[inline(always)]
-    pub fn map_view(replace: widget::Id, rooms: SelectedRooms, cache: MapCache, offset: MapViewOffset) -> Self {
+    pub fn map_view(replace: widget::Id, rooms: RoomRect, cache: MapCache, offset: MapViewOffset) -> Self {
         AdditionalRender {
             replace: replace,
             draw_type: AdditionalRenderType::MapView((rooms, cache, offset)),
@@ -0,0 +1,63 @@
+//! Colors for each player's objects.
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use conrod::{self, Color};
+
+use relations::Relation;
+use super::constants::*;
+
+/// Colors players' objects are drawn in.
+///
+/// Players with a relation use its color, and everyone else gets a color picked from their user id, so that it
+/// stays the same between sessions.
+#[derive(Clone, Debug, Default)]
+pub struct PlayerColors {
+    relations: HashMap<String, Relation>,
+}
+
+impl PlayerColors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the known relations, by user id.
+    pub fn set_relations(&mut self, relations: HashMap<String, Relation>) {
+        self.relations = relations;
+    }
+
+    pub fn relation(&self, user_id: &str) -> Option<Relation> {
+        self.relations.get(user_id).cloned()
+    }
+
+    pub fn color(&self, user_id: &str) -> Color {
+        match self.relation(user_id) {
+            Some(relation) => relation_color(relation),
+            None => user_color(user_id),
+        }
+    }
+}
+
+pub fn relation_color(relation: Relation) -> Color {
+    match relation {
+        Relation::Own => OWN_COLOR,
+        Relation::Ally => ALLY_COLOR,
+        Relation::Enemy => ENEMY_COLOR,
+        Relation::Neutral => NEUTRAL_COLOR,
+    }
+}
+
+/// Picks a color for a user from their id.
+///
+/// This hashes the id with FNV-1a rather than the standard library's hasher, which isn't guaranteed to give the
+/// same result between releases.
+pub fn user_color(user_id: &str) -> Color {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in user_id.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+
+    let hue = (hash % 360) as f32 / 360.0 * 2.0 * PI;
+    conrod::color::hsl(hue, USER_COLOR_SATURATION, USER_COLOR_LIGHTNESS)
+}
@@ -1,29 +1,51 @@
 use std::collections::hash_map::{Entry, HashMap};
+use std::mem;
 
 use screeps_api::{RoomName, TerrainGrid};
-use screeps_rs_network::NetworkEvent;
+use screeps_rs_network::{Badge, NetworkEvent};
 use conrod::image::{Id as ImageId, Map as ImageMap};
 use glium;
 
-use super::map_view;
+use super::{badge, map_view};
+use super::player_colors::PlayerColors;
 
 // pub type Texture = glium::texture::CompressedSrgbTexture2d;
 pub type Texture = glium::texture::SrgbTexture2d;
 
 pub const INVALIDATE_EVERY_UPDATES: u32 = 200;
 
+/// A badge image, and whether it's been used since cached images were last trimmed.
+struct UsedImage {
+    id: ImageId,
+    used: bool,
+}
+
+impl UsedImage {
+    fn new(id: ImageId) -> Self {
+        UsedImage { id: id, used: true }
+    }
+}
+
 pub struct RenderCache {
     /// RenderCache will invalidate
     updates_till_invalidation: u32,
     room_terrains: HashMap<RoomName, ImageId>,
+    badges: HashMap<Badge, UsedImage>,
+    /// Badge images of users, by user id, for the map view to draw on their controllers.
+    user_badges: HashMap<String, UsedImage>,
     pub image_map: ImageMap<Texture>,
+    /// Colors for players' objects, updated by the layout as relations become known.
+    pub player_colors: PlayerColors,
 }
 
 impl RenderCache {
     pub fn new() -> Self {
         RenderCache {
             room_terrains: HashMap::new(),
+            badges: HashMap::new(),
+            user_badges: HashMap::new(),
             image_map: ImageMap::new(),
+            player_colors: PlayerColors::new(),
             updates_till_invalidation: INVALIDATE_EVERY_UPDATES,
         }
     }
@@ -37,6 +59,27 @@ impl RenderCache {
             | NetworkEvent::WebsocketParseError { .. }
             | NetworkEvent::MapView { .. }
             | NetworkEvent::RoomView { .. }
+            | NetworkEvent::ConsoleOutput { .. }
+            | NetworkEvent::ConsoleCommand { .. }
+            | NetworkEvent::ConsoleHistory { .. }
+            | NetworkEvent::MapViewSnapshots { .. }
+            | NetworkEvent::UserCpu { .. }
+            | NetworkEvent::Memory { .. }
+            | NetworkEvent::MemorySet { .. }
+            | NetworkEvent::MemorySegments { .. }
+            | NetworkEvent::MemorySegmentSet { .. }
+            | NetworkEvent::MemoryWatch { .. }
+            | NetworkEvent::Branches { .. }
+            | NetworkEvent::Code { .. }
+            | NetworkEvent::CodeUploaded { .. }
+            | NetworkEvent::ActiveBranchSet { .. }
+            | NetworkEvent::ServerInfo { .. }
+            | NetworkEvent::WorldSize { .. }
+            | NetworkEvent::ServerVersion { .. }
+            | NetworkEvent::GameTime { .. }
+            | NetworkEvent::RoomStatus { .. }
+            | NetworkEvent::MapStats { .. }
+            | NetworkEvent::Users { .. }
             | NetworkEvent::ShardList { .. } => (),
             NetworkEvent::RoomTerrain { room_name, .. } => self.invalidate_terrain(room_name),
         }
@@ -99,10 +142,57 @@ impl RenderCache {
         self.room_terrains.get(&room_name).cloned()
     }
 
+    /// Gets a generated image for the given badge, or generates it if it doesn't exist yet.
+    ///
+    /// Returns `None` for custom badges, which can't be drawn.
+    pub fn get_or_generate_badge(&mut self, display: &glium::Display, badge: &Badge) -> Option<ImageId> {
+        if !badge::can_draw(badge) {
+            return None;
+        }
+
+        let RenderCache {
+            ref mut badges,
+            ref mut image_map,
+            ..
+        } = *self;
+
+        if let Some(cached) = badges.get_mut(badge) {
+            cached.used = true;
+            return Some(cached.id);
+        }
+
+        debug!("Creating new cached badge image for pattern {}", badge.pattern);
+        let new_texture =
+            Texture::new(display, badge::make_badge_texture(badge)).expect("expected creating srgb texture to suceed");
+        let id = image_map.insert(new_texture);
+        badges.insert(badge.clone(), UsedImage::new(id));
+
+        Some(id)
+    }
+
+    /// Records which badge image belongs to a user.
+    pub fn set_user_badge(&mut self, user_id: &str, image_id: ImageId) {
+        // this is called every frame, so avoid allocating a new key unless something changed.
+        match self.user_badges.get_mut(user_id) {
+            Some(cached) if cached.id == image_id => {
+                cached.used = true;
+                return;
+            }
+            _ => (),
+        }
+        self.user_badges.insert(user_id.to_owned(), UsedImage::new(image_id));
+    }
+
+    /// Gets the badge image of a user, if it's been generated.
+    pub fn get_user_badge(&self, user_id: &str) -> Option<ImageId> {
+        self.user_badges.get(user_id).map(|cached| cached.id)
+    }
+
     /// Does a check to see if we should invalidate cached images.
     ///
     /// Every 200 times this is called, all rendered images which aren't
-    /// within the two RoomNames will be removed.
+    /// within the two RoomNames will be removed, along with badges which
+    /// haven't been used since the last check.
     pub fn invalidation_check(&mut self, r1: RoomName, r2: RoomName) {
         self.updates_till_invalidation -= 1;
         if self.updates_till_invalidation == 0 {
@@ -111,14 +201,31 @@ impl RenderCache {
         }
     }
 
-    /// Removes all cached rendered images which aren't within the two RoomNames, inclusively.
+    /// Removes all cached rendered images which aren't within the two RoomNames, inclusively, and badges which
+    /// haven't been used since this was last called.
     pub fn invalidate_outside_of(&mut self, r1: RoomName, r2: RoomName) {
         let RenderCache {
             ref mut room_terrains,
+            ref mut badges,
+            ref mut user_badges,
             ref mut image_map,
             ..
         } = *self;
 
+        badges.retain(|badge, cached| {
+            if mem::replace(&mut cached.used, false) {
+                true
+            } else {
+                debug!("Trimming cached badge image for pattern {}", badge.pattern);
+                image_map.remove(cached.id);
+                false
+            }
+        });
+        // users whose badge was trimmed would otherwise point at a removed image.
+        user_badges.retain(|_, cached| {
+            mem::replace(&mut cached.used, false) && badges.values().any(|badge| badge.id == cached.id)
+        });
+
         let min_x = r1.x_coord.min(r1.x_coord);
         let max_x = r1.x_coord.max(r2.x_coord);
         let min_y = r1.y_coord.min(r2.y_coord);
@@ -1,5 +1,7 @@
 use std::ops::{Generator, GeneratorState};
 
+use super::constants::OVERVIEW_ROOM_SIZE;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct MapViewOffset {
     pub(super) x_offset: f64,
@@ -16,6 +18,12 @@ impl MapViewOffset {
             room_size: size,
         }
     }
+
+    /// Whether rooms are small enough on screen that only their owners are shown, rather than map views.
+    #[inline(always)]
+    pub fn is_overview(&self) -> bool {
+        self.room_size < OVERVIEW_ROOM_SIZE
+    }
 }
 
 pub(super) struct IterAdapter<G>(pub(super) G);
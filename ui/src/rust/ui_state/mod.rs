@@ -1,11 +1,43 @@
+use std::collections::HashSet;
+
 use {conrod, screeps_api, time};
 use NetworkHandler;
+use relations::Relations;
 use map_view_utils::{bound_zoom, zoom_multiplier_from_factor, ZOOM_MODIFIER};
 
 #[derive(Debug, PartialEq)]
 pub enum Event {
     LeftMenuOpened,
     LeftMenuClosed,
+    ConsoleOpened,
+    ConsoleClosed,
+    ConsoleShardFilter(Option<String>),
+    ConsoleInput(String),
+    ConsoleHistorySelected {
+        /// Index into the console history, or None for a new command.
+        position: Option<usize>,
+        text: String,
+    },
+    ConsoleSubmitted,
+    ToolsOpened,
+    ToolsClosed,
+    ToolsTab(ToolsTab),
+    MemoryPathInput(String),
+    /// Shows the memory at the path currently typed in.
+    MemoryPathLoaded,
+    /// Expands or collapses the value at a JSON pointer in the memory tree.
+    MemoryTreeToggle(String),
+    SegmentSelected(u8),
+    /// Switches between showing segments as plain text (false) and as pretty-printed JSON (true).
+    SegmentShowJson(bool),
+    SegmentEdited(String),
+    SegmentSaved,
+    WatchOpened,
+    WatchClosed,
+    WatchInput(String),
+    WatchAdded(String),
+    WatchRemoved(String),
+    WatchDocked(WatchDock),
     SwitchShard(Option<String>),
     LoginUsername(String),
     LoginPassword(String),
@@ -47,6 +79,8 @@ pub struct LoginScreenState {
     pub username: String,
     pub password: String,
     pub server: String,
+    /// When `server` was last edited, so its version is only looked up once typing stops.
+    pub server_edited: Option<time::Timespec>,
     pub shard: String,
 }
 
@@ -57,6 +91,7 @@ impl Default for LoginScreenState {
             username: String::new(),
             password: String::new(),
             server: "https://screeps.com".to_owned(),
+            server_edited: None,
             shard: "shard0".to_owned(),
         }
     }
@@ -77,6 +112,11 @@ pub struct MapScreenState {
     pub shard: Option<String>,
     pub map_scroll: ScrollState,
     pub panels: PanelStates,
+    pub console: ConsoleState,
+    pub tools: ToolsState,
+    pub watch: WatchState,
+    /// Relations with other players, loaded from the relations file when the map is first shown.
+    pub relations: Relations,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -88,6 +128,80 @@ pub enum MenuState {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct PanelStates {
     pub left: MenuState,
+    pub console: MenuState,
+    pub tools: MenuState,
+    pub watch: MenuState,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ConsoleState {
+    /// Only show console output from this shard, if set. Commands are also sent to this shard.
+    pub shard_filter: Option<String>,
+    /// Command currently being typed.
+    pub input: String,
+    /// Position in the command history currently shown in the input, if any.
+    pub history_position: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ToolsState {
+    pub tab: ToolsTab,
+    pub memory: MemoryToolState,
+    pub segments: SegmentToolState,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ToolsTab {
+    Memory,
+    Segments,
+}
+
+impl Default for ToolsTab {
+    fn default() -> Self {
+        ToolsTab::Memory
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct MemoryToolState {
+    /// Dot-separated memory path currently being typed.
+    pub path_input: String,
+    /// Memory path currently shown, empty for all of `Memory`.
+    pub loaded_path: String,
+    /// JSON pointers of the objects and arrays expanded in the tree.
+    pub expanded: HashSet<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SegmentToolState {
+    /// Segment currently shown.
+    pub selected: u8,
+    /// Whether to pretty-print segments which contain JSON.
+    pub show_json: bool,
+    /// Edited text of the selected segment, if it's been changed since it was loaded.
+    pub edited: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct WatchState {
+    /// Memory paths being watched.
+    pub paths: Vec<String>,
+    /// Path currently being typed.
+    pub input: String,
+    pub dock: WatchDock,
+}
+
+/// Side of the window the watch panel is docked to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WatchDock {
+    Left,
+    Right,
+}
+
+impl Default for WatchDock {
+    fn default() -> Self {
+        WatchDock::Right
+    }
 }
 
 impl Default for MenuState {
@@ -118,6 +232,8 @@ pub struct MapZoomEvent {
 pub struct MapClickEvent {
     /// If the screen was clicked, the relative (x, y) that it was clicked at.
     pub clicked: (f64, f64),
+    /// Whether the clicked room should be pinned or unpinned rather than selected.
+    pub pin: bool,
 }
 
 #[derive(Debug)]
@@ -130,6 +246,8 @@ pub struct ScrollState {
     pub zoom_factor: f64,
     /// The room name currently selected.
     pub selected_room: Option<screeps_api::RoomName>,
+    /// Rooms pinned to be shown in full detail along with the selected room, oldest first.
+    pub pinned_rooms: Vec<screeps_api::RoomName>,
 }
 
 impl MapScreenState {
@@ -137,8 +255,12 @@ impl MapScreenState {
         // TODO: saved position? or use API to get position?
         MapScreenState {
             panels: PanelStates::default(),
+            console: ConsoleState::default(),
+            tools: ToolsState::default(),
+            watch: WatchState::default(),
             shard: None,
             map_scroll: ScrollState::default(),
+            relations: Relations::load(),
         }
     }
 }
@@ -176,12 +298,108 @@ impl State {
                 }
                 _ => (),
             },
+            Event::ConsoleOpened => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("console opened");
+                state.panels.console = MenuState::Open;
+            },
+            Event::ConsoleClosed => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("console closed");
+                state.panels.console = MenuState::Closed;
+            },
+            Event::ConsoleShardFilter(shard) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("console shard filter changed");
+                state.console.shard_filter = shard;
+            },
+            Event::ConsoleInput(text) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                state.console.input = text;
+                state.console.history_position = None;
+            },
+            Event::ConsoleHistorySelected { position, text } => {
+                if let ScreenState::Map(ref mut state) = self.screen_state {
+                    debug!("console history position changed");
+                    state.console.input = text;
+                    state.console.history_position = position;
+                }
+            }
+            Event::ConsoleSubmitted => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("console command submitted");
+                state.console.input.clear();
+                state.console.history_position = None;
+            },
+            Event::ToolsOpened => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("tools opened");
+                state.panels.tools = MenuState::Open;
+            },
+            Event::ToolsClosed => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("tools closed");
+                state.panels.tools = MenuState::Closed;
+            },
+            Event::ToolsTab(tab) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("tools tab changed");
+                state.tools.tab = tab;
+            },
+            Event::MemoryPathInput(text) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                state.tools.memory.path_input = text;
+            },
+            Event::MemoryPathLoaded => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("memory path loaded");
+                let memory = &mut state.tools.memory;
+                memory.loaded_path = memory.path_input.trim().trim_matches('.').to_owned();
+                memory.expanded.clear();
+            },
+            Event::MemoryTreeToggle(pointer) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                let expanded = &mut state.tools.memory.expanded;
+                if !expanded.remove(&pointer) {
+                    expanded.insert(pointer);
+                }
+            },
+            Event::SegmentSelected(segment) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("memory segment {} selected", segment);
+                state.tools.segments.selected = segment;
+                state.tools.segments.edited = None;
+            },
+            Event::SegmentShowJson(show_json) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                state.tools.segments.show_json = show_json;
+                state.tools.segments.edited = None;
+            },
+            Event::SegmentEdited(text) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                state.tools.segments.edited = Some(text);
+            },
+            Event::SegmentSaved => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("memory segment saved");
+                state.tools.segments.edited = None;
+            },
+            Event::WatchOpened => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("watch panel opened");
+                state.panels.watch = MenuState::Open;
+            },
+            Event::WatchClosed => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("watch panel closed");
+                state.panels.watch = MenuState::Closed;
+            },
+            Event::WatchInput(text) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                state.watch.input = text;
+            },
+            Event::WatchAdded(path) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("watching {}", path);
+                state.watch.input.clear();
+                state.watch.paths.push(path);
+            },
+            Event::WatchRemoved(path) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("no longer watching {}", path);
+                state.watch.paths.retain(|watched| *watched != path);
+            },
+            Event::WatchDocked(dock) => if let ScreenState::Map(ref mut state) = self.screen_state {
+                debug!("watch panel docked to {:?}", dock);
+                state.watch.dock = dock;
+            },
             // Event::ShardButton(new_shard) => if let ScreenState::Map(ref mut state) = self.screen_state {
             //     state.shard = new_shard;
             // },
             Event::LoginServer(new_server) => if let ScreenState::Login(ref mut state) = self.screen_state {
                 debug!("login server changed");
                 state.server = new_server;
+                state.server_edited = Some(time::get_time());
             },
             Event::LoginShard(new_shard) => if let ScreenState::Login(ref mut state) = self.screen_state {
                 debug!("login shard changed");
@@ -238,6 +456,7 @@ impl Default for ScrollState {
             scroll_y: 0.0,
             zoom_factor: 1.0,
             selected_room: None,
+            pinned_rooms: Vec::new(),
         }
     }
 }
@@ -343,6 +562,24 @@ impl ScrollState {
 
         info!("Clicked {}", room_clicked);
 
-        self.selected_room = Some(room_clicked);
+        if update.pin {
+            match self.pinned_rooms.iter().position(|&room| room == room_clicked) {
+                Some(idx) => {
+                    self.pinned_rooms.remove(idx);
+                }
+                None => self.pinned_rooms.push(room_clicked),
+            }
+        } else {
+            self.selected_room = Some(room_clicked);
+        }
+    }
+
+    /// Rooms to show in full detail: the selected room, then pinned rooms.
+    pub fn detail_rooms(&self) -> Vec<screeps_api::RoomName> {
+        self.selected_room
+            .iter()
+            .chain(&self.pinned_rooms)
+            .cloned()
+            .collect()
     }
 }
@@ -0,0 +1,292 @@
+//! A collapsible, editable view of a JSON value.
+//!
+//! Each value in the tree is identified by its JSON pointer, such as `/creeps/John/role`.
+use std::collections::HashSet;
+
+use conrod::{color, Borderable, Color, Colorable, Labelable, Positionable, Sizeable, Widget};
+use conrod::widget;
+use conrod::position::Scalar;
+
+use serde_json::Value;
+
+use widgets::text_box::{self, TextBox};
+
+/// Number of characters of a primitive value shown before it's cut off.
+const MAX_PREVIEW_LENGTH: usize = 60;
+/// Spaces to indent each level of the tree by.
+const INDENT: &str = "    ";
+
+/// A widget showing a JSON value as a list of rows, one for each value in expanded objects and arrays.
+///
+/// Clicking on an object or array toggles whether it's expanded, and clicking a primitive value edits it.
+#[derive(WidgetCommon)]
+pub struct JsonTree<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    value: &'a Value,
+    expanded: &'a HashSet<String>,
+    style: Style,
+}
+
+/// Unique graphical styling for the JsonTree.
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Color of the text.
+    #[conrod(default = "theme.label_color")]
+    pub label_color: Option<Color>,
+    /// Height of each row.
+    #[conrod(default = "20.0")]
+    pub row_height: Option<Scalar>,
+}
+
+widget_ids! {
+    struct Ids {
+        rows,
+    }
+}
+
+/// The `State` of the `JsonTree` widget that will be cached within the `Ui`.
+pub struct State {
+    ids: Ids,
+    /// Pointer to the value currently being edited, and the text typed so far.
+    editing: Option<(String, String)>,
+}
+
+/// Events produced by the `JsonTree`.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// The object or array at this pointer was clicked, and should be expanded or collapsed.
+    Toggle(String),
+    /// The value at this pointer was edited.
+    ///
+    /// Strings are edited as plain text and stay strings. Other values are edited as JSON, and aren't saved until
+    /// the text is valid JSON.
+    Edit { pointer: String, value: Value },
+}
+
+/// Escapes a key so that it can be used as a JSON pointer segment.
+pub fn escape_pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Splits a JSON pointer into its unescaped segments.
+pub fn pointer_segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+struct Row {
+    pointer: String,
+    label: String,
+    /// The primitive value's text for editing, or None for objects and arrays.
+    edit_text: Option<String>,
+    /// Whether the value is a string, edited without quotes.
+    is_string: bool,
+}
+
+fn preview(value: &Value) -> String {
+    let mut text = value.to_string();
+    if text.chars().count() > MAX_PREVIEW_LENGTH {
+        text = text.chars().take(MAX_PREVIEW_LENGTH).collect();
+        text.push_str("...");
+    }
+    text
+}
+
+fn push_rows(rows: &mut Vec<Row>, expanded: &HashSet<String>, value: &Value, pointer: &str, depth: usize) {
+    let children: Vec<(String, &Value)> = match *value {
+        Value::Object(ref map) => map.iter().map(|(key, value)| (key.clone(), value)).collect(),
+        Value::Array(ref list) => list.iter()
+            .enumerate()
+            .map(|(idx, value)| (idx.to_string(), value))
+            .collect(),
+        _ => return,
+    };
+
+    for (key, child) in children {
+        let child_pointer = format!("{}/{}", pointer, escape_pointer_segment(&key));
+        let indent = INDENT.repeat(depth);
+        let open = expanded.contains(&child_pointer);
+
+        let row = match *child {
+            Value::Object(ref map) => Row {
+                label: format!("{}{} {}: {{{} keys}}", indent, if open { "-" } else { "+" }, key, map.len()),
+                pointer: child_pointer.clone(),
+                edit_text: None,
+                is_string: false,
+            },
+            Value::Array(ref list) => Row {
+                label: format!("{}{} {}: [{} items]", indent, if open { "-" } else { "+" }, key, list.len()),
+                pointer: child_pointer.clone(),
+                edit_text: None,
+                is_string: false,
+            },
+            ref primitive => Row {
+                label: format!("{}  {}: {}", indent, key, preview(primitive)),
+                pointer: child_pointer.clone(),
+                edit_text: Some(match *primitive {
+                    Value::String(ref s) => s.clone(),
+                    ref other => other.to_string(),
+                }),
+                is_string: primitive.is_string(),
+            },
+        };
+        rows.push(row);
+
+        if open {
+            push_rows(rows, expanded, child, &child_pointer, depth + 1);
+        }
+    }
+}
+
+impl<'a> JsonTree<'a> {
+    /// Construct a JsonTree showing the children of `value`, expanding those whose pointers are in `expanded`.
+    pub fn new(value: &'a Value, expanded: &'a HashSet<String>) -> Self {
+        JsonTree {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            value: value,
+            expanded: expanded,
+        }
+    }
+
+    builder_methods!{
+        pub label_color { style.label_color = Some(Color) }
+        pub row_height { style.row_height = Some(Scalar) }
+    }
+}
+
+impl<'a> Widget for JsonTree<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Vec<Event>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+            editing: None,
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style.clone()
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            style,
+            ui,
+            ..
+        } = args;
+        let JsonTree { value, expanded, .. } = self;
+
+        let mut events = Vec::new();
+
+        let mut rows = Vec::new();
+        match *value {
+            Value::Object(_) | Value::Array(_) => push_rows(&mut rows, expanded, value, "", 0),
+            ref primitive => rows.push(Row {
+                label: preview(primitive),
+                pointer: String::new(),
+                edit_text: None,
+                is_string: false,
+            }),
+        }
+
+        let label_color = style.label_color(ui.theme());
+        let row_height = style.row_height(ui.theme());
+        let font_size = ui.theme.font_size_small;
+
+        let (mut items, scrollbar) = widget::List::flow_down(rows.len())
+            .item_size(row_height)
+            .scrollbar_on_top()
+            .wh(rect.dim())
+            .xy(rect.xy())
+            .parent(id)
+            .set(state.ids.rows, ui);
+
+        let mut new_editing = state.editing.clone();
+
+        while let Some(item) = items.next(ui) {
+            let row = &rows[item.i];
+
+            let editing_text = match state.editing {
+                Some((ref pointer, ref text)) if *pointer == row.pointer => Some(text.clone()),
+                _ => None,
+            };
+
+            match editing_text {
+                Some(text) => {
+                    let text_box = TextBox::new(&text)
+                        .font_size(font_size)
+                        .left_justify()
+                        .pad_text(2.0);
+                    // text typed this frame, for when enter is pressed right after typing.
+                    let mut latest = text.clone();
+                    for event in item.set(text_box, ui) {
+                        match event {
+                            text_box::Event::Update(text) => {
+                                latest = text.clone();
+                                new_editing = Some((row.pointer.clone(), text));
+                            }
+                            text_box::Event::Enter => {
+                                let value = if row.is_string {
+                                    Ok(Value::String(latest.clone()))
+                                } else {
+                                    ::serde_json::from_str(&latest)
+                                };
+                                match value {
+                                    Ok(value) => {
+                                        events.push(Event::Edit {
+                                            pointer: row.pointer.clone(),
+                                            value: value,
+                                        });
+                                        new_editing = None;
+                                    }
+                                    Err(e) => warn!("not saving {}: invalid JSON: {}", row.pointer, e),
+                                }
+                            }
+                            text_box::Event::Up | text_box::Event::Down => {}
+                        }
+                    }
+                }
+                None => {
+                    let button = widget::Button::new()
+                        .color(color::TRANSPARENT)
+                        .border(0.0)
+                        .label(&row.label)
+                        .label_font_size(font_size)
+                        .label_color(label_color)
+                        .left_justify_label();
+                    if item.set(button, ui).was_clicked() {
+                        match row.edit_text {
+                            Some(ref text) => new_editing = Some((row.pointer.clone(), text.clone())),
+                            None => {
+                                new_editing = None;
+                                if !row.pointer.is_empty() {
+                                    events.push(Event::Toggle(row.pointer.clone()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(scrollbar) = scrollbar {
+            scrollbar.set(ui);
+        }
+
+        if new_editing != state.editing {
+            state.update(|state| state.editing = new_editing);
+        }
+
+        events
+    }
+}
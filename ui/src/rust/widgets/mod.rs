@@ -1,3 +1,5 @@
 //! Conrod widgets
 mod text;
+pub mod sparkline;
+pub mod json_tree;
 pub use self::text::{text_box, text_edit};
@@ -0,0 +1,115 @@
+//! A small line graph of recent values, without any axes or labels.
+use conrod::{Color, Colorable, Positionable, Sizeable, Widget};
+use conrod::widget;
+use conrod::position::Scalar;
+
+/// A widget drawing a series of values as a single line, scaled to fit its bounding rectangle.
+#[derive(WidgetCommon)]
+pub struct Sparkline<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    values: &'a [f64],
+    max: Option<f64>,
+    style: Style,
+}
+
+/// Unique graphical styling for the Sparkline.
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Color of the line.
+    #[conrod(default = "theme.shape_color")]
+    pub color: Option<Color>,
+    /// Thickness of the line.
+    #[conrod(default = "1.0")]
+    pub thickness: Option<Scalar>,
+}
+
+widget_ids! {
+    struct Ids {
+        line,
+    }
+}
+
+/// The `State` of the `Sparkline` widget that will be cached within the `Ui`.
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a> Sparkline<'a> {
+    /// Construct a Sparkline widget, with values ordered from left to right.
+    pub fn new(values: &'a [f64]) -> Self {
+        Sparkline {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            values: values,
+            max: None,
+        }
+    }
+
+    /// Sets the value drawn at the top of the graph. Defaults to the largest value given.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    builder_methods!{
+        pub thickness { style.thickness = Some(Scalar) }
+    }
+}
+
+impl<'a> Widget for Sparkline<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style.clone()
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            style,
+            ui,
+            ..
+        } = args;
+        let Sparkline { values, max, .. } = self;
+
+        // a line needs at least two points.
+        if values.len() < 2 {
+            return;
+        }
+
+        let max = max.unwrap_or_else(|| values.iter().cloned().fold(0.0, f64::max));
+        let max = if max > 0.0 { max } else { 1.0 };
+
+        let step = rect.w() / (values.len() - 1) as Scalar;
+        let points = values.iter().enumerate().map(|(idx, &value)| {
+            [
+                rect.left() + step * idx as Scalar,
+                rect.bottom() + rect.h() * (value / max).max(0.0).min(1.0),
+            ]
+        });
+
+        widget::PointPath::new(points)
+            .wh(rect.dim())
+            .xy(rect.xy())
+            .color(style.color(ui.theme()))
+            .thickness(style.thickness(ui.theme()))
+            .graphics_for(id)
+            .parent(id)
+            .set(state.ids.line, ui);
+    }
+}
+
+impl<'a> Colorable for Sparkline<'a> {
+    builder_method!(color { style.color = Some(Color) });
+}
@@ -115,6 +115,10 @@ pub mod text_box {
         Update(String),
         /// The `Return` or `Enter` key was pressed.
         Enter,
+        /// The `Up` arrow key was pressed.
+        Up,
+        /// The `Down` arrow key was pressed.
+        Down,
     }
 
     impl<'a> Widget for TextBox<'a> {
@@ -196,6 +200,8 @@ pub mod text_box {
                     event::Widget::Press(press) => match press.button {
                         event::Button::Keyboard(key) => match key {
                             input::Key::Return => events.push(Event::Enter),
+                            input::Key::Up => events.push(Event::Up),
+                            input::Key::Down => events.push(Event::Down),
                             _ => (),
                         },
                         _ => (),